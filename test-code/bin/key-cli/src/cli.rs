@@ -0,0 +1,67 @@
+use clap::{Parser, Subcommand};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "key-cli",
+    about = "Manage secp256k1 keys and co-sign multisig transactions",
+    version
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Generate a new secret key
+    Keygen {
+        /// Output file for the hex encoded secret key
+        #[arg(long)]
+        out: String,
+    },
+
+    /// Print the compressed public key of a secret key
+    Pubkey {
+        /// Hex encoded secret key file
+        #[arg(long)]
+        key: String,
+    },
+
+    /// Print the address of an M-of-N multisig account
+    MultisigAddress {
+        /// Number of signatures required
+        #[arg(long)]
+        threshold: u8,
+
+        /// Hex encoded compressed public keys of the account
+        #[arg(long = "pubkey", required = true)]
+        pubkeys: Vec<String>,
+    },
+
+    /// Add a signature to a partially signed transaction file
+    Cosign {
+        /// Hex encoded SCALE transaction file, updated in place unless `--out` is set
+        #[arg(long)]
+        tx: String,
+
+        /// Hex encoded secret key file
+        #[arg(long)]
+        key: String,
+
+        /// Threshold of the account, only needed for the first signature
+        #[arg(long)]
+        threshold: Option<u8>,
+
+        /// Public keys of the account, only needed for the first signature
+        #[arg(long = "pubkey")]
+        pubkeys: Vec<String>,
+
+        /// Current nonce of the account, only needed for the first signature
+        #[arg(long)]
+        nonce: Option<u64>,
+
+        /// Output file for the signed transaction
+        #[arg(long)]
+        out: Option<String>,
+    },
+}
@@ -0,0 +1,70 @@
+use std::fmt;
+
+use rm_reth_types::{multisig::MultisigError, tx::error::TransactionError};
+
+#[derive(Debug)]
+pub enum KeyCliError {
+    Io(std::io::Error),
+    Hex(hex::FromHexError),
+    Codec(parity_scale_codec::Error),
+    Transaction(TransactionError),
+    Multisig(MultisigError),
+    InvalidKey(String),
+    MissingAccount,
+    ConflictingAccount,
+}
+
+impl fmt::Display for KeyCliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyCliError::Io(e) => write!(f, "IO error: {}", e),
+            KeyCliError::Hex(e) => write!(f, "Hex decode error: {}", e),
+            KeyCliError::Codec(e) => write!(f, "SCALE decode error: {}", e),
+            KeyCliError::Transaction(e) => write!(f, "Transaction error: {}", e),
+            KeyCliError::Multisig(e) => write!(f, "Multisig error: {}", e),
+            KeyCliError::InvalidKey(key) => write!(f, "Invalid key: {}", key),
+            KeyCliError::MissingAccount => write!(
+                f,
+                "Transaction has no witness yet, pass --threshold, --pubkey and --nonce"
+            ),
+            KeyCliError::ConflictingAccount => write!(
+                f,
+                "Transaction already has a witness, drop --threshold, --pubkey and --nonce"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for KeyCliError {}
+
+impl From<std::io::Error> for KeyCliError {
+    fn from(err: std::io::Error) -> Self {
+        KeyCliError::Io(err)
+    }
+}
+
+impl From<hex::FromHexError> for KeyCliError {
+    fn from(err: hex::FromHexError) -> Self {
+        KeyCliError::Hex(err)
+    }
+}
+
+impl From<parity_scale_codec::Error> for KeyCliError {
+    fn from(err: parity_scale_codec::Error) -> Self {
+        KeyCliError::Codec(err)
+    }
+}
+
+impl From<TransactionError> for KeyCliError {
+    fn from(err: TransactionError) -> Self {
+        KeyCliError::Transaction(err)
+    }
+}
+
+impl From<MultisigError> for KeyCliError {
+    fn from(err: MultisigError) -> Self {
+        KeyCliError::Multisig(err)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, KeyCliError>;
@@ -0,0 +1,106 @@
+use std::{fs, fs::OpenOptions, io::Write};
+
+use k256::ecdsa::SigningKey;
+use parity_scale_codec::{Decode, Encode};
+use rand::RngCore;
+use rm_reth_types::{
+    bytes::FixedBytes,
+    multisig::{MultisigAccount, MultisigWitness, PublicKey},
+    tx::transaction::Transaction,
+};
+
+use crate::error::{KeyCliError, Result};
+
+pub fn generate() -> SigningKey {
+    let mut rng = rand::rng();
+    let mut secret = [0u8; 32];
+
+    // out of range scalars are astronomically rare, just draw again
+    loop {
+        rng.fill_bytes(&mut secret);
+
+        if let Ok(key) = SigningKey::from_bytes(&secret.into()) {
+            return key;
+        }
+    }
+}
+
+pub fn read_signing_key(path: &str) -> Result<SigningKey> {
+    let bytes = hex::decode(fs::read_to_string(path)?.trim())?;
+
+    SigningKey::from_slice(&bytes).map_err(|_| KeyCliError::InvalidKey(path.into()))
+}
+
+/// Writes `key` to a new file only its owner can read, refusing to overwrite an
+/// existing one.
+pub fn write_signing_key(path: &str, key: &SigningKey) -> Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    options
+        .open(path)?
+        .write_all(hex::encode(key.to_bytes()).as_bytes())?;
+    Ok(())
+}
+
+pub fn parse_public_key(value: &str) -> Result<PublicKey> {
+    let bytes = hex::decode(value.trim_start_matches("0x"))?;
+    let bytes: [u8; 33] = bytes
+        .try_into()
+        .map_err(|_| KeyCliError::InvalidKey(value.into()))?;
+
+    let key = PublicKey(FixedBytes(bytes));
+    key.verifying_key()
+        .ok_or_else(|| KeyCliError::InvalidKey(value.into()))?;
+
+    Ok(key)
+}
+
+pub fn parse_account(threshold: u8, pubkeys: &[String]) -> Result<MultisigAccount> {
+    let keys = pubkeys
+        .iter()
+        .map(|key| parse_public_key(key))
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(MultisigAccount::new(threshold, keys)?)
+}
+
+pub fn read_transaction(path: &str) -> Result<Transaction> {
+    let bytes = hex::decode(fs::read_to_string(path)?.trim())?;
+
+    Ok(Transaction::decode(&mut bytes.as_slice())?)
+}
+
+pub fn write_transaction(path: &str, tx: &Transaction) -> Result<()> {
+    fs::write(path, hex::encode(tx.encode()))?;
+    Ok(())
+}
+
+/// Adds the signature of `key` to `tx`. The first signature starts the witness
+/// from the account and nonce, later ones take them from `tx` and refuse new
+/// ones.
+pub fn cosign(
+    tx: Transaction,
+    key: &SigningKey,
+    threshold: Option<u8>,
+    pubkeys: &[String],
+    nonce: Option<u64>,
+) -> Result<Transaction> {
+    let account_given = threshold.is_some() || !pubkeys.is_empty() || nonce.is_some();
+
+    let mut witness = match (tx.witness()?, threshold, nonce) {
+        (Some(_), ..) if account_given => return Err(KeyCliError::ConflictingAccount),
+        (Some(witness), ..) => witness,
+        (None, Some(threshold), Some(nonce)) => {
+            MultisigWitness::new(parse_account(threshold, pubkeys)?, nonce)
+        }
+        (None, ..) => return Err(KeyCliError::MissingAccount),
+    };
+
+    witness.cosign(key, &tx.signing_hash(witness.nonce)?)?;
+
+    Ok(tx.with_witness(witness)?)
+}
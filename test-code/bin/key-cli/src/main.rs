@@ -0,0 +1,60 @@
+mod cli;
+mod error;
+mod keys;
+
+use clap::Parser;
+use cli::{Cli, Command};
+use error::Result;
+use rm_reth_types::multisig::PublicKey;
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    match cli.command {
+        Command::Keygen { out } => {
+            let key = keys::generate();
+            keys::write_signing_key(&out, &key)?;
+
+            println!("Secret key written to {}", out);
+            println!("Public key: {}", PublicKey::from_signing_key(&key).0);
+        }
+        Command::Pubkey { key } => {
+            let key = keys::read_signing_key(&key)?;
+            println!("{}", PublicKey::from_signing_key(&key).0);
+        }
+        Command::MultisigAddress { threshold, pubkeys } => {
+            let account = keys::parse_account(threshold, &pubkeys)?;
+            println!("{}", account.address());
+        }
+        Command::Cosign {
+            tx,
+            key,
+            threshold,
+            pubkeys,
+            nonce,
+            out,
+        } => {
+            let key = keys::read_signing_key(&key)?;
+            let signed = keys::cosign(
+                keys::read_transaction(&tx)?,
+                &key,
+                threshold,
+                &pubkeys,
+                nonce,
+            )?;
+
+            let out = out.unwrap_or(tx);
+            keys::write_transaction(&out, &signed)?;
+
+            let witness = signed.witness()?.expect("witness was just added");
+            println!(
+                "Signed {} ({}/{} signatures)",
+                out,
+                witness.signatures.len(),
+                witness.account.threshold
+            );
+        }
+    }
+
+    Ok(())
+}
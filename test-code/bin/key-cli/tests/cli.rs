@@ -0,0 +1,129 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+use k256::ecdsa::SigningKey;
+use parity_scale_codec::{Decode, Encode};
+use rm_reth_types::{
+    Address,
+    int::Uint256,
+    multisig::{MultisigAccount, PublicKey},
+    tx::transaction::Transaction,
+};
+
+fn workdir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("key-cli-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn key_cli(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_key-cli"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn keygen(path: &Path) -> PublicKey {
+    let output = key_cli(&["keygen", "--out", path.to_str().unwrap()]);
+    assert!(output.status.success());
+
+    let bytes = hex::decode(fs::read_to_string(path).unwrap()).unwrap();
+    PublicKey::from_signing_key(&SigningKey::from_slice(&bytes).unwrap())
+}
+
+fn read_tx(path: &Path) -> Transaction {
+    let bytes = hex::decode(fs::read_to_string(path).unwrap()).unwrap();
+    Transaction::decode(&mut bytes.as_slice()).unwrap()
+}
+
+#[test]
+fn keygen_writes_a_private_new_file() {
+    let dir = workdir("keygen");
+    let path = dir.join("a.key");
+
+    keygen(&path);
+    let written = fs::read_to_string(&path).unwrap();
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    // an existing key is never overwritten
+    let output = key_cli(&["keygen", "--out", path.to_str().unwrap()]);
+    assert!(!output.status.success());
+    assert_eq!(fs::read_to_string(&path).unwrap(), written);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn cosign_takes_the_account_only_for_the_first_signature() {
+    let dir = workdir("cosign");
+    let (a, b) = (dir.join("a.key"), dir.join("b.key"));
+    let keys = [keygen(&a), keygen(&b)];
+
+    let account = MultisigAccount::new(2, keys.to_vec()).unwrap();
+    let tx = Transaction::new(
+        account.address(),
+        Address::default(),
+        Uint256::from(10),
+        vec![],
+    );
+
+    let tx_path = dir.join("tx.hex");
+    fs::write(&tx_path, hex::encode(tx.encode())).unwrap();
+
+    let pubkeys: Vec<_> = keys.iter().map(|key| key.0.to_string()).collect();
+    let cosign = |key: &Path, extra: &[&str]| {
+        let mut args = vec![
+            "cosign",
+            "--tx",
+            tx_path.to_str().unwrap(),
+            "--key",
+            key.to_str().unwrap(),
+        ];
+        args.extend_from_slice(extra);
+        key_cli(&args)
+    };
+    let account_args = [
+        "--threshold",
+        "2",
+        "--pubkey",
+        &pubkeys[0],
+        "--pubkey",
+        &pubkeys[1],
+    ];
+
+    // the first signature needs the nonce as well as the account
+    let output = cosign(&a, &account_args);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("MissingAccount"));
+
+    let output = cosign(&a, &[&account_args[..], &["--nonce", "7"]].concat());
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("(1/2 signatures)"));
+
+    // later signatures refuse an account the witness already fixes
+    let output = cosign(&b, &["--nonce", "8"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("ConflictingAccount"));
+
+    let output = cosign(&b, &[]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("(2/2 signatures)"));
+
+    let witness = read_tx(&tx_path).witness().unwrap().unwrap();
+    assert_eq!(witness.nonce, 7);
+    assert_eq!(witness.account, account);
+    assert_eq!(witness.signatures.len(), 2);
+
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,18 @@
+use clap::Parser;
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "replay",
+    about = "Replay a dispatcher command journal into a fresh node",
+    version
+)]
+pub struct Cli {
+    /// Path to the journal file
+    #[arg(long)]
+    pub journal: String,
+
+    /// Path to a new redb database file to keep the replayed chain in,
+    /// in memory if omitted
+    #[arg(long)]
+    pub db: Option<String>,
+}
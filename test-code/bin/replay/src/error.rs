@@ -0,0 +1,52 @@
+use std::fmt;
+
+use dispatcher::journal::JournalError;
+use node::error::NodeError;
+use storage::error::StorageError;
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Journal(JournalError),
+    Storage(StorageError),
+    Node(NodeError),
+    DatabaseExists(String),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Journal(e) => write!(f, "Journal error: {}", e),
+            ReplayError::Storage(e) => write!(f, "Storage error: {}", e),
+            ReplayError::Node(e) => write!(f, "Node error: {}", e),
+            ReplayError::DatabaseExists(path) => {
+                write!(
+                    f,
+                    "Database {} already exists, replay needs a fresh one",
+                    path
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<JournalError> for ReplayError {
+    fn from(err: JournalError) -> Self {
+        ReplayError::Journal(err)
+    }
+}
+
+impl From<StorageError> for ReplayError {
+    fn from(err: StorageError) -> Self {
+        ReplayError::Storage(err)
+    }
+}
+
+impl From<NodeError> for ReplayError {
+    fn from(err: NodeError) -> Self {
+        ReplayError::Node(err)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, ReplayError>;
@@ -0,0 +1,49 @@
+mod cli;
+mod error;
+
+use std::{path::Path, sync::Arc};
+
+use clap::Parser;
+use cli::Cli;
+use dispatcher::{
+    journal::{read_journal, replay},
+    service::Dispatcher,
+};
+use error::{ReplayError, Result};
+use node::manager::NodeManager;
+use storage::StorageManager;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    let entries = read_journal(&cli.journal)?;
+
+    let storage = match &cli.db {
+        Some(db) if Path::new(db).exists() => {
+            return Err(ReplayError::DatabaseExists(db.clone()));
+        }
+        Some(db) => StorageManager::create_or_open(db)?,
+        None => StorageManager::in_memory()?,
+    };
+
+    let node = Arc::new(NodeManager::genesis_with_storage(storage)?);
+
+    println!("Replaying {} commands from {}", entries.len(), cli.journal);
+    let report = replay(entries, Dispatcher::new(node.clone())).await;
+
+    println!(
+        "Applied {}, failed {}, skipped {}",
+        report.applied, report.failed, report.skipped
+    );
+    if report.skipped > 0 {
+        println!("Blocks the block producer built are not journaled and were not replayed");
+    }
+    println!("Height: {}", node.height());
+
+    if let Some(head) = node.get_latest_block()? {
+        println!("Head: {}", head.get_hash());
+    }
+
+    Ok(())
+}
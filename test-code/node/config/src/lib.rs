@@ -7,6 +7,115 @@ pub struct Config {
     pub single_tx_max_size: u64,
     pub tx_max_size: u64,
     pub min_tx_threshold: u64,
+    pub consensus: ConsensusConfig,
+    pub staking: StakingConfig,
+    pub finality: FinalityConfig,
+    pub producer: ProducerConfig,
+    pub execution: ExecutionConfig,
+}
+
+/// Consensus engine the node seals and validates blocks with.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConsensusConfig {
+    /// Proof of work requiring `difficulty` leading zero bits in the block hash.
+    Pow { difficulty: u32 },
+    /// Accepts every block, for local development.
+    Dev,
+    /// Proof of authority over hex encoded compressed public keys, signing with
+    /// the hex encoded secret key `signer` if this node is a validator.
+    Poa {
+        validators: Vec<String>,
+        signer: Option<String>,
+    },
+    /// Tendermint style BFT among hex encoded compressed public keys, with blocks
+    /// final once a commit certificate is stored. This node takes part in the
+    /// rounds with the hex encoded secret key `signer` if it is a validator. Step
+    /// timeouts start at `timeout_ms` and grow by half of it every round.
+    Bft {
+        validators: Vec<String>,
+        signer: Option<String>,
+        timeout_ms: u64,
+    },
+}
+
+/// Rules of the validator registry.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StakingConfig {
+    /// Blocks unstaked tokens stay locked before they can be withdrawn.
+    pub unbonding_period: u64,
+    /// Blocks per epoch, the validator set only changes between epochs.
+    pub epoch_length: u64,
+    /// Size of the validator set, filled with the largest stakes.
+    pub max_validators: usize,
+    /// Smallest stake that can make it into the validator set.
+    pub min_stake: u64,
+}
+
+impl Default for StakingConfig {
+    fn default() -> Self {
+        Self {
+            unbonding_period: 100,
+            epoch_length: 100,
+            max_validators: 21,
+            min_stake: 1,
+        }
+    }
+}
+
+/// Blocks of the chain spec known to be canonical, as heights and hex encoded
+/// block hashes. Blocks up to the highest one imported are final.
+pub const CHECKPOINTS: &[(u64, &str)] = &[];
+
+/// When blocks become final and can no longer be replaced.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FinalityConfig {
+    /// Blocks this far below the head are final, `None` to only trust
+    /// checkpoints and commit certificates.
+    pub confirmation_depth: Option<u64>,
+    /// Heights and hex encoded hashes of the blocks the chain must contain.
+    pub checkpoints: Vec<(u64, String)>,
+    /// Blocks kept below the finalized height, older ones being pruned after
+    /// every import. `None` keeps every block.
+    pub retain_blocks: Option<u64>,
+}
+
+impl Default for FinalityConfig {
+    fn default() -> Self {
+        Self {
+            confirmation_depth: Some(64),
+            checkpoints: CHECKPOINTS
+                .iter()
+                .map(|(height, hash)| (*height, hash.to_string()))
+                .collect(),
+            retain_blocks: None,
+        }
+    }
+}
+
+/// Schedule of the block producer task.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProducerConfig {
+    /// Time between two blocks, unless the mempool fills up sooner.
+    pub interval_ms: u64,
+    /// Skips the blocks that would not contain any transaction.
+    pub skip_empty: bool,
+}
+
+impl Default for ProducerConfig {
+    fn default() -> Self {
+        Self {
+            interval_ms: 1000,
+            skip_empty: true,
+        }
+    }
+}
+
+/// How the transactions of a block are executed.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExecutionConfig {
+    /// Workers of the parallel executor, `None` to execute transactions one after
+    /// the other.
+    pub parallel_workers: Option<usize>,
 }
 
 pub fn load_config() -> Config {
@@ -14,6 +123,11 @@ pub fn load_config() -> Config {
         single_tx_max_size: 100,
         tx_max_size: 1000,
         min_tx_threshold: 100,
+        consensus: ConsensusConfig::Pow { difficulty: 0 },
+        staking: StakingConfig::default(),
+        finality: FinalityConfig::default(),
+        producer: ProducerConfig::default(),
+        execution: ExecutionConfig::default(),
     }
 }
 
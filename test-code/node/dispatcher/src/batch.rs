@@ -0,0 +1,126 @@
+use node::{error::NodeError, manager::NodeManager, producer::BlockProducer};
+use rm_reth_types::{block::block::Block, vesting::VestingStatus};
+use storage::Snapshot;
+
+use crate::{
+    command::{Command, CommandClass, Response},
+    error::DispatchError,
+    service::handle_single,
+};
+
+/// Largest number of commands a [`Command::Batch`] may carry.
+pub const MAX_BATCH_SIZE: usize = 100;
+
+/// Chain and state as of one storage read transaction.
+struct ReadView<'a> {
+    snapshot: Snapshot<'a>,
+    height: u64,
+}
+
+impl<'a> ReadView<'a> {
+    fn open(node: &'a NodeManager) -> Result<Self, DispatchError> {
+        let snapshot = node.storage().snapshot()?;
+        let mut height = node.height();
+
+        // the head moves before its block is stored
+        if height > 0 && !snapshot.has_block(height)? {
+            height -= 1;
+        }
+
+        Ok(Self { snapshot, height })
+    }
+
+    /// Answers the state and chain reads from the view, `None` for the other
+    /// commands.
+    fn read(&self, cmd: &Command) -> Result<Option<Response>, DispatchError> {
+        let snapshot = &self.snapshot;
+
+        let response = match cmd {
+            Command::GetBalance(address) => Response::GetBalance(snapshot.balance(address)?),
+            Command::GetNonce(address) => Response::GetNonce(snapshot.nonce(address)?),
+            Command::GetAsset(asset) => Response::Asset(snapshot.asset(asset)?),
+            Command::GetAssetBalance { address, asset } => {
+                Response::GetAssetBalance(snapshot.asset_balance(address, asset)?)
+            }
+            Command::GetTotalSupply(asset) => {
+                let metadata = snapshot
+                    .asset(asset)?
+                    .ok_or(NodeError::UnknownAsset(*asset))?;
+
+                Response::TotalSupply(metadata.total_supply)
+            }
+            Command::GetHtlc(id) => Response::Htlc(snapshot.htlc(id)?),
+            Command::GetOpenHtlcs(address) => Response::Htlcs(snapshot.open_htlcs(address)?),
+            Command::GetVesting(address) => Response::Vesting(VestingStatus::new(
+                self.height + 1,
+                snapshot.balance(address)?,
+                snapshot.vesting(address)?,
+            )),
+            Command::GetStake(address) => Response::Stake(snapshot.validator(address)?),
+            Command::GetHeight => Response::Height(self.height),
+            Command::GetBlock(height) => Response::Block(Box::new(self.block(*height)?)),
+            Command::GetLatestBlock => Response::Block(Box::new(self.block(self.height)?)),
+            Command::GetHeader(height) => Response::Header(self.block(*height)?.header().clone()),
+            _ => return Ok(None),
+        };
+
+        Ok(Some(response))
+    }
+
+    fn block(&self, height: u64) -> Result<Block, DispatchError> {
+        let block = self
+            .snapshot
+            .block(height)?
+            .ok_or(NodeError::BlockNotExist(height))?;
+
+        Ok(block)
+    }
+}
+
+/// Runs `commands` in order, each failing on its own.
+///
+/// Consecutive reads are answered from one storage read transaction, so they
+/// observe a single state of the chain. Any other command ends that view, the
+/// reads after it seeing its effects.
+pub(crate) async fn handle_batch(
+    commands: Vec<Command>,
+    node: &NodeManager,
+    producer: &BlockProducer,
+) -> Result<Vec<Result<Response, DispatchError>>, DispatchError> {
+    if commands.len() > MAX_BATCH_SIZE {
+        return Err(DispatchError::BatchTooLarge {
+            len: commands.len(),
+            max: MAX_BATCH_SIZE,
+        });
+    }
+
+    let mut view: Option<ReadView<'_>> = None;
+    let mut results = Vec::with_capacity(commands.len());
+
+    for cmd in commands {
+        if matches!(cmd, Command::Batch(_)) {
+            results.push(Err(DispatchError::NestedBatch));
+            continue;
+        }
+
+        if cmd.class() != CommandClass::Query {
+            view = None;
+            results.push(handle_single(cmd, node, producer).await);
+            continue;
+        }
+
+        let read = match &view {
+            Some(view) => view.read(&cmd),
+            None => ReadView::open(node).and_then(|opened| view.insert(opened).read(&cmd)),
+        };
+
+        let result = match read.transpose() {
+            Some(result) => result,
+            None => handle_single(cmd, node, producer).await,
+        };
+
+        results.push(result);
+    }
+
+    Ok(results)
+}
@@ -1,12 +1,31 @@
+use node::manager::MempoolStatus;
+use parity_scale_codec::{Decode, Encode};
 use rm_reth_types::{
-    Address, block::block::Block, bytes::FixedBytes, hash::Hash, int::Uint256,
+    Address,
+    asset::{AssetId, AssetMetadata},
+    block::block::{Block, Header},
+    bytes::FixedBytes,
+    hash::Hash,
+    htlc::Htlc,
+    int::Uint256,
+    log::LogEntry,
+    staking::{EpochValidators, ValidatorRecord},
     tx::transaction::Transaction,
+    vesting::VestingStatus,
 };
+use vm::simulate::Simulation;
 
-#[derive(Debug)]
+use crate::error::{DispatchError, ErrorCode};
+
+/// Request handled by the dispatcher.
+///
+/// Commands are journaled SCALE encoded, which identifies variants by position:
+/// new ones go at the end.
+#[derive(Debug, Encode, Decode)]
 pub enum Command {
     // transection
     SubmitTx(Transaction),
+    SimulateTx(Transaction),
     // ValidateTransaction(Transaction),
 
     // block
@@ -17,19 +36,145 @@ pub enum Command {
     // status
     GetBalance(Address),
     GetNonce(Address),
+    GetLogs {
+        from: u64,
+        to: u64,
+        address: Option<Address>,
+        topics: Vec<Option<Hash>>,
+    },
+    GetAsset(AssetId),
+    GetAssetBalance {
+        address: Address,
+        asset: AssetId,
+    },
+    GetTotalSupply(AssetId),
+    GetHtlc(Hash),
+    GetOpenHtlcs(Address),
+    GetVesting(Address),
+    GetStake(Address),
+    GetValidators,
+    GetNextValidators,
+    GetFinalizedHeight,
+    GetBlock(u64),
+    GetLatestBlock,
+    GetHeight,
+    GetHeader(u64),
+    GetBlockRange {
+        from: u64,
+        to: u64,
+    },
+    GetMempool,
 
     // node
     MineBlock(FixedBytes<32>),
+    StartProducer,
+    StopProducer,
     // SyncPeer(PeerId),
+    /// Commands run in order in one dispatcher call, see [`crate::batch`].
+    Batch(Vec<Command>),
 }
 
 #[derive(Debug)]
 pub enum Response {
     Ok,
     TxReceipt(TxReceipt),
-    Block(Block),
+    Block(Box<Block>),
+    Blocks(Vec<Block>),
+    Header(Header),
+    Height(u64),
+    Mempool(MempoolStatus),
     GetBalance(Uint256),
     GetNonce(u64),
+    Simulation(Simulation),
+    Logs(Vec<LogEntry>),
+    Asset(Option<AssetMetadata>),
+    GetAssetBalance(Uint256),
+    TotalSupply(Uint256),
+    Htlc(Option<Htlc>),
+    Htlcs(Vec<Htlc>),
+    Vesting(VestingStatus),
+    Stake(Option<ValidatorRecord>),
+    Validators(EpochValidators),
+    FinalizedHeight(u64),
+    /// Outcome of every command of a [`Command::Batch`], in order.
+    Batch(Vec<Result<Response, DispatchError>>),
+    /// Failure of a command, for transports that carry errors as responses.
+    Error {
+        code: ErrorCode,
+        message: String,
+    },
+}
+
+/// Kind of work a command does, which is rate limited on its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CommandClass {
+    /// Admits transactions into the mempool.
+    Submit = 0,
+    /// Reads state without changing it.
+    Query = 1,
+    /// Builds blocks or controls block production.
+    Block = 2,
+}
+
+impl Command {
+    /// Class of the command. A batch takes the class of its heaviest command, a
+    /// block command over a submission over a query, but is rate limited by
+    /// [`Command::cost`].
+    pub fn class(&self) -> CommandClass {
+        match self {
+            Command::Batch(commands) => {
+                let classes: Vec<_> = commands.iter().map(Command::class).collect();
+
+                [CommandClass::Block, CommandClass::Submit]
+                    .into_iter()
+                    .find(|class| classes.contains(class))
+                    .unwrap_or(CommandClass::Query)
+            }
+            Command::SubmitTx(_) => CommandClass::Submit,
+            Command::MineBlock(_) | Command::StartProducer | Command::StopProducer => {
+                CommandClass::Block
+            }
+            Command::SimulateTx(_)
+            | Command::GetBalance(_)
+            | Command::GetNonce(_)
+            | Command::GetLogs { .. }
+            | Command::GetAsset(_)
+            | Command::GetAssetBalance { .. }
+            | Command::GetTotalSupply(_)
+            | Command::GetHtlc(_)
+            | Command::GetOpenHtlcs(_)
+            | Command::GetVesting(_)
+            | Command::GetStake(_)
+            | Command::GetValidators
+            | Command::GetNextValidators
+            | Command::GetFinalizedHeight
+            | Command::GetBlock(_)
+            | Command::GetLatestBlock
+            | Command::GetHeight
+            | Command::GetHeader(_)
+            | Command::GetBlockRange { .. }
+            | Command::GetMempool => CommandClass::Query,
+        }
+    }
+
+    /// Rate limit tokens the command takes from each class, indexed by
+    /// [`CommandClass`]. A batch costs one token per command it carries.
+    pub fn cost(&self) -> [u64; 3] {
+        let mut cost = [0; 3];
+
+        match self {
+            Command::Batch(commands) => {
+                for command in commands {
+                    for (total, tokens) in cost.iter_mut().zip(command.cost()) {
+                        *total += tokens;
+                    }
+                }
+            }
+            command => cost[command.class() as usize] = 1,
+        }
+
+        cost
+    }
 }
 
 #[derive(Debug)]
@@ -48,19 +193,71 @@ impl CommandLog for Command {
     fn name(&self) -> &'static str {
         match self {
             Command::MineBlock { .. } => "mine_block",
+            Command::StartProducer => "start_producer",
+            Command::StopProducer => "stop_producer",
             Command::SubmitTx(_) => "submit_tx",
+            Command::SimulateTx(_) => "simulate_tx",
             Command::GetBalance(_) => "get_balance",
             Command::GetNonce(_) => "get_nonce",
+            Command::GetLogs { .. } => "get_logs",
+            Command::GetAsset(_) => "get_asset",
+            Command::GetAssetBalance { .. } => "get_asset_balance",
+            Command::GetTotalSupply(_) => "get_total_supply",
+            Command::GetHtlc(_) => "get_htlc",
+            Command::GetOpenHtlcs(_) => "get_open_htlcs",
+            Command::GetVesting(_) => "get_vesting",
+            Command::GetStake(_) => "get_stake",
+            Command::GetValidators => "get_validators",
+            Command::GetNextValidators => "get_next_validators",
+            Command::GetFinalizedHeight => "get_finalized_height",
+            Command::GetBlock(_) => "get_block",
+            Command::GetLatestBlock => "get_latest_block",
+            Command::GetHeight => "get_height",
+            Command::GetHeader(_) => "get_header",
+            Command::GetBlockRange { .. } => "get_block_range",
+            Command::GetMempool => "get_mempool",
+            Command::Batch(_) => "batch",
         }
     }
 
     fn summary(&self) -> String {
         match self {
             Command::MineBlock { .. } => "mine new block".into(),
+            Command::StartProducer => "start block producer".into(),
+            Command::StopProducer => "stop block producer".into(),
             // TODO: tx display with tx hash
             Command::SubmitTx(tx) => format!("tx={:?}", tx),
+            Command::SimulateTx(tx) => format!("tx={:?}", tx),
             Command::GetBalance(addr) => format!("addr={}", addr),
             Command::GetNonce(addr) => format!("addr={}", addr),
+            Command::GetLogs {
+                from,
+                to,
+                address,
+                topics,
+            } => format!(
+                "from={} to={} address={:?} topics={:?}",
+                from, to, address, topics
+            ),
+            Command::GetAsset(asset) => format!("asset={}", asset),
+            Command::GetAssetBalance { address, asset } => {
+                format!("addr={} asset={}", address, asset)
+            }
+            Command::GetTotalSupply(asset) => format!("asset={}", asset),
+            Command::GetHtlc(id) => format!("id={}", id),
+            Command::GetOpenHtlcs(addr) => format!("addr={}", addr),
+            Command::GetVesting(addr) => format!("addr={}", addr),
+            Command::GetStake(addr) => format!("addr={}", addr),
+            Command::GetValidators => "current validator set".into(),
+            Command::GetNextValidators => "next validator set".into(),
+            Command::GetFinalizedHeight => "finalized height".into(),
+            Command::GetBlock(height) => format!("height={}", height),
+            Command::GetLatestBlock => "latest block".into(),
+            Command::GetHeight => "chain height".into(),
+            Command::GetHeader(height) => format!("height={}", height),
+            Command::GetBlockRange { from, to } => format!("from={} to={}", from, to),
+            Command::GetMempool => "mempool status".into(),
+            Command::Batch(commands) => format!("commands={}", commands.len()),
         }
     }
 }
@@ -1,5 +1,9 @@
+use node::error::NodeError;
+use storage::error::StorageError;
 use thiserror::Error;
 
+use crate::command::Response;
+
 #[derive(Debug, Error)]
 pub enum DispatchError {
     #[error("timeout")]
@@ -8,6 +12,151 @@ pub enum DispatchError {
     #[error("concurrency limit reached")]
     Overloaded,
 
+    #[error("batch of {len} commands, at most {max} allowed")]
+    BatchTooLarge { len: usize, max: usize },
+
+    #[error("batches cannot be nested")]
+    NestedBatch,
+
+    #[error(transparent)]
+    Node(#[from] NodeError),
+
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+
     #[error(transparent)]
     Inner(#[from] anyhow::Error),
 }
+
+/// Stable identifier of a [`DispatchError`], forwarded as is by every transport.
+///
+/// Codes are never reused: new failures get new codes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u16)]
+pub enum ErrorCode {
+    Internal = 1,
+    Timeout = 2,
+    Overloaded = 3,
+    Storage = 4,
+    InvalidBatch = 5,
+
+    MempoolFull = 100,
+    InvalidTransaction = 101,
+
+    BlockNotFound = 200,
+    InvalidBlock = 201,
+    Consensus = 202,
+    BlockPruned = 203,
+
+    InvalidLogRange = 300,
+    UnknownAsset = 301,
+    InvalidBlockRange = 302,
+}
+
+impl ErrorCode {
+    pub const ALL: [ErrorCode; 14] = [
+        ErrorCode::Internal,
+        ErrorCode::Timeout,
+        ErrorCode::Overloaded,
+        ErrorCode::Storage,
+        ErrorCode::InvalidBatch,
+        ErrorCode::MempoolFull,
+        ErrorCode::InvalidTransaction,
+        ErrorCode::BlockNotFound,
+        ErrorCode::InvalidBlock,
+        ErrorCode::Consensus,
+        ErrorCode::BlockPruned,
+        ErrorCode::InvalidLogRange,
+        ErrorCode::UnknownAsset,
+        ErrorCode::InvalidBlockRange,
+    ];
+
+    #[inline]
+    pub fn as_u16(self) -> u16 {
+        self as u16
+    }
+
+    pub fn from_u16(code: u16) -> Option<Self> {
+        Self::ALL.into_iter().find(|known| known.as_u16() == code)
+    }
+
+    /// Name of the code for transports preferring strings.
+    pub fn name(self) -> &'static str {
+        match self {
+            ErrorCode::Internal => "internal",
+            ErrorCode::Timeout => "timeout",
+            ErrorCode::Overloaded => "overloaded",
+            ErrorCode::Storage => "storage",
+            ErrorCode::InvalidBatch => "invalid_batch",
+            ErrorCode::MempoolFull => "mempool_full",
+            ErrorCode::InvalidTransaction => "invalid_transaction",
+            ErrorCode::BlockNotFound => "block_not_found",
+            ErrorCode::InvalidBlock => "invalid_block",
+            ErrorCode::Consensus => "consensus",
+            ErrorCode::BlockPruned => "block_pruned",
+            ErrorCode::InvalidLogRange => "invalid_log_range",
+            ErrorCode::UnknownAsset => "unknown_asset",
+            ErrorCode::InvalidBlockRange => "invalid_block_range",
+        }
+    }
+}
+
+impl DispatchError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            DispatchError::Timeout => ErrorCode::Timeout,
+            DispatchError::Overloaded => ErrorCode::Overloaded,
+            DispatchError::BatchTooLarge { .. } | DispatchError::NestedBatch => {
+                ErrorCode::InvalidBatch
+            }
+            DispatchError::Storage(_) => ErrorCode::Storage,
+            DispatchError::Inner(_) => ErrorCode::Internal,
+            DispatchError::Node(e) => match e {
+                NodeError::StorageError(_) => ErrorCode::Storage,
+                NodeError::MempoolFull => ErrorCode::MempoolFull,
+                NodeError::InvalidTransaction(_) => ErrorCode::InvalidTransaction,
+                NodeError::BlockNotExist(_) => ErrorCode::BlockNotFound,
+                NodeError::InvalidBlockHash
+                | NodeError::UnexpectedBlock { .. }
+                | NodeError::StaleBlock(_)
+                | NodeError::UnknownParent(_)
+                | NodeError::BelowFinalized { .. }
+                | NodeError::IrreversibleBlock(_)
+                | NodeError::CheckpointMismatch(_) => ErrorCode::InvalidBlock,
+                NodeError::Consensus(_) => ErrorCode::Consensus,
+                NodeError::BlockPruned(_) => ErrorCode::BlockPruned,
+                NodeError::InvalidLogRange { .. } => ErrorCode::InvalidLogRange,
+                NodeError::UnknownAsset(_) => ErrorCode::UnknownAsset,
+                NodeError::InvalidBlockRange { .. } => ErrorCode::InvalidBlockRange,
+                NodeError::InitializeError(_)
+                | NodeError::ProcessBlockError(_)
+                | NodeError::InsertBlockError(_)
+                | NodeError::InvalidCheckpoint(_) => ErrorCode::Internal,
+            },
+        }
+    }
+}
+
+impl From<DispatchError> for Response {
+    fn from(error: DispatchError) -> Self {
+        Response::Error {
+            code: error.code(),
+            message: error.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_are_unique_and_round_trip() {
+        for code in ErrorCode::ALL {
+            assert_eq!(ErrorCode::from_u16(code.as_u16()), Some(code));
+        }
+
+        assert_eq!(ErrorCode::from_u16(0), None);
+        assert_eq!(ErrorCode::MempoolFull.as_u16(), 100);
+    }
+}
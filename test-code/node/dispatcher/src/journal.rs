@@ -0,0 +1,219 @@
+//! Append-only record of the commands a dispatcher handled, to replay them into a
+//! fresh node when debugging.
+//!
+//! Every record is a SCALE encoded byte vector holding the millisecond UNIX
+//! timestamp the command arrived at followed by the SCALE encoded [`Command`].
+//!
+//! Commands changing the node are appended and run one at a time, so the journal
+//! holds them in the order they took effect. Blocks a running producer builds are
+//! not commands though: a journal spanning `StartProducer` doesn't replay into
+//! the same chain.
+
+use std::{
+    fs::{File, OpenOptions},
+    future::Future,
+    io::Write,
+    mem,
+    path::Path,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use parity_scale_codec::{Decode, Encode};
+use tower::{Layer, Service, ServiceExt};
+
+use crate::{
+    command::{Command, CommandClass, Response},
+    service::Dispatcher,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum JournalError {
+    #[error("journal io error: ({0})")]
+    Io(#[from] std::io::Error),
+
+    #[error("corrupted journal entry {index}: ({source})")]
+    Corrupted {
+        index: usize,
+        #[source]
+        source: parity_scale_codec::Error,
+    },
+}
+
+#[derive(Debug, Encode, Decode)]
+pub struct JournalEntry {
+    pub timestamp_ms: u64,
+    pub command: Command,
+}
+
+/// Journal file commands are appended to.
+#[derive(Debug)]
+pub struct Journal {
+    file: Mutex<File>,
+    /// Held while a command changing the node is appended and run.
+    order: tokio::sync::Mutex<()>,
+}
+
+impl Journal {
+    /// Opens `path` for appending, creating it if needed.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, JournalError> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+
+        Ok(Self {
+            file: Mutex::new(file),
+            order: tokio::sync::Mutex::new(()),
+        })
+    }
+
+    pub fn append(&self, command: &Command) -> Result<(), JournalError> {
+        let mut file = self.file.lock().expect("journal lock poisoned");
+
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_millis() as u64);
+
+        let record = (timestamp_ms, command).encode().encode();
+
+        file.write_all(&record)?;
+        file.flush()?;
+
+        Ok(())
+    }
+}
+
+/// Reads every entry of the journal at `path`, in the order they were appended.
+pub fn read_journal<P: AsRef<Path>>(path: P) -> Result<Vec<JournalEntry>, JournalError> {
+    let bytes = std::fs::read(path)?;
+    let mut input = bytes.as_slice();
+    let mut entries = vec![];
+
+    while !input.is_empty() {
+        let corrupted = |source| JournalError::Corrupted {
+            index: entries.len(),
+            source,
+        };
+
+        let record = Vec::<u8>::decode(&mut input).map_err(corrupted)?;
+        let entry = JournalEntry::decode(&mut record.as_slice()).map_err(corrupted)?;
+
+        entries.push(entry);
+    }
+
+    Ok(entries)
+}
+
+/// Outcome of [`replay`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ReplayReport {
+    pub applied: usize,
+    /// Commands that failed, as they may have when they were journaled.
+    pub failed: usize,
+    /// Block producer controls. The blocks the producer built in between were
+    /// never journaled, so a replay skipping them may end on a different chain.
+    pub skipped: usize,
+}
+
+/// Feeds `entries` in order to `dispatcher`, without the limits of the
+/// dispatcher stack the commands originally went through.
+pub async fn replay(
+    entries: impl IntoIterator<Item = JournalEntry>,
+    dispatcher: Dispatcher,
+) -> ReplayReport {
+    let mut report = ReplayReport::default();
+
+    for entry in entries {
+        if matches!(
+            entry.command,
+            Command::StartProducer | Command::StopProducer
+        ) {
+            if matches!(entry.command, Command::StartProducer) {
+                tracing::warn!("journal ran the block producer, its blocks are not replayed");
+            }
+            report.skipped += 1;
+            continue;
+        }
+
+        match dispatcher.clone().oneshot(entry.command).await {
+            Ok(_) => report.applied += 1,
+            Err(e) => {
+                tracing::debug!(error = %e, "replayed command failed");
+                report.failed += 1;
+            }
+        }
+    }
+
+    report
+}
+
+/// Appends every command to a [`Journal`] before handing it over. Commands
+/// changing the node wait for the ones journaled before them to finish.
+#[derive(Clone)]
+pub struct JournalLayer {
+    journal: Arc<Journal>,
+}
+
+impl JournalLayer {
+    pub fn new(journal: Arc<Journal>) -> Self {
+        Self { journal }
+    }
+}
+
+impl<S> Layer<S> for JournalLayer {
+    type Service = JournalService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        JournalService {
+            inner,
+            journal: self.journal.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct JournalService<S> {
+    inner: S,
+    journal: Arc<Journal>,
+}
+
+impl<S> Service<Command> for JournalService<S>
+where
+    S: Service<Command, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, cmd: Command) -> Self::Future {
+        // queries leave the node as it was, so they may run in any order
+        if cmd.class() == CommandClass::Query {
+            append(&self.journal, &cmd);
+            return Box::pin(self.inner.call(cmd));
+        }
+
+        // the readied service goes into the future, its clone stays behind
+        let clone = self.inner.clone();
+        let mut inner = mem::replace(&mut self.inner, clone);
+        let journal = self.journal.clone();
+
+        Box::pin(async move {
+            let _order = journal.order.lock().await;
+
+            append(&journal, &cmd);
+            inner.call(cmd).await
+        })
+    }
+}
+
+fn append(journal: &Journal, cmd: &Command) {
+    // a journal failure loses the replay, not the command
+    if let Err(e) = journal.append(cmd) {
+        tracing::warn!(error = %e, "failed to journal command");
+    }
+}
@@ -39,7 +39,9 @@ where
     }
 
     fn call(&mut self, cmd: Command) -> Self::Future {
-        let mut inner = self.inner.clone();
+        // the readied service handles the call, the clone waits for the next one
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
 
         let span = tracing::info_span!("command", name = cmd.name(), summary = cmd.summary(),);
 
@@ -0,0 +1,73 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Instant,
+};
+
+use tower::{Layer, Service};
+
+use crate::{
+    command::{Command, CommandLog},
+    metrics::Metrics,
+};
+
+/// Records the count, failures and latency of every command into [`Metrics`].
+#[derive(Clone)]
+pub struct MetricsLayer {
+    metrics: Arc<Metrics>,
+}
+
+impl MetricsLayer {
+    pub fn new(metrics: Arc<Metrics>) -> Self {
+        Self { metrics }
+    }
+}
+
+impl<S> Layer<S> for MetricsLayer {
+    type Service = MetricsService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        MetricsService {
+            inner,
+            metrics: self.metrics.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct MetricsService<S> {
+    inner: S,
+    metrics: Arc<Metrics>,
+}
+
+impl<S> Service<Command> for MetricsService<S>
+where
+    S: Service<Command>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, cmd: Command) -> Self::Future {
+        let metrics = self.metrics.clone();
+        let name = cmd.name();
+        let start = Instant::now();
+
+        let future = self.inner.call(cmd);
+
+        Box::pin(async move {
+            let result = future.await;
+
+            metrics.record(name, start.elapsed(), result.is_ok());
+
+            result
+        })
+    }
+}
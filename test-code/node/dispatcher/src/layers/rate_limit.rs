@@ -0,0 +1,236 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use tower::{BoxError, Layer, Service};
+
+use crate::{
+    command::{Command, CommandClass},
+    error::DispatchError,
+};
+
+/// At most `num` commands every `per`, sustained, with bursts of up to `num`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    pub num: u64,
+    pub per: Duration,
+}
+
+impl RateLimit {
+    pub const fn new(num: u64, per: Duration) -> Self {
+        Self { num, per }
+    }
+}
+
+/// Rate limit of every [`CommandClass`], `None` leaving the class unlimited.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RateLimits {
+    pub submit: Option<RateLimit>,
+    pub query: Option<RateLimit>,
+    pub block: Option<RateLimit>,
+}
+
+impl RateLimits {
+    fn get(&self, class: CommandClass) -> Option<RateLimit> {
+        match class {
+            CommandClass::Submit => self.submit,
+            CommandClass::Query => self.query,
+            CommandClass::Block => self.block,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    limit: RateLimit,
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl Bucket {
+    fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            tokens: limit.num as f64,
+            refilled: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.refilled).as_secs_f64();
+        let rate = self.limit.num as f64 / self.limit.per.as_secs_f64();
+
+        self.tokens = (self.tokens + elapsed * rate).min(self.limit.num as f64);
+        self.refilled = now;
+    }
+
+    fn has(&mut self, tokens: u64) -> bool {
+        self.refill();
+        self.tokens >= tokens as f64
+    }
+
+    fn try_acquire(&mut self, tokens: u64) -> bool {
+        if !self.has(tokens) {
+            return false;
+        }
+
+        self.tokens -= tokens as f64;
+        true
+    }
+}
+
+/// Rejects commands past the rate limit of their class with
+/// [`DispatchError::Overloaded`] instead of queueing them.
+#[derive(Clone)]
+pub struct RateLimitLayer {
+    buckets: Arc<[Option<Mutex<Bucket>>; 3]>,
+}
+
+impl RateLimitLayer {
+    pub fn new(limits: &RateLimits) -> Self {
+        let bucket = |class| {
+            limits
+                .get(class)
+                .map(|limit| Mutex::new(Bucket::new(limit)))
+        };
+
+        Self {
+            buckets: Arc::new([
+                bucket(CommandClass::Submit),
+                bucket(CommandClass::Query),
+                bucket(CommandClass::Block),
+            ]),
+        }
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimitService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimitService {
+            inner,
+            buckets: self.buckets.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimitService<S> {
+    inner: S,
+    buckets: Arc<[Option<Mutex<Bucket>>; 3]>,
+}
+
+impl<S> RateLimitService<S> {
+    /// Takes the tokens of every class at once, or none of them if any bucket
+    /// falls short, so a rejected batch doesn't drain the other classes.
+    fn try_acquire(&self, cost: [u64; 3]) -> bool {
+        // buckets are locked in class order, so concurrent calls can't deadlock
+        let mut buckets: Vec<_> = self
+            .buckets
+            .iter()
+            .zip(cost)
+            .filter(|(_, tokens)| *tokens > 0)
+            .filter_map(|(bucket, tokens)| {
+                let bucket = bucket.as_ref()?.lock().expect("rate limit lock poisoned");
+                Some((bucket, tokens))
+            })
+            .collect();
+
+        if !buckets
+            .iter_mut()
+            .all(|(bucket, tokens)| bucket.has(*tokens))
+        {
+            return false;
+        }
+
+        for (bucket, tokens) in &mut buckets {
+            bucket.try_acquire(*tokens);
+        }
+        true
+    }
+}
+
+impl<S> Service<Command> for RateLimitService<S>
+where
+    S: Service<Command>,
+    S::Error: Into<BoxError>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = BoxError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, cmd: Command) -> Self::Future {
+        if !self.try_acquire(cmd.cost()) {
+            return Box::pin(async { Err(DispatchError::Overloaded.into()) });
+        }
+
+        let future = self.inner.call(cmd);
+
+        Box::pin(async move { future.await.map_err(Into::into) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rm_reth_types::{Address, int::Uint256, tx::transaction::Transaction};
+    use tower::{ServiceExt, service_fn};
+
+    use super::*;
+
+    #[test]
+    fn bucket_refills_over_time() {
+        let mut bucket = Bucket::new(RateLimit::new(2, Duration::from_millis(50)));
+
+        assert!(bucket.try_acquire(1));
+        assert!(bucket.try_acquire(1));
+        assert!(!bucket.try_acquire(1));
+
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(bucket.try_acquire(1));
+        assert!(!bucket.try_acquire(1));
+    }
+
+    #[tokio::test]
+    async fn batches_take_one_token_per_command() {
+        let limits = RateLimits {
+            submit: Some(RateLimit::new(3, Duration::from_secs(3600))),
+            ..Default::default()
+        };
+        let service = RateLimitLayer::new(&limits)
+            .layer(service_fn(|_| async { Ok::<_, DispatchError>(()) }));
+
+        let submit = |amount| {
+            let tx = Transaction::new(
+                Address::default(),
+                Address::default(),
+                Uint256::from(amount),
+                vec![],
+            );
+            Command::SubmitTx(tx)
+        };
+        let batch = |len| Command::Batch((0..len).map(submit).collect());
+
+        assert!(service.clone().oneshot(batch(4)).await.is_err());
+        assert!(service.clone().oneshot(batch(2)).await.is_ok());
+
+        // a rejected batch leaves the remaining token for a single command
+        assert!(service.clone().oneshot(batch(2)).await.is_err());
+        assert!(service.clone().oneshot(submit(0)).await.is_ok());
+        assert!(service.clone().oneshot(submit(0)).await.is_err());
+
+        // queries in a batch don't draw on the submit bucket
+        let queries = Command::Batch(vec![Command::GetHeight, Command::GetMempool]);
+        assert!(service.clone().oneshot(queries).await.is_ok());
+    }
+}
@@ -1,21 +1,42 @@
+pub mod batch;
 pub mod command;
 pub mod error;
+pub mod journal;
 pub mod layers;
+pub mod metrics;
 pub mod service;
 
 #[cfg(test)]
 mod test {
-    use std::{sync::Arc, time::Duration};
+    use std::{
+        sync::{Arc, atomic::Ordering},
+        time::Duration,
+    };
 
-    use node::manager::NodeManager;
+    use config::ProducerConfig;
+    use node::{finality::Finality, manager::NodeManager, producer::BlockProducer};
     use rand::{Rng, SeedableRng, rngs::StdRng};
-    use rm_reth_types::{Address, int::Uint256, tx::transaction::Transaction};
-    use storage::StorageManager;
+    use rm_reth_types::{
+        Address,
+        asset::{AssetId, AssetOp},
+        hash::Hash,
+        htlc::{Htlc, HtlcStatus},
+        int::Uint256,
+        multisig::PublicKey,
+        staking::prove_possession,
+        tx::{kind::TxKind, transaction::Transaction},
+    };
+    use storage::{StorageManager, TableId};
     use tokio::time::interval;
-    use tower::ServiceExt;
+    use tower::{Service, ServiceExt};
 
     use crate::{
-        command::Command,
+        batch::MAX_BATCH_SIZE,
+        command::{Command, Response},
+        error::{DispatchError, ErrorCode},
+        journal::{Journal, JournalError, ReplayReport, read_journal, replay},
+        layers::rate_limit::{RateLimit, RateLimits},
+        metrics::{Metrics, serve_metrics},
         service::{Dispatcher, DispatcherConfig, build_dispatcher},
     };
 
@@ -23,6 +44,32 @@ mod test {
         [id; 20].into()
     }
 
+    fn test_config() -> DispatcherConfig {
+        DispatcherConfig {
+            timeout: Duration::from_secs(1),
+            ..Default::default()
+        }
+    }
+
+    /// Node at genesis on in-memory storage, served through the layers `config`
+    /// sets up.
+    fn test_dispatcher(
+        config: &DispatcherConfig,
+    ) -> (
+        Arc<NodeManager>,
+        impl Service<Command, Response = Response, Error = DispatchError, Future: Send>
+        + Clone
+        + Send
+        + use<>,
+    ) {
+        let node = Arc::new(
+            NodeManager::genesis_with_storage(StorageManager::in_memory().unwrap()).unwrap(),
+        );
+        let service = build_dispatcher(Dispatcher::new(node.clone()), config);
+
+        (node, service)
+    }
+
     #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
     async fn test_valid_node_processing() {
         let storage = StorageManager::new_default().unwrap();
@@ -43,6 +90,7 @@ mod test {
         let cfg = DispatcherConfig {
             // concurrency_limit: 100,
             timeout: Duration::from_secs(1),
+            ..Default::default()
         };
 
         let service = build_dispatcher(dispatcher, &cfg);
@@ -122,4 +170,875 @@ mod test {
             "Total balance should be conserved"
         );
     }
+
+    #[tokio::test]
+    async fn test_simulate_tx_does_not_touch_state() {
+        let (node, service) = test_dispatcher(&test_config());
+
+        node.mint(&addr(1), &Uint256::from(100)).unwrap();
+
+        let tx = Transaction::new(addr(1), addr(2), Uint256::from(30), vec![]);
+
+        let response = service
+            .clone()
+            .oneshot(Command::SimulateTx(tx))
+            .await
+            .unwrap();
+
+        let Response::Simulation(simulation) = response else {
+            panic!("unexpected response: {:?}", response);
+        };
+
+        assert!(simulation.success());
+        assert_eq!(simulation.balance_changes.len(), 2);
+        assert_eq!(simulation.balance_changes[0].after, Uint256::from(70));
+
+        assert!(node.mempool().is_empty());
+
+        let response = service.oneshot(Command::GetBalance(addr(1))).await.unwrap();
+        assert!(matches!(response, Response::GetBalance(balance) if balance == Uint256::from(100)));
+    }
+
+    #[tokio::test]
+    async fn test_asset_queries() {
+        let (_node, service) = test_dispatcher(&test_config());
+        let gold = AssetId::derive(&addr(1), "GOLD");
+
+        let response = service.clone().oneshot(Command::GetTotalSupply(gold)).await;
+        assert!(response.is_err());
+
+        let issue = Transaction::asset(
+            addr(1),
+            AssetOp::Issue {
+                symbol: "GOLD".into(),
+                decimals: 8,
+                initial_supply: Uint256::from(1000),
+            },
+        );
+
+        for cmd in [
+            Command::SubmitTx(issue),
+            Command::MineBlock([0u8; 32].into()),
+        ] {
+            service.clone().oneshot(cmd).await.unwrap();
+        }
+
+        let response = service
+            .clone()
+            .oneshot(Command::GetAsset(gold))
+            .await
+            .unwrap();
+        let Response::Asset(Some(metadata)) = response else {
+            panic!("unexpected response: {:?}", response);
+        };
+        assert_eq!(metadata.symbol, "GOLD");
+        assert_eq!(metadata.decimals, 8);
+
+        let response = service
+            .clone()
+            .oneshot(Command::GetAssetBalance {
+                address: addr(1),
+                asset: gold,
+            })
+            .await
+            .unwrap();
+        assert!(
+            matches!(response, Response::GetAssetBalance(balance) if balance == Uint256::from(1000))
+        );
+
+        let response = service
+            .oneshot(Command::GetTotalSupply(gold))
+            .await
+            .unwrap();
+        assert!(matches!(response, Response::TotalSupply(supply) if supply == Uint256::from(1000)));
+    }
+
+    #[tokio::test]
+    async fn test_htlc_lock_and_claim() {
+        let (node, service) = test_dispatcher(&test_config());
+
+        node.mint(&addr(1), &Uint256::from(100)).unwrap();
+
+        let lock = Transaction::with_kind(
+            addr(1),
+            addr(1),
+            Uint256::from(40),
+            TxKind::HtlcLock {
+                recipient: addr(2),
+                hashlock: Hash::hash(b"secret"),
+                timelock: 10,
+            },
+        );
+
+        // the same lock twice in block 1 makes two locks
+        for cmd in [
+            Command::SubmitTx(lock.clone()),
+            Command::SubmitTx(lock.clone()),
+            Command::MineBlock([0u8; 32].into()),
+        ] {
+            service.clone().oneshot(cmd).await.unwrap();
+        }
+
+        let response = service
+            .clone()
+            .oneshot(Command::GetOpenHtlcs(addr(2)))
+            .await
+            .unwrap();
+        let Response::Htlcs(open) = response else {
+            panic!("unexpected response: {:?}", response);
+        };
+        let mut ids: Vec<_> = (0..2)
+            .map(|index| Htlc::lock_id(&lock.hash(), 1, index))
+            .collect();
+        ids.sort_by_key(|id| id.0);
+        assert_eq!(open.iter().map(|htlc| htlc.id).collect::<Vec<_>>(), ids);
+
+        let id = ids[0];
+        let claim = Transaction::with_kind(
+            addr(2),
+            addr(2),
+            Uint256::zero(),
+            TxKind::HtlcClaim {
+                id,
+                preimage: b"secret".to_vec(),
+            },
+        );
+
+        for cmd in [
+            Command::SubmitTx(claim),
+            Command::MineBlock([0u8; 32].into()),
+        ] {
+            service.clone().oneshot(cmd).await.unwrap();
+        }
+
+        let response = service.clone().oneshot(Command::GetHtlc(id)).await.unwrap();
+        let Response::Htlc(Some(htlc)) = response else {
+            panic!("unexpected response: {:?}", response);
+        };
+        assert_eq!(
+            htlc.status,
+            HtlcStatus::Claimed {
+                preimage: b"secret".to_vec()
+            }
+        );
+
+        let response = service
+            .clone()
+            .oneshot(Command::GetOpenHtlcs(addr(2)))
+            .await;
+        assert!(
+            matches!(response, Ok(Response::Htlcs(open)) if open.len() == 1 && open[0].id == ids[1])
+        );
+
+        for (id, expected) in [(1, 20), (2, 40)] {
+            let response = service
+                .clone()
+                .oneshot(Command::GetBalance(addr(id)))
+                .await
+                .unwrap();
+            assert!(
+                matches!(response, Response::GetBalance(balance) if balance == Uint256::from(expected))
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_vesting_limits_spending() {
+        let (node, service) = test_dispatcher(&test_config());
+
+        node.mint(&addr(1), &Uint256::from(1000)).unwrap();
+
+        let grant = Transaction::with_kind(
+            addr(1),
+            addr(1),
+            Uint256::from(600),
+            TxKind::VestingGrant {
+                beneficiary: addr(2),
+                start: 0,
+                cliff: 10,
+                duration: 100,
+            },
+        );
+
+        // the beneficiary has to accept the grant before it is paid out
+        let accept = Transaction::with_kind(
+            addr(2),
+            addr(2),
+            Uint256::zero(),
+            TxKind::VestingAccept { id: grant.hash() },
+        );
+
+        // only the unvested grant is locked, other funds stay spendable
+        let gift = Transaction::new(addr(1), addr(2), Uint256::from(50), vec![]);
+
+        for cmd in [
+            Command::SubmitTx(grant),
+            Command::SubmitTx(accept),
+            Command::SubmitTx(gift),
+            Command::MineBlock([0u8; 32].into()),
+        ] {
+            service.clone().oneshot(cmd).await.unwrap();
+        }
+
+        for cmd in [
+            Command::SubmitTx(Transaction::new(
+                addr(2),
+                addr(3),
+                Uint256::from(60),
+                vec![],
+            )),
+            Command::SubmitTx(Transaction::new(
+                addr(2),
+                addr(3),
+                Uint256::from(50),
+                vec![],
+            )),
+            Command::MineBlock([0u8; 32].into()),
+        ] {
+            service.clone().oneshot(cmd).await.unwrap();
+        }
+
+        let response = service
+            .clone()
+            .oneshot(Command::GetVesting(addr(2)))
+            .await
+            .unwrap();
+        let Response::Vesting(status) = response else {
+            panic!("unexpected response: {:?}", response);
+        };
+
+        assert_eq!(status.balance, Uint256::from(600));
+        assert_eq!(status.locked, Uint256::from(600));
+        assert_eq!(status.spendable, Uint256::zero());
+        assert_eq!(status.schedule.unwrap().grantor, addr(1));
+
+        let response = service
+            .clone()
+            .oneshot(Command::GetBalance(addr(3)))
+            .await
+            .unwrap();
+        assert!(matches!(response, Response::GetBalance(balance) if balance == Uint256::from(50)));
+    }
+
+    #[tokio::test]
+    async fn test_staking_rotates_validators_per_epoch() {
+        let (node, service) = test_dispatcher(&test_config());
+
+        node.mint(&addr(1), &Uint256::from(1000)).unwrap();
+        node.mint(&addr(2), &Uint256::from(1000)).unwrap();
+
+        let stake = |id: u8, amount: u64| {
+            let key = k256::ecdsa::SigningKey::from_bytes(&[id; 32].into()).unwrap();
+
+            Transaction::with_kind(
+                addr(id),
+                addr(id),
+                Uint256::from(amount),
+                TxKind::Stake {
+                    public_key: PublicKey::from_signing_key(&key),
+                    proof: prove_possession(&key, &addr(id)),
+                },
+            )
+        };
+
+        for cmd in [
+            Command::SubmitTx(stake(1, 300)),
+            Command::SubmitTx(stake(2, 500)),
+            Command::MineBlock([0u8; 32].into()),
+            Command::SubmitTx(Transaction::with_kind(
+                addr(1),
+                addr(1),
+                Uint256::from(100),
+                TxKind::Unstake,
+            )),
+            Command::MineBlock([0u8; 32].into()),
+        ] {
+            service.clone().oneshot(cmd).await.unwrap();
+        }
+
+        let response = service
+            .clone()
+            .oneshot(Command::GetStake(addr(1)))
+            .await
+            .unwrap();
+        let Response::Stake(Some(record)) = response else {
+            panic!("unexpected response: {:?}", response);
+        };
+
+        assert_eq!(record.stake, Uint256::from(200));
+        assert_eq!(record.unbonding[0].release_height, 102);
+
+        let validators = |cmd| {
+            let service = service.clone();
+
+            async move {
+                match service.oneshot(cmd).await.unwrap() {
+                    Response::Validators(set) => set,
+                    response => panic!("unexpected response: {:?}", response),
+                }
+            }
+        };
+
+        // the registry only takes effect at the next epoch
+        let current = validators(Command::GetValidators).await;
+        assert_eq!(current.epoch, 0);
+        assert!(current.validators.is_empty());
+
+        let next = validators(Command::GetNextValidators).await;
+        let picked: Vec<_> = next.validators.iter().map(|v| v.address).collect();
+        assert_eq!(next.epoch, 1);
+        assert_eq!(picked, vec![addr(2), addr(1)]);
+
+        // blocks 3..=99 close the first epoch
+        for _ in 3..100 {
+            service
+                .clone()
+                .oneshot(Command::MineBlock([0u8; 32].into()))
+                .await
+                .unwrap();
+        }
+
+        let current = validators(Command::GetValidators).await;
+        assert_eq!(current.epoch, 1);
+        assert_eq!(current.validators, next.validators);
+        assert_eq!(current.validator_set().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_finalized_height_trails_head_by_confirmation_depth() {
+        let node = Arc::new(
+            NodeManager::genesis_with_storage(StorageManager::in_memory().unwrap())
+                .unwrap()
+                .with_finality(Finality::new(Some(2), [])),
+        );
+
+        let cfg = test_config();
+
+        let service = build_dispatcher(Dispatcher::new(node.clone()), &cfg);
+
+        for _ in 0..5 {
+            service
+                .clone()
+                .oneshot(Command::MineBlock([0u8; 32].into()))
+                .await
+                .unwrap();
+        }
+
+        let response = service
+            .clone()
+            .oneshot(Command::GetFinalizedHeight)
+            .await
+            .unwrap();
+
+        assert!(matches!(response, Response::FinalizedHeight(3)));
+    }
+
+    #[tokio::test]
+    async fn test_producer_mines_submitted_transactions_until_stopped() {
+        let node = Arc::new(
+            NodeManager::genesis_with_storage(StorageManager::in_memory().unwrap()).unwrap(),
+        );
+
+        node.mint(&addr(1), &Uint256::from(100)).unwrap();
+
+        let cfg = test_config();
+
+        let producer = BlockProducer::new(
+            node.clone(),
+            ProducerConfig {
+                interval_ms: 20,
+                skip_empty: true,
+            },
+        );
+        let service = build_dispatcher(Dispatcher::new(node.clone()).with_producer(producer), &cfg);
+
+        let transfer = Transaction::new(addr(1), addr(2), Uint256::from(10), vec![]);
+
+        for cmd in [Command::StartProducer, Command::SubmitTx(transfer.clone())] {
+            service.clone().oneshot(cmd).await.unwrap();
+        }
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(node.current_block_id().load(Ordering::Acquire), 2);
+
+        for cmd in [Command::StopProducer, Command::SubmitTx(transfer)] {
+            service.clone().oneshot(cmd).await.unwrap();
+        }
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(node.current_block_id().load(Ordering::Acquire), 2);
+
+        let response = service
+            .clone()
+            .oneshot(Command::GetBalance(addr(2)))
+            .await
+            .unwrap();
+
+        assert!(matches!(response, Response::GetBalance(balance) if balance == Uint256::from(10)));
+    }
+
+    fn is_overloaded(result: Result<Response, DispatchError>) -> bool {
+        matches!(result, Err(DispatchError::Overloaded))
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrency_limit_sheds_burst() {
+        let cfg = DispatcherConfig {
+            concurrency_limit: 4,
+            ..Default::default()
+        };
+
+        let (_node, service) = test_dispatcher(&cfg);
+
+        // readied clones hold their slot until they are called or dropped
+        let mut held = vec![];
+        for _ in 0..cfg.concurrency_limit {
+            let mut clone = service.clone();
+            clone.ready().await.unwrap();
+            held.push(clone);
+        }
+
+        let burst: Vec<_> = (0..32)
+            .map(|_| tokio::spawn(service.clone().oneshot(Command::GetBalance(addr(1)))))
+            .collect();
+
+        for task in burst {
+            assert!(is_overloaded(task.await.unwrap()));
+        }
+
+        held.pop();
+
+        let response = service
+            .clone()
+            .oneshot(Command::GetBalance(addr(1)))
+            .await
+            .unwrap();
+        assert!(matches!(response, Response::GetBalance(_)));
+
+        // the remaining readied clones still complete their calls
+        for clone in held {
+            clone.oneshot(Command::GetNonce(addr(1))).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rate_limits_are_per_command_class() {
+        let (node, service) = test_dispatcher(&DispatcherConfig {
+            rate_limits: RateLimits {
+                submit: None,
+                query: Some(RateLimit::new(5, Duration::from_secs(60))),
+                block: Some(RateLimit::new(1, Duration::from_secs(60))),
+            },
+            ..Default::default()
+        });
+
+        node.mint(&addr(1), &Uint256::from(1000)).unwrap();
+
+        let mut served = 0;
+        for _ in 0..20 {
+            let result = service.clone().oneshot(Command::GetBalance(addr(1))).await;
+
+            if is_overloaded(result) {
+                continue;
+            }
+            served += 1;
+        }
+        assert_eq!(served, 5);
+
+        // queries being throttled leaves the other classes alone
+        for to in 2..12 {
+            let tx = Transaction::new(addr(1), addr(to), Uint256::from(1), vec![]);
+            service
+                .clone()
+                .oneshot(Command::SubmitTx(tx))
+                .await
+                .unwrap();
+        }
+
+        service
+            .clone()
+            .oneshot(Command::MineBlock([0u8; 32].into()))
+            .await
+            .unwrap();
+        assert!(is_overloaded(
+            service
+                .clone()
+                .oneshot(Command::MineBlock([0u8; 32].into()))
+                .await
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_metrics_are_scraped_in_prometheus_format() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let metrics = Metrics::new();
+        let (node, service) = test_dispatcher(&DispatcherConfig {
+            metrics: Some(metrics.clone()),
+            ..Default::default()
+        });
+
+        for cmd in [
+            Command::GetBalance(addr(1)),
+            Command::GetBalance(addr(2)),
+            Command::MineBlock([0u8; 32].into()),
+            Command::MineBlock([0u8; 32].into()),
+            // an empty batch is rejected before reaching the mempool
+            Command::SubmitTx(Transaction::multi_transfer(addr(1), vec![])),
+        ] {
+            let _ = service.clone().oneshot(cmd).await;
+        }
+
+        assert_eq!(metrics.command_count("get_balance"), (2, 0));
+        assert_eq!(metrics.command_count("submit_tx"), (1, 1));
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = tokio::spawn(serve_metrics(listener, metrics, node));
+
+        let scrape = |path: &'static str| async move {
+            let mut stream = tokio::net::TcpStream::connect(address).await.unwrap();
+            let request = format!("GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n");
+            stream.write_all(request.as_bytes()).await.unwrap();
+
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            response
+        };
+
+        let response = scrape("/metrics").await;
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+
+        for line in [
+            "# TYPE dispatcher_commands_total counter",
+            "dispatcher_commands_total{command=\"get_balance\"} 2",
+            "dispatcher_command_errors_total{command=\"submit_tx\"} 1",
+            "dispatcher_command_duration_seconds_count{command=\"mine_block\"} 2",
+            "dispatcher_command_duration_seconds_bucket{command=\"get_balance\",le=\"+Inf\"} 2",
+            "node_height 2",
+            "node_mempool_size 0",
+            "node_peers 0",
+        ] {
+            assert!(response.lines().any(|l| l == line), "missing {line}");
+        }
+        assert!(response.contains("node_block_time_seconds "));
+
+        assert!(scrape("/").await.starts_with("HTTP/1.1 404 Not Found"));
+
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_errors_carry_stable_codes() {
+        let (node, service) = test_dispatcher(&test_config());
+
+        let code = |result: Result<Response, DispatchError>| result.unwrap_err().code();
+
+        let empty_batch = Transaction::multi_transfer(addr(1), vec![]);
+        assert_eq!(
+            code(
+                service
+                    .clone()
+                    .oneshot(Command::SubmitTx(empty_batch))
+                    .await
+            ),
+            ErrorCode::InvalidTransaction
+        );
+
+        let gold = AssetId::derive(&addr(1), "GOLD");
+        assert_eq!(
+            code(service.clone().oneshot(Command::GetTotalSupply(gold)).await),
+            ErrorCode::UnknownAsset
+        );
+
+        let logs = Command::GetLogs {
+            from: 5,
+            to: 1,
+            address: None,
+            topics: vec![],
+        };
+        assert_eq!(
+            code(service.clone().oneshot(logs).await),
+            ErrorCode::InvalidLogRange
+        );
+
+        while !node.mempool().is_full() {
+            node.mempool().push(Transaction::dummy()).unwrap();
+        }
+
+        let transfer = Transaction::new(addr(1), addr(2), Uint256::zero(), vec![]);
+        let error = service
+            .clone()
+            .oneshot(Command::SubmitTx(transfer))
+            .await
+            .unwrap_err();
+
+        let Response::Error { code, message } = Response::from(error) else {
+            unreachable!();
+        };
+        assert_eq!(code, ErrorCode::MempoolFull);
+        assert_eq!(message, "mempool full error");
+    }
+
+    #[tokio::test]
+    async fn test_block_and_chain_queries() {
+        let (_node, service) = test_dispatcher(&test_config());
+
+        for tag in 1..=3u8 {
+            service
+                .clone()
+                .oneshot(Command::MineBlock([tag; 32].into()))
+                .await
+                .unwrap();
+        }
+
+        let transfer = Transaction::new(addr(1), addr(2), Uint256::zero(), vec![]);
+        service
+            .clone()
+            .oneshot(Command::SubmitTx(transfer))
+            .await
+            .unwrap();
+
+        let query = |cmd| {
+            let service = service.clone();
+            async move { service.oneshot(cmd).await }
+        };
+
+        let Ok(Response::Height(3)) = query(Command::GetHeight).await else {
+            panic!("unexpected height");
+        };
+
+        let Ok(Response::Block(latest)) = query(Command::GetLatestBlock).await else {
+            panic!("unexpected latest block");
+        };
+        assert_eq!(latest.id(), 3);
+
+        let Ok(Response::Block(block)) = query(Command::GetBlock(2)).await else {
+            panic!("unexpected block");
+        };
+        assert_eq!(block.header().extra_data, [2u8; 32].into());
+
+        let Ok(Response::Header(header)) = query(Command::GetHeader(3)).await else {
+            panic!("unexpected header");
+        };
+        assert_eq!(header.prev_block, block.get_hash());
+
+        let Ok(Response::Blocks(blocks)) = query(Command::GetBlockRange { from: 1, to: 10 }).await
+        else {
+            panic!("unexpected block range");
+        };
+        let ids: Vec<_> = blocks.iter().map(|block| block.id()).collect();
+        assert_eq!(ids, vec![1, 2, 3]);
+
+        let Ok(Response::Mempool(mempool)) = query(Command::GetMempool).await else {
+            panic!("unexpected mempool status");
+        };
+        assert_eq!(mempool.pending, 1);
+        assert!(!mempool.ready);
+
+        let code = |result: Result<Response, DispatchError>| result.unwrap_err().code();
+        assert_eq!(
+            code(query(Command::GetBlock(4)).await),
+            ErrorCode::BlockNotFound
+        );
+        assert_eq!(
+            code(query(Command::GetBlockRange { from: 0, to: 500 }).await),
+            ErrorCode::InvalidBlockRange
+        );
+    }
+
+    #[tokio::test]
+    async fn test_journal_replays_into_identical_chain() {
+        let path = std::env::temp_dir().join(format!("journal-{}.scale", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let genesis = || {
+            let node =
+                NodeManager::genesis_with_storage(StorageManager::in_memory().unwrap()).unwrap();
+            node.mint(&addr(1), &Uint256::from(1000)).unwrap();
+            Arc::new(node)
+        };
+
+        let node = genesis();
+        let cfg = DispatcherConfig {
+            journal: Some(Arc::new(Journal::open(&path).unwrap())),
+            ..Default::default()
+        };
+        let service = build_dispatcher(Dispatcher::new(node.clone()), &cfg);
+
+        let transfer = |to: u8, amount: u64| {
+            Command::SubmitTx(Transaction::new(
+                addr(1),
+                addr(to),
+                Uint256::from(amount),
+                vec![],
+            ))
+        };
+
+        for cmd in [
+            Command::StartProducer,
+            Command::StopProducer,
+            transfer(2, 100),
+            transfer(3, 50),
+            Command::MineBlock([1u8; 32].into()),
+            Command::GetBalance(addr(2)),
+            Command::SubmitTx(Transaction::multi_transfer(addr(1), vec![])),
+            transfer(2, 25),
+            Command::MineBlock([2u8; 32].into()),
+        ] {
+            let _ = service.clone().oneshot(cmd).await;
+        }
+
+        let entries = read_journal(&path).unwrap();
+        assert_eq!(entries.len(), 9);
+        assert!(matches!(entries[4].command, Command::MineBlock(_)));
+        assert!(
+            entries
+                .windows(2)
+                .all(|w| w[0].timestamp_ms <= w[1].timestamp_ms)
+        );
+
+        let replayed = genesis();
+        let report = replay(entries, Dispatcher::new(replayed.clone())).await;
+        assert_eq!(
+            report,
+            ReplayReport {
+                applied: 6,
+                failed: 1,
+                skipped: 2,
+            }
+        );
+
+        let head = |node: &NodeManager| node.get_latest_block().unwrap().unwrap().get_hash();
+        assert_eq!(replayed.height(), 2);
+        assert_eq!(head(&replayed), head(&node));
+
+        for id in 1..=3 {
+            let balance = |node: &NodeManager| {
+                node.storage()
+                    .get_ref(TableId::Balance)
+                    .to_balance()
+                    .get_or_default(&addr(id))
+                    .unwrap()
+            };
+            assert_eq!(balance(&replayed), balance(&node));
+        }
+
+        // a torn write at the end is reported instead of replayed
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes.extend([0x10, 0x00]);
+        std::fs::write(&path, bytes).unwrap();
+        assert!(matches!(
+            read_journal(&path),
+            Err(JournalError::Corrupted { index: 9, .. })
+        ));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_journal_replays_into_identical_chain() {
+        let path =
+            std::env::temp_dir().join(format!("journal-concurrent-{}.scale", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let genesis = || {
+            let node =
+                NodeManager::genesis_with_storage(StorageManager::in_memory().unwrap()).unwrap();
+            node.mint(&addr(1), &Uint256::from(1000)).unwrap();
+            Arc::new(node)
+        };
+
+        let node = genesis();
+        let cfg = DispatcherConfig {
+            journal: Some(Arc::new(Journal::open(&path).unwrap())),
+            ..Default::default()
+        };
+        let service = build_dispatcher(Dispatcher::new(node.clone()), &cfg);
+
+        let tasks: Vec<_> = (0..240u8)
+            .map(|i| {
+                let cmd = match i % 4 {
+                    3 => Command::MineBlock([i; 32].into()),
+                    2 => Command::GetBalance(addr(2)),
+                    _ => Command::SubmitTx(Transaction::new(
+                        addr(1),
+                        addr(2 + i % 8),
+                        Uint256::from(1 + i as u64),
+                        vec![],
+                    )),
+                };
+                tokio::spawn(service.clone().oneshot(cmd))
+            })
+            .collect();
+
+        for task in tasks {
+            let _ = task.await.unwrap();
+        }
+
+        let replayed = genesis();
+        let report = replay(
+            read_journal(&path).unwrap(),
+            Dispatcher::new(replayed.clone()),
+        )
+        .await;
+        assert_eq!(report.applied + report.failed, 240);
+
+        let head = |node: &NodeManager| node.get_latest_block().unwrap().unwrap().get_hash();
+        assert_eq!(replayed.height(), node.height());
+        assert_eq!(head(&replayed), head(&node));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_batch_runs_commands_in_order() {
+        let (node, service) = test_dispatcher(&Default::default());
+        node.mint(&addr(1), &Uint256::from(1000)).unwrap();
+
+        let batch = Command::Batch(vec![
+            Command::GetBalance(addr(1)),
+            Command::GetHeight,
+            Command::GetBlock(5),
+            Command::Batch(vec![]),
+            Command::SubmitTx(Transaction::new(
+                addr(1),
+                addr(2),
+                Uint256::from(100),
+                vec![],
+            )),
+            Command::MineBlock([1u8; 32].into()),
+            Command::GetBalance(addr(2)),
+            Command::GetLatestBlock,
+        ]);
+
+        let Ok(Response::Batch(results)) = service.clone().oneshot(batch).await else {
+            panic!("unexpected batch response");
+        };
+        assert_eq!(results.len(), 8);
+
+        let code =
+            |result: &Result<Response, DispatchError>| result.as_ref().err().map(|e| e.code());
+
+        assert!(
+            matches!(&results[0], Ok(Response::GetBalance(balance)) if *balance == Uint256::from(1000))
+        );
+        assert!(matches!(results[1], Ok(Response::Height(0))));
+        assert_eq!(code(&results[2]), Some(ErrorCode::BlockNotFound));
+        assert_eq!(code(&results[3]), Some(ErrorCode::InvalidBatch));
+        assert!(matches!(results[4], Ok(Response::Ok)));
+        assert!(matches!(results[5], Ok(Response::Ok)));
+
+        // reads after a mined block see it
+        assert!(
+            matches!(&results[6], Ok(Response::GetBalance(balance)) if *balance == Uint256::from(100))
+        );
+        assert!(matches!(&results[7], Ok(Response::Block(block)) if block.id() == 1));
+
+        let oversized = Command::Batch((0..=MAX_BATCH_SIZE).map(|_| Command::GetHeight).collect());
+        let Err(e) = service.oneshot(oversized).await else {
+            panic!("oversized batch accepted");
+        };
+        assert_eq!(e.code(), ErrorCode::InvalidBatch);
+    }
 }
@@ -0,0 +1,217 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use node::manager::NodeManager;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+/// Upper bounds, in seconds, of the command latency histogram buckets.
+pub const LATENCY_BUCKETS: [f64; 10] =
+    [0.0005, 0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.5, 1.0, 5.0];
+
+// largest request head read before answering a scrape
+const MAX_REQUEST_SIZE: usize = 8 * 1024;
+
+#[derive(Debug, Default, Clone)]
+struct CommandStats {
+    count: u64,
+    errors: u64,
+    // cumulative counts of the `LATENCY_BUCKETS`
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    latency_sum: f64,
+}
+
+/// Per command counters and latency histograms filled by
+/// [`crate::layers::metrics::MetricsLayer`], rendered together with node gauges.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    commands: Mutex<BTreeMap<&'static str, CommandStats>>,
+}
+
+impl Metrics {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    pub fn record(&self, command: &'static str, latency: Duration, success: bool) {
+        let mut commands = self.commands.lock().expect("metrics lock poisoned");
+        let stats = commands.entry(command).or_default();
+
+        let seconds = latency.as_secs_f64();
+
+        stats.count += 1;
+        stats.latency_sum += seconds;
+
+        if !success {
+            stats.errors += 1;
+        }
+
+        for (bucket, bound) in stats.buckets.iter_mut().zip(LATENCY_BUCKETS) {
+            if seconds <= bound {
+                *bucket += 1;
+            }
+        }
+    }
+
+    /// Number of calls and failed calls of `command` so far.
+    pub fn command_count(&self, command: &str) -> (u64, u64) {
+        self.commands
+            .lock()
+            .expect("metrics lock poisoned")
+            .get(command)
+            .map_or((0, 0), |stats| (stats.count, stats.errors))
+    }
+
+    /// Prometheus text exposition of the command metrics and the gauges of `node`.
+    pub fn render(&self, node: &NodeManager) -> String {
+        let commands = self.commands.lock().expect("metrics lock poisoned").clone();
+        let mut out = String::new();
+
+        write_family(
+            &mut out,
+            "dispatcher_commands_total",
+            "counter",
+            "Commands handled by the dispatcher.",
+        );
+        for (command, stats) in &commands {
+            let _ = writeln!(
+                out,
+                "dispatcher_commands_total{{command=\"{command}\"}} {}",
+                stats.count
+            );
+        }
+
+        write_family(
+            &mut out,
+            "dispatcher_command_errors_total",
+            "counter",
+            "Commands that failed, including rejected and timed out ones.",
+        );
+        for (command, stats) in &commands {
+            let _ = writeln!(
+                out,
+                "dispatcher_command_errors_total{{command=\"{command}\"}} {}",
+                stats.errors
+            );
+        }
+
+        write_family(
+            &mut out,
+            "dispatcher_command_duration_seconds",
+            "histogram",
+            "Time taken to handle a command.",
+        );
+        for (command, stats) in &commands {
+            for (bound, count) in LATENCY_BUCKETS.iter().zip(stats.buckets) {
+                let _ = writeln!(
+                    out,
+                    "dispatcher_command_duration_seconds_bucket{{command=\"{command}\",le=\"{bound}\"}} {count}"
+                );
+            }
+
+            let _ = writeln!(
+                out,
+                "dispatcher_command_duration_seconds_bucket{{command=\"{command}\",le=\"+Inf\"}} {}\n\
+                 dispatcher_command_duration_seconds_sum{{command=\"{command}\"}} {}\n\
+                 dispatcher_command_duration_seconds_count{{command=\"{command}\"}} {}",
+                stats.count, stats.latency_sum, stats.count
+            );
+        }
+
+        let height = node.height();
+        let block_time = node.last_block_time().unwrap_or_default();
+
+        let gauges = [
+            ("node_height", "Id of the head block.", height as f64),
+            (
+                "node_finalized_height",
+                "Id of the highest final block.",
+                node.get_finalized_height() as f64,
+            ),
+            (
+                "node_mempool_size",
+                "Transactions waiting in the mempool.",
+                node.mempool().len() as f64,
+            ),
+            ("node_peers", "Known peers.", node.peer_pool().len() as f64),
+            (
+                "node_block_time_seconds",
+                "Time between the imports of the last two blocks.",
+                block_time.as_secs_f64(),
+            ),
+        ];
+
+        for (name, help, value) in gauges {
+            write_family(&mut out, name, "gauge", help);
+            let _ = writeln!(out, "{name} {value}");
+        }
+
+        out
+    }
+}
+
+fn write_family(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}\n# TYPE {name} {kind}");
+}
+
+/// Answers `GET /metrics` on `listener` with [`Metrics::render`] until the task
+/// is dropped.
+pub async fn serve_metrics(listener: TcpListener, metrics: Arc<Metrics>, node: Arc<NodeManager>) {
+    loop {
+        let (stream, _) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                tracing::warn!(error = %e, "metrics accept failed");
+                continue;
+            }
+        };
+
+        let metrics = metrics.clone();
+        let node = node.clone();
+
+        tokio::spawn(async move {
+            if let Err(e) = handle_scrape(stream, &metrics, &node).await {
+                tracing::debug!(error = %e, "metrics scrape failed");
+            }
+        });
+    }
+}
+
+async fn handle_scrape(
+    mut stream: TcpStream,
+    metrics: &Metrics,
+    node: &NodeManager,
+) -> std::io::Result<()> {
+    let mut request = Vec::new();
+    let mut buf = [0u8; 1024];
+
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+        let read = stream.read(&mut buf).await?;
+
+        if read == 0 || request.len() + read > MAX_REQUEST_SIZE {
+            return Ok(());
+        }
+
+        request.extend_from_slice(&buf[..read]);
+    }
+
+    let (status, body) = if request.starts_with(b"GET /metrics ") {
+        ("200 OK", metrics.render(node))
+    } else {
+        ("404 Not Found", String::new())
+    };
+
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
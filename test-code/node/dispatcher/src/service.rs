@@ -4,45 +4,116 @@ use std::sync::Arc;
 use std::task::{Context, Poll};
 use std::time::Duration;
 
-use node::manager::NodeManager;
+use config::get_config;
+use node::{
+    error::NodeError,
+    manager::NodeManager,
+    producer::{self, BlockProducer},
+};
+use rm_reth_types::log::LogFilter;
 use storage::TableId;
+use tower::load_shed::error::Overloaded;
 use tower::timeout::TimeoutLayer;
+use tower::timeout::error::Elapsed;
 use tower::{BoxError, Service, ServiceBuilder};
 
+use crate::batch;
 use crate::command::{Command, Response};
+use crate::error::DispatchError;
+use crate::journal::{Journal, JournalLayer};
 use crate::layers::logging::LoggingLayer;
+use crate::layers::metrics::MetricsLayer;
+use crate::layers::rate_limit::{RateLimitLayer, RateLimits};
+use crate::metrics::Metrics;
 
 pub struct DispatcherConfig {
-    // pub concurrency_limit: usize,
+    /// Commands handled at once by all clones of the dispatcher, the ones past it
+    /// are shed.
+    pub concurrency_limit: usize,
     pub timeout: Duration,
+    pub rate_limits: RateLimits,
+    /// Registry the command metrics are recorded into, if any.
+    pub metrics: Option<Arc<Metrics>>,
+    /// Journal the commands reaching the dispatcher are appended to, if any.
+    pub journal: Option<Arc<Journal>>,
 }
 
-// TODO: error handler
+impl Default for DispatcherConfig {
+    fn default() -> Self {
+        Self {
+            concurrency_limit: 256,
+            timeout: Duration::from_secs(10),
+            rate_limits: RateLimits::default(),
+            metrics: None,
+            journal: None,
+        }
+    }
+}
+
+/// Stacks the dispatcher layers. Commands past the rate limit of their class or
+/// the concurrency limit fail right away with [`DispatchError::Overloaded`], and
+/// the ones running past the timeout with [`DispatchError::Timeout`].
+///
+/// Every failure comes out as a [`DispatchError`], whose
+/// [`code`](DispatchError::code) transports forward.
 pub fn build_dispatcher(
     dispatcher: Dispatcher,
     cfg: &DispatcherConfig,
-) -> impl Service<Command, Response = Response, Error = BoxError> + Clone {
+) -> impl Service<Command, Response = Response, Error = DispatchError, Future: Send> + Clone + Send + use<>
+{
     ServiceBuilder::new()
+        .map_err(into_dispatch_error)
+        .option_layer(cfg.metrics.clone().map(MetricsLayer::new))
         .layer(LoggingLayer)
-        // .layer(ConcurrencyLimitLayer::new(cfg.concurrency_limit))
+        .layer(RateLimitLayer::new(&cfg.rate_limits))
+        .load_shed()
+        .concurrency_limit(cfg.concurrency_limit)
         .layer(TimeoutLayer::new(cfg.timeout))
+        .option_layer(cfg.journal.clone().map(JournalLayer::new))
         .service(dispatcher)
 }
 
+fn into_dispatch_error(error: BoxError) -> DispatchError {
+    if error.is::<Overloaded>() {
+        return DispatchError::Overloaded;
+    }
+
+    if error.is::<Elapsed>() {
+        return DispatchError::Timeout;
+    }
+
+    match error.downcast::<DispatchError>() {
+        Ok(error) => *error,
+        Err(error) => DispatchError::Inner(anyhow::anyhow!(error)),
+    }
+}
+
 #[derive(Clone)]
 pub struct Dispatcher {
     node: Arc<NodeManager>,
+    producer: Arc<BlockProducer>,
 }
 
 impl Dispatcher {
     pub fn new(node: Arc<NodeManager>) -> Self {
-        Self { node }
+        let producer = BlockProducer::new(node.clone(), get_config().producer.clone());
+
+        Self {
+            node,
+            producer: Arc::new(producer),
+        }
+    }
+
+    /// Replaces the block producer `StartProducer` and `StopProducer` control.
+    pub fn with_producer(mut self, producer: BlockProducer) -> Self {
+        self.producer = Arc::new(producer);
+        self
     }
 }
 
 impl Service<Command> for Dispatcher {
     type Response = Response;
-    type Error = anyhow::Error;
+    type Error = DispatchError;
     type Future = Pin<Box<dyn Future<Output = Result<Response, Self::Error>> + Send>>;
 
     fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
@@ -51,18 +122,42 @@ impl Service<Command> for Dispatcher {
 
     fn call(&mut self, cmd: Command) -> Self::Future {
         let node = self.node.clone();
+        let producer = self.producer.clone();
 
-        Box::pin(async move { handle_command(cmd, &node) })
+        Box::pin(async move { handle_command(cmd, &node, &producer).await })
+    }
+}
+
+pub async fn handle_command(
+    cmd: Command,
+    node: &NodeManager,
+    producer: &BlockProducer,
+) -> Result<Response, DispatchError> {
+    match cmd {
+        Command::Batch(commands) => Ok(Response::Batch(
+            batch::handle_batch(commands, node, producer).await?,
+        )),
+        cmd => handle_single(cmd, node, producer).await,
     }
 }
 
-pub fn handle_command(cmd: Command, node: &NodeManager) -> Result<Response, anyhow::Error> {
+/// Handles every command but [`Command::Batch`], which fails with
+/// [`DispatchError::NestedBatch`].
+pub(crate) async fn handle_single(
+    cmd: Command,
+    node: &NodeManager,
+    producer: &BlockProducer,
+) -> Result<Response, DispatchError> {
     match cmd {
         // transection
         Command::SubmitTx(tx) => {
             node.push_transaction(tx)?;
             Ok(Response::Ok)
         }
+        Command::SimulateTx(tx) => {
+            let simulation = node.simulate_transaction(&tx)?;
+            Ok(Response::Simulation(simulation))
+        }
         // Command::ValidateTransaction(tx) => {
         //     Ok(Response::Ok)
         // },
@@ -91,17 +186,78 @@ pub fn handle_command(cmd: Command, node: &NodeManager) -> Result<Response, anyh
 
             Ok(Response::GetNonce(nonce))
         }
+        Command::GetLogs {
+            from,
+            to,
+            address,
+            topics,
+        } => {
+            let filter = LogFilter {
+                from,
+                to,
+                address,
+                topics,
+            };
+
+            Ok(Response::Logs(node.get_logs(&filter)?))
+        }
+        Command::GetAsset(asset) => Ok(Response::Asset(node.get_asset(&asset)?)),
+        Command::GetAssetBalance { address, asset } => {
+            let balance = node.get_asset_balance(&address, &asset)?;
+
+            Ok(Response::GetAssetBalance(balance))
+        }
+        Command::GetTotalSupply(asset) => Ok(Response::TotalSupply(node.get_total_supply(&asset)?)),
+        Command::GetHtlc(id) => Ok(Response::Htlc(node.get_htlc(&id)?)),
+        Command::GetOpenHtlcs(address) => Ok(Response::Htlcs(node.get_open_htlcs(&address)?)),
+        Command::GetVesting(address) => Ok(Response::Vesting(node.get_vesting_status(&address)?)),
+        Command::GetStake(address) => Ok(Response::Stake(node.get_stake(&address)?)),
+        Command::GetValidators => Ok(Response::Validators(node.current_validators()?)),
+        Command::GetNextValidators => Ok(Response::Validators(node.next_validators()?)),
+        Command::GetFinalizedHeight => Ok(Response::FinalizedHeight(node.get_finalized_height())),
+        Command::GetBlock(height) => {
+            let block = node
+                .get_block(height)?
+                .ok_or(NodeError::BlockNotExist(height))?;
+
+            Ok(Response::Block(Box::new(block)))
+        }
+        Command::GetLatestBlock => {
+            let height = node.height();
+            let block = node
+                .get_latest_block()?
+                .ok_or(NodeError::BlockNotExist(height))?;
+
+            Ok(Response::Block(Box::new(block)))
+        }
+        Command::GetHeight => Ok(Response::Height(node.height())),
+        Command::GetHeader(height) => {
+            let header = node
+                .get_header(height)?
+                .ok_or(NodeError::BlockNotExist(height))?;
+
+            Ok(Response::Header(header))
+        }
+        Command::GetBlockRange { from, to } => {
+            Ok(Response::Blocks(node.get_block_range(from, to)?))
+        }
+        Command::GetMempool => Ok(Response::Mempool(node.mempool_status())),
         // Command::QueryStateRoot() => {},
 
         // node
         Command::MineBlock(extra_data) => {
-            let tx_pool = node.process_execution_transaction()?;
-
-            let block = node.create_block_with_processed_tx_pool(tx_pool);
-
-            node.mine_with_block(block, extra_data)?;
+            producer::mine_block(node, extra_data)?;
 
+            Ok(Response::Ok)
+        }
+        Command::StartProducer => {
+            producer.start();
+            Ok(Response::Ok)
+        }
+        Command::StopProducer => {
+            producer.stop().await;
             Ok(Response::Ok)
         } // Command::SyncPeer(PeerId),
+        Command::Batch(_) => Err(DispatchError::NestedBatch),
     }
 }
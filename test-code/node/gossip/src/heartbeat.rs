@@ -2,17 +2,25 @@ use std::{sync::Arc, time::Duration};
 
 use moka::sync::Cache;
 
-use rm_reth_types::peers::{PeerId, PeerPool};
+use node::manager::NodeManager;
+use rm_reth_types::peers::PeerId;
 
+/// Peers heard from within the last heartbeat interval.
+///
+/// Peers whose heartbeat expires are disconnected from the node, which announces
+/// them as [`node::events::NodeEvent::PeerDisconnected`].
 pub struct PeerHeartBeat(pub Cache<PeerId, ()>);
 
 impl PeerHeartBeat {
-    pub fn new(pool: Arc<PeerPool>, max_capacity: u64, heartbeat_interval: Duration) -> Self {
+    pub fn new(node: Arc<NodeManager>, max_capacity: u64, heartbeat_interval: Duration) -> Self {
         let cache: Cache<u32, _> = Cache::builder()
             .max_capacity(max_capacity)
             .time_to_live(heartbeat_interval)
-            .eviction_listener(move |peer_id, _, _| {
-                pool.remove(&peer_id);
+            .eviction_listener(move |peer_id: Arc<PeerId>, _, cause| {
+                // refreshing a heartbeat replaces the entry
+                if cause.was_evicted() {
+                    node.disconnect_peer(*peer_id);
+                }
             })
             .build();
 
@@ -21,17 +29,20 @@ impl PeerHeartBeat {
         Self(cache)
     }
 
-    pub fn sync_data_with_pool(&self, pool: &PeerPool) {
+    /// Disconnects the peers of `node` without a live heartbeat.
+    pub fn sync_data_with_pool(&self, node: &NodeManager) {
+        self.0.run_pending_tasks();
+
         let mut remove_ids: Vec<PeerId> = vec![];
 
-        for peer in pool.iter() {
+        for peer in node.peer_pool().iter() {
             if self.0.get(peer.key()).is_none() {
                 remove_ids.push(peer.key().clone());
             }
         }
 
         for id in remove_ids {
-            pool.remove(&id);
+            node.disconnect_peer(id);
         }
     }
 }
@@ -2,6 +2,7 @@ pub mod config;
 pub mod heartbeat;
 pub mod manager;
 pub mod message;
+pub mod relay;
 pub mod selector;
 
 // mod test {
@@ -1,21 +1,43 @@
-use rm_reth_types::peers::{Peer, PeerPool};
+use std::sync::Arc;
 
-use crate::message::{GossipMessage, MessageCache};
+use node::manager::NodeManager;
+use rm_reth_types::peers::{Peer, PeerId};
+use tokio::{sync::mpsc, task::JoinHandle};
+
+use crate::{
+    config::GossipConfig,
+    heartbeat::PeerHeartBeat,
+    message::{GossipMessage, MessageCache},
+    relay::{Announcement, relay_events},
+};
 
 pub struct GossipManager {
-    peer_pool: PeerPool,
+    node: Arc<NodeManager>,
     message_cache: MessageCache,
     // peer_selector: PeerSelector,
-    // heartbeat: PeerHeartBeat,
+    heartbeat: PeerHeartBeat,
     // config: Arc<GossipConfig>,
 }
 
 impl GossipManager {
+    pub fn new(node: Arc<NodeManager>, config: &GossipConfig) -> Self {
+        Self {
+            message_cache: MessageCache::new(config.cache_size, config.max_age),
+            heartbeat: PeerHeartBeat::new(
+                node.clone(),
+                config.max_peers as u64,
+                config.heartbeat_interval,
+            ),
+            node,
+        }
+    }
+
     pub async fn broadcast_peers(&mut self, content: Peer) -> (Vec<Peer>, GossipMessage<Peer>) {
         let msg = GossipMessage::new(content);
 
         let peers = self
-            .peer_pool
+            .node
+            .peer_pool()
             .iter()
             .map(|p| (p.key().clone(), p.value().clone()))
             .collect();
@@ -23,19 +45,135 @@ impl GossipManager {
         (peers, msg)
     }
 
+    /// Connects the node to the peer announced in `msg`, which also counts as
+    /// its heartbeat.
     pub async fn handle_received(&mut self, msg: GossipMessage<Peer>) {
         if !self.message_cache.should_process_message(&msg.id) {
             return;
         }
 
-        if msg.should_forward() {
+        // too old to tell whether the peer is still there
+        if !msg.should_forward() {
+            return;
+        }
+
+        self.update_heartbeat(msg.content.0);
+        self.node.connect_peer(msg.content);
+    }
+
+    /// Hands the consensus messages among `msg` to the node, the rest only
+    /// tells what peers may be asked for.
+    pub async fn handle_announcement(&mut self, msg: GossipMessage<Announcement>) {
+        if !self.message_cache.should_process_message(&msg.id) {
             return;
         }
 
-        self.peer_pool.insert(msg.content.0, msg.content.1);
+        if let Announcement::Consensus(message) = msg.content {
+            self.node.receive_consensus_message(message);
+        }
+    }
+
+    /// Sends the node events worth announcing to `out`, see [`relay_events`].
+    pub fn spawn_relay(&self, out: mpsc::Sender<GossipMessage<Announcement>>) -> JoinHandle<()> {
+        tokio::spawn(relay_events(self.node.events().subscribe(), out))
+    }
+
+    pub fn update_heartbeat(&self, id: PeerId) {
+        self.heartbeat.0.insert(id, ());
     }
 
-    // pub fn update_heartbeat(&self, id: PeerId) {
-    //     self.heartbeat.0.insert(id, ());
-    // }
+    /// Disconnects the peers not heard from within the heartbeat interval.
+    pub fn expire_peers(&self) {
+        self.heartbeat.sync_data_with_pool(&self.node);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use node::{events::NodeEvent, producer::produce_block};
+    use rm_reth_types::{
+        Address, int::Uint256, socket::SocketAddrCodec, tx::transaction::Transaction,
+    };
+    use storage::StorageManager;
+    use tokio::time::{sleep, timeout};
+
+    use super::*;
+
+    fn config() -> GossipConfig {
+        GossipConfig {
+            cache_size: 100,
+            max_age: Duration::from_secs(60),
+            fanout_size: 3,
+            max_peers: 16,
+            heartbeat_interval: Duration::from_millis(50),
+        }
+    }
+
+    fn node() -> Arc<NodeManager> {
+        Arc::new(NodeManager::genesis_with_storage(StorageManager::in_memory().unwrap()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn relays_node_events_as_announcements() {
+        let node = node();
+        let manager = GossipManager::new(node.clone(), &config());
+
+        let (out, mut announcements) = mpsc::channel(16);
+        let relay = manager.spawn_relay(out);
+
+        let tx = Transaction::new(
+            Address::from([1; 20]),
+            Address::from([2; 20]),
+            Uint256::zero(),
+            vec![],
+        );
+        node.push_transaction(tx.clone()).unwrap();
+        // peers are not announced
+        node.connect_peer((7, SocketAddrCodec("127.0.0.1:30303".parse().unwrap())));
+        produce_block(&node).unwrap();
+
+        let mut next = async || {
+            timeout(Duration::from_secs(5), announcements.recv())
+                .await
+                .expect("no announcement")
+                .unwrap()
+                .content
+        };
+
+        assert!(matches!(next().await, Announcement::NewTransaction(hash) if hash == tx.hash()));
+        assert!(matches!(next().await, Announcement::NewBlock { id: 1, .. }));
+
+        relay.abort();
+    }
+
+    #[tokio::test]
+    async fn gossiped_peers_connect_until_their_heartbeat_expires() {
+        let node = node();
+        let mut manager = GossipManager::new(node.clone(), &config());
+        let mut events = node.events().subscribe();
+
+        let peer = (7, SocketAddrCodec("127.0.0.1:30303".parse().unwrap()));
+        manager.handle_received(GossipMessage::new(peer)).await;
+
+        assert!(matches!(
+            events.try_recv(),
+            Ok(NodeEvent::PeerConnected((7, _)))
+        ));
+
+        manager.expire_peers();
+        assert!(events.try_recv().is_err());
+        assert!(node.peer_pool().contains_key(&7));
+
+        sleep(config().heartbeat_interval * 2).await;
+        manager.expire_peers();
+
+        assert!(matches!(
+            events.try_recv(),
+            Ok(NodeEvent::PeerDisconnected(7))
+        ));
+        assert!(events.try_recv().is_err());
+        assert!(node.peer_pool().is_empty());
+    }
 }
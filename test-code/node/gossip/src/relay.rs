@@ -0,0 +1,57 @@
+use node::{consensus::bft::Message, events::NodeEvent};
+use parity_scale_codec::{Decode, Encode};
+use rm_reth_types::hash::Hash;
+use tokio::sync::{
+    broadcast::{self, error::RecvError},
+    mpsc,
+};
+
+use crate::message::GossipMessage;
+
+/// Node event announced to peers.
+#[derive(Debug, Clone, Encode, Decode)]
+pub enum Announcement {
+    NewBlock { id: u64, hash: Hash },
+    NewTransaction(Hash),
+    Consensus(Message),
+}
+
+impl Announcement {
+    pub fn from_event(event: &NodeEvent) -> Option<Self> {
+        match event {
+            NodeEvent::NewBlock(block) => Some(Announcement::NewBlock {
+                id: block.id(),
+                hash: block.get_hash(),
+            }),
+            NodeEvent::TxAccepted(hash) => Some(Announcement::NewTransaction(*hash)),
+            NodeEvent::Consensus(message) => Some(Announcement::Consensus((**message).clone())),
+            _ => None,
+        }
+    }
+}
+
+/// Turns the node `events` worth announcing into gossip messages for `out`,
+/// until either side closes.
+///
+/// Announcements are best effort: those missed while lagging behind are
+/// skipped, peers catch up on the next block.
+pub async fn relay_events(
+    mut events: broadcast::Receiver<NodeEvent>,
+    out: mpsc::Sender<GossipMessage<Announcement>>,
+) {
+    loop {
+        let event = match events.recv().await {
+            Ok(event) => event,
+            Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => return,
+        };
+
+        let Some(announcement) = Announcement::from_event(&event) else {
+            continue;
+        };
+
+        if out.send(GossipMessage::new(announcement)).await.is_err() {
+            return;
+        }
+    }
+}
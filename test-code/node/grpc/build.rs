@@ -0,0 +1,13 @@
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // a protoc is shipped with the build, none has to be installed
+    let mut config = prost_build::Config::new();
+    config.protoc_executable(protoc_bin_vendored::protoc_bin_path()?);
+
+    tonic_prost_build::configure().compile_with_config(
+        config,
+        &["proto/rmreth/node/v1/node.proto"],
+        &["proto"],
+    )?;
+
+    Ok(())
+}
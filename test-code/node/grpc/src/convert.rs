@@ -0,0 +1,81 @@
+//! Conversions between the protobuf messages and `rm-reth-types`.
+
+use parity_scale_codec::{Decode, Encode};
+use rm_reth_types::{
+    Address, block::block::Block, hash::Hash, int::Uint256, tx::transaction::Transaction,
+};
+
+use crate::{error::ConvertError, proto};
+
+fn fixed<const N: usize>(field: &'static str, bytes: &[u8]) -> Result<[u8; N], ConvertError> {
+    bytes.try_into().map_err(|_| ConvertError::InvalidLength {
+        field,
+        expected: N,
+        actual: bytes.len(),
+    })
+}
+
+pub fn address(field: &'static str, bytes: &[u8]) -> Result<Address, ConvertError> {
+    fixed::<20>(field, bytes).map(Address::from)
+}
+
+pub fn amount(field: &'static str, bytes: &[u8]) -> Result<Uint256, ConvertError> {
+    fixed::<32>(field, bytes).map(Uint256::from_le_bytes)
+}
+
+pub fn hash(field: &'static str, bytes: &[u8]) -> Result<Hash, ConvertError> {
+    fixed::<32>(field, bytes).map(Into::into)
+}
+
+impl From<&Transaction> for proto::Transaction {
+    fn from(tx: &Transaction) -> Self {
+        Self {
+            from: tx.from.as_slice().to_vec(),
+            to: tx.to.as_slice().to_vec(),
+            amount: tx.amount.to_le_bytes().to_vec(),
+            data: tx.data.clone(),
+        }
+    }
+}
+
+impl TryFrom<proto::Transaction> for Transaction {
+    type Error = ConvertError;
+
+    fn try_from(tx: proto::Transaction) -> Result<Self, Self::Error> {
+        Ok(Transaction::new(
+            address("from", &tx.from)?,
+            address("to", &tx.to)?,
+            amount("amount", &tx.amount)?,
+            tx.data,
+        ))
+    }
+}
+
+impl From<&Block> for proto::Block {
+    fn from(block: &Block) -> Self {
+        let header = block.header();
+
+        Self {
+            hash: block.get_hash().as_slice().to_vec(),
+            header: Some(proto::Header {
+                block_id: header.block_id,
+                prev_block: header.prev_block.as_slice().to_vec(),
+                logs_bloom: header.logs_bloom.as_slice().to_vec(),
+                difficulty: header.difficulty,
+                extra_data: header.extra_data.as_slice().to_vec(),
+            }),
+            transactions: block.data().tx_pool.iter().map(Into::into).collect(),
+            encoded: block.encode(),
+        }
+    }
+}
+
+/// The block is rebuilt from its SCALE encoding, the other fields being views
+/// of it.
+impl TryFrom<proto::Block> for Block {
+    type Error = ConvertError;
+
+    fn try_from(block: proto::Block) -> Result<Self, Self::Error> {
+        Ok(Block::decode(&mut block.encoded.as_slice())?)
+    }
+}
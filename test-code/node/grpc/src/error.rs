@@ -0,0 +1,63 @@
+use dispatcher::error::{DispatchError, ErrorCode};
+use thiserror::Error;
+use tonic::{Code, Status, metadata::MetadataValue};
+
+/// Metadata entry carrying the stable [`ErrorCode`] of a failed call.
+pub const ERROR_CODE_KEY: &str = "rmreth-error-code";
+
+/// Message not convertible into its `rm-reth-types` counterpart.
+#[derive(Debug, Error)]
+pub enum ConvertError {
+    #[error("{field}: expected {expected} bytes, got {actual}")]
+    InvalidLength {
+        field: &'static str,
+        expected: usize,
+        actual: usize,
+    },
+
+    #[error("missing {0}")]
+    Missing(&'static str),
+
+    #[error("block: {0}")]
+    Decode(#[from] parity_scale_codec::Error),
+}
+
+impl From<ConvertError> for Status {
+    fn from(e: ConvertError) -> Self {
+        Status::invalid_argument(e.to_string())
+    }
+}
+
+/// Status of a failed dispatcher call, its [`ErrorCode`] under
+/// [`ERROR_CODE_KEY`].
+pub fn dispatch_status(e: DispatchError) -> Status {
+    let code = e.code();
+
+    let status_code = match code {
+        ErrorCode::Timeout => Code::DeadlineExceeded,
+        ErrorCode::Overloaded | ErrorCode::MempoolFull => Code::ResourceExhausted,
+        ErrorCode::InvalidBatch
+        | ErrorCode::InvalidTransaction
+        | ErrorCode::InvalidLogRange
+        | ErrorCode::InvalidBlockRange => Code::InvalidArgument,
+        ErrorCode::BlockNotFound | ErrorCode::UnknownAsset => Code::NotFound,
+        ErrorCode::InvalidBlock | ErrorCode::Consensus => Code::FailedPrecondition,
+        ErrorCode::BlockPruned => Code::OutOfRange,
+        ErrorCode::Internal | ErrorCode::Storage => Code::Internal,
+    };
+
+    let mut status = Status::new(status_code, e.to_string());
+    status
+        .metadata_mut()
+        .insert(ERROR_CODE_KEY, MetadataValue::from(code.as_u16()));
+
+    status
+}
+
+/// [`ErrorCode`] of a status returned by the node, if it comes from the
+/// dispatcher.
+pub fn error_code(status: &Status) -> Option<ErrorCode> {
+    let code = status.metadata().get(ERROR_CODE_KEY)?.to_str().ok()?;
+
+    ErrorCode::from_u16(code.parse().ok()?)
+}
@@ -0,0 +1,150 @@
+//! gRPC API over the dispatcher, for backend services: transaction submission,
+//! balance, nonce and block queries, and a server-streaming block feed.
+//!
+//! The service is described by `proto/rmreth/node/v1/node.proto`, from which the
+//! [`proto`] messages, the [`NodeClient`] and the [`NodeServer`] are generated.
+
+pub mod convert;
+pub mod error;
+pub mod service;
+
+pub mod proto {
+    tonic::include_proto!("rmreth.node.v1");
+}
+
+pub use proto::{node_client::NodeClient, node_server::NodeServer};
+pub use service::{NodeService, serve_grpc};
+
+#[cfg(test)]
+mod test {
+    use std::{sync::Arc, time::Duration};
+
+    use dispatcher::{
+        command::Command,
+        error::ErrorCode,
+        service::{Dispatcher, build_dispatcher},
+    };
+    use node::manager::NodeManager;
+    use rm_reth_types::{Address, block::block::Block, int::Uint256, tx::transaction::Transaction};
+    use storage::StorageManager;
+    use tokio::{net::TcpListener, time::timeout};
+    use tokio_stream::StreamExt;
+    use tonic::Code;
+    use tower::ServiceExt;
+
+    use crate::{NodeClient, error::error_code, proto, serve_grpc};
+
+    fn addr(id: u8) -> Address {
+        [id; 20].into()
+    }
+
+    #[tokio::test]
+    async fn test_client_round_trip() {
+        let node = NodeManager::genesis_with_storage(StorageManager::in_memory().unwrap()).unwrap();
+        node.mint(&addr(1), &Uint256::from(1000)).unwrap();
+        let node = Arc::new(node);
+        let service = build_dispatcher(Dispatcher::new(node.clone()), &Default::default());
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(serve_grpc(listener, service.clone(), node.clone()));
+
+        let mut client = NodeClient::connect(url).await.unwrap();
+
+        let balance = client
+            .get_balance(proto::AccountRequest {
+                address: addr(1).as_slice().to_vec(),
+            })
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(balance.balance, Uint256::from(1000).to_le_bytes());
+
+        let nonce = client
+            .get_nonce(proto::AccountRequest {
+                address: addr(1).as_slice().to_vec(),
+            })
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(nonce.nonce, 0);
+
+        let status = client
+            .get_balance(proto::AccountRequest {
+                address: vec![1, 2, 3],
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+
+        // dispatcher failures keep their stable code
+        let status = client
+            .get_block(proto::GetBlockRequest { id: Some(7) })
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::NotFound);
+        assert_eq!(error_code(&status), Some(ErrorCode::BlockNotFound));
+
+        let mut feed = client
+            .subscribe_blocks(proto::SubscribeBlocksRequest { from: Some(0) })
+            .await
+            .unwrap()
+            .into_inner();
+
+        let tx = Transaction::new(addr(1), addr(2), Uint256::from(10), vec![]);
+        let submitted = client
+            .submit_transaction(proto::SubmitTransactionRequest {
+                transaction: Some((&tx).into()),
+            })
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(submitted.tx_hash, tx.hash().as_slice());
+        assert_eq!(node.mempool().len(), 1);
+
+        service
+            .oneshot(Command::MineBlock([0u8; 32].into()))
+            .await
+            .unwrap();
+
+        let mut next_block = async || {
+            timeout(Duration::from_secs(5), feed.next())
+                .await
+                .expect("no block within 5s")
+                .unwrap()
+                .unwrap()
+        };
+
+        // the stored blocks come first, the new head follows
+        let genesis = next_block().await;
+        assert_eq!(genesis.header.unwrap().block_id, 0);
+
+        let head = next_block().await;
+        assert_eq!(head.header.as_ref().unwrap().block_id, 1);
+        assert_eq!(head.transactions, vec![proto::Transaction::from(&tx)]);
+
+        let block = Block::try_from(head).unwrap();
+        assert_eq!(
+            block.get_hash(),
+            node.get_block(1).unwrap().unwrap().get_hash()
+        );
+
+        let latest = client
+            .get_block(proto::GetBlockRequest { id: None })
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(latest.hash, block.get_hash().as_slice());
+
+        let balance = client
+            .get_balance(proto::AccountRequest {
+                address: addr(2).as_slice().to_vec(),
+            })
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(balance.balance, Uint256::from(10).to_le_bytes());
+
+        server.abort();
+    }
+}
@@ -0,0 +1,247 @@
+use std::sync::{Arc, Mutex};
+
+use dispatcher::{
+    command::{self, Command},
+    error::DispatchError,
+};
+use node::{
+    events::NodeEvent,
+    manager::{MAX_BLOCK_RANGE, NodeManager},
+};
+use rm_reth_types::tx::transaction::Transaction;
+use tokio::{
+    net::TcpListener,
+    sync::{
+        broadcast::{self, error::RecvError},
+        mpsc,
+    },
+};
+use tokio_stream::wrappers::{ReceiverStream, TcpListenerStream};
+use tonic::{Request, Response, Status, transport::Server};
+use tower::{Service, ServiceExt};
+
+use crate::{
+    convert,
+    error::{ConvertError, dispatch_status},
+    proto::{
+        self,
+        node_server::{Node, NodeServer},
+    },
+};
+
+/// Blocks buffered for a feed subscriber before the node stops waiting for it.
+pub const FEED_BUFFER: usize = 16;
+
+/// [`Node`] gRPC service answering through the dispatcher `service`, the block
+/// feed following the [`NodeManager`] event bus.
+pub struct NodeService<S> {
+    // the dispatcher is not required to be `Sync`, each call works on a clone
+    service: Mutex<S>,
+    node: Arc<NodeManager>,
+}
+
+impl<S> NodeService<S>
+where
+    S: Service<Command, Response = command::Response, Error = DispatchError> + Clone,
+{
+    pub fn new(service: S, node: Arc<NodeManager>) -> Self {
+        Self {
+            service: Mutex::new(service),
+            node,
+        }
+    }
+
+    async fn call(&self, command: Command) -> Result<command::Response, Status> {
+        let service = self
+            .service
+            .lock()
+            .expect("grpc service lock poisoned")
+            .clone();
+
+        service.oneshot(command).await.map_err(dispatch_status)
+    }
+}
+
+fn unexpected(response: command::Response) -> Status {
+    Status::internal(format!("unexpected dispatcher response: {response:?}"))
+}
+
+#[tonic::async_trait]
+impl<S> Node for NodeService<S>
+where
+    S: Service<Command, Response = command::Response, Error = DispatchError>
+        + Clone
+        + Send
+        + 'static,
+    S::Future: Send,
+{
+    async fn submit_transaction(
+        &self,
+        request: Request<proto::SubmitTransactionRequest>,
+    ) -> Result<Response<proto::SubmitTransactionResponse>, Status> {
+        let tx: Transaction = request
+            .into_inner()
+            .transaction
+            .ok_or(ConvertError::Missing("transaction"))?
+            .try_into()?;
+        let tx_hash = tx.hash().as_slice().to_vec();
+
+        self.call(Command::SubmitTx(tx)).await?;
+
+        Ok(Response::new(proto::SubmitTransactionResponse { tx_hash }))
+    }
+
+    async fn get_balance(
+        &self,
+        request: Request<proto::AccountRequest>,
+    ) -> Result<Response<proto::BalanceResponse>, Status> {
+        let address = convert::address("address", &request.get_ref().address)?;
+
+        match self.call(Command::GetBalance(address)).await? {
+            command::Response::GetBalance(balance) => Ok(Response::new(proto::BalanceResponse {
+                balance: balance.to_le_bytes().to_vec(),
+            })),
+            response => Err(unexpected(response)),
+        }
+    }
+
+    async fn get_nonce(
+        &self,
+        request: Request<proto::AccountRequest>,
+    ) -> Result<Response<proto::NonceResponse>, Status> {
+        let address = convert::address("address", &request.get_ref().address)?;
+
+        match self.call(Command::GetNonce(address)).await? {
+            command::Response::GetNonce(nonce) => Ok(Response::new(proto::NonceResponse { nonce })),
+            response => Err(unexpected(response)),
+        }
+    }
+
+    async fn get_block(
+        &self,
+        request: Request<proto::GetBlockRequest>,
+    ) -> Result<Response<proto::Block>, Status> {
+        let command = match request.get_ref().id {
+            Some(id) => Command::GetBlock(id),
+            None => Command::GetLatestBlock,
+        };
+
+        match self.call(command).await? {
+            command::Response::Block(block) => Ok(Response::new(block.as_ref().into())),
+            response => Err(unexpected(response)),
+        }
+    }
+
+    type SubscribeBlocksStream = ReceiverStream<Result<proto::Block, Status>>;
+
+    async fn subscribe_blocks(
+        &self,
+        request: Request<proto::SubscribeBlocksRequest>,
+    ) -> Result<Response<Self::SubscribeBlocksStream>, Status> {
+        // subscribed before the backfill, no block can fall in between
+        let events = self.node.events().subscribe();
+        let service = self
+            .service
+            .lock()
+            .expect("grpc service lock poisoned")
+            .clone();
+        let from = request.get_ref().from;
+
+        let (sender, receiver) = mpsc::channel(FEED_BUFFER);
+
+        tokio::spawn(async move {
+            if let Err(status) = feed_blocks(from, events, service, &sender).await {
+                let _ = sender.send(Err(status)).await;
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(receiver)))
+    }
+}
+
+/// Streams the stored blocks from `from`, then every new head, until the
+/// subscriber goes away.
+async fn feed_blocks<S>(
+    from: Option<u64>,
+    mut events: broadcast::Receiver<NodeEvent>,
+    service: S,
+    sender: &mpsc::Sender<Result<proto::Block, Status>>,
+) -> Result<(), Status>
+where
+    S: Service<Command, Response = command::Response, Error = DispatchError> + Clone,
+{
+    let mut next = from;
+
+    while let Some(from) = next {
+        let command = Command::GetBlockRange {
+            from,
+            to: from.saturating_add(MAX_BLOCK_RANGE - 1),
+        };
+
+        let blocks = match service.clone().oneshot(command).await {
+            Ok(command::Response::Blocks(blocks)) => blocks,
+            Ok(response) => return Err(unexpected(response)),
+            Err(e) => return Err(dispatch_status(e)),
+        };
+
+        for block in &blocks {
+            if sender.send(Ok(block.into())).await.is_err() {
+                return Ok(());
+            }
+        }
+
+        if let Some(last) = blocks.last() {
+            next = Some(last.id() + 1);
+        }
+
+        // the range is cut at the head, a short one reached it
+        if (blocks.len() as u64) < MAX_BLOCK_RANGE {
+            break;
+        }
+    }
+
+    loop {
+        match events.recv().await {
+            Ok(NodeEvent::NewBlock(block)) => {
+                // already sent by the backfill
+                if next.is_some_and(|next| block.id() < next) {
+                    continue;
+                }
+
+                next = Some(block.id() + 1);
+
+                if sender.send(Ok(block.as_ref().into())).await.is_err() {
+                    return Ok(());
+                }
+            }
+            // the blocks of the new branch are sent from the fork on
+            Ok(NodeEvent::Reorg { ancestor, .. }) => next = Some(ancestor + 1),
+            Ok(_) => {}
+            Err(RecvError::Lagged(missed)) => {
+                return Err(Status::data_loss(format!(
+                    "lagging behind, {missed} events missed"
+                )));
+            }
+            Err(RecvError::Closed) => return Ok(()),
+        }
+    }
+}
+
+/// Serves the [`Node`] gRPC service on connections accepted on `listener`.
+pub async fn serve_grpc<S>(
+    listener: TcpListener,
+    service: S,
+    node: Arc<NodeManager>,
+) -> Result<(), tonic::transport::Error>
+where
+    S: Service<Command, Response = command::Response, Error = DispatchError>
+        + Clone
+        + Send
+        + 'static,
+    S::Future: Send,
+{
+    Server::builder()
+        .add_service(NodeServer::new(NodeService::new(service, node)))
+        .serve_with_incoming(TcpListenerStream::new(listener))
+        .await
+}
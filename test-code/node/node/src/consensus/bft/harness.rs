@@ -0,0 +1,404 @@
+//! Deterministic in-process network of BFT validators.
+//!
+//! Messages are delivered after a pseudo-random delay drawn from a seed and
+//! timeouts fire on a logical clock, so a run only depends on its parameters.
+
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet},
+    time::Duration,
+};
+
+use k256::ecdsa::SigningKey;
+use rm_reth_types::{
+    block::block::Block,
+    finality::{CommitCertificate, ValidatorSet, Vote, VoteKind},
+    hash::Hash,
+    multisig::PublicKey,
+};
+use storage::StorageManager;
+
+use crate::{
+    consensus::{
+        ConsensusError,
+        bft::{Bft, BftTimeouts, Message, Output, Proposal, Tendermint, Timeout},
+    },
+    error::NodeError,
+    manager::NodeManager,
+};
+
+// logical milliseconds after which a run is considered stuck
+const TIME_LIMIT: u64 = 10 * 60 * 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Behaviour {
+    Honest,
+    /// Never sends nor handles anything.
+    Crashed,
+    /// Proposes different blocks to different validators and votes both for and
+    /// against every proposal it sees.
+    Equivocating,
+}
+
+enum Event {
+    Deliver { to: usize, message: Message },
+    Timeout { node: usize, timeout: Timeout },
+}
+
+struct Validator {
+    key: SigningKey,
+    behaviour: Behaviour,
+    machine: Tendermint,
+    node: NodeManager,
+    decided: Vec<(Block, CommitCertificate)>,
+}
+
+struct Network {
+    validators: Vec<Validator>,
+    set: ValidatorSet,
+    heights: u64,
+    now: u64,
+    max_delay: u64,
+    rng: u64,
+    seq: u64,
+    queue: BinaryHeap<Reverse<(u64, u64)>>,
+    events: HashMap<u64, Event>,
+    // (height, round) the equivocating validators already attacked
+    attacked: HashSet<(usize, u64, u32)>,
+}
+
+impl Network {
+    fn new(behaviours: &[Behaviour], seed: u64, max_delay: u64, timeout: u64) -> Self {
+        let keys: Vec<_> = (1..=behaviours.len() as u8)
+            .map(|id| SigningKey::from_bytes(&[id; 32].into()).unwrap())
+            .collect();
+        let set =
+            ValidatorSet::new(keys.iter().map(PublicKey::from_signing_key).collect()).unwrap();
+        let timeouts = BftTimeouts::new(Duration::from_millis(timeout));
+
+        let validators = keys
+            .into_iter()
+            .zip(behaviours)
+            .map(|(key, behaviour)| Validator {
+                machine: Tendermint::new(set.clone(), key.clone(), timeouts).unwrap(),
+                node: NodeManager::genesis_with_storage(StorageManager::in_memory().unwrap())
+                    .unwrap()
+                    .with_consensus(Bft::new(set.clone(), timeouts)),
+                key,
+                behaviour: *behaviour,
+                decided: vec![],
+            })
+            .collect();
+
+        Self {
+            validators,
+            set,
+            heights: 0,
+            now: 0,
+            max_delay,
+            rng: seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1,
+            seq: 0,
+            queue: BinaryHeap::new(),
+            events: HashMap::new(),
+            attacked: HashSet::new(),
+        }
+    }
+
+    /// Runs until every honest validator decided `heights` blocks.
+    fn run(&mut self, heights: u64) {
+        self.heights = heights;
+
+        for index in self.honest() {
+            let candidate = self.candidate(index, index as u8);
+            let outputs = self.validators[index].machine.start(1, candidate);
+            self.dispatch(index, outputs);
+        }
+
+        while !self
+            .honest()
+            .into_iter()
+            .all(|index| self.validators[index].decided.len() as u64 >= heights)
+        {
+            let Reverse((at, seq)) = self.queue.pop().expect("network stalled");
+            assert!(at <= TIME_LIMIT, "no decision after {at} ms");
+            self.now = at;
+
+            match self.events.remove(&seq).unwrap() {
+                Event::Deliver { to, message } => match self.validators[to].behaviour {
+                    Behaviour::Honest => {
+                        let outputs = self.validators[to].machine.handle_message(message);
+                        self.dispatch(to, outputs);
+                    }
+                    Behaviour::Crashed => {}
+                    Behaviour::Equivocating => self.equivocate(to, message),
+                },
+                Event::Timeout { node, timeout } => {
+                    let outputs = self.validators[node].machine.handle_timeout(timeout);
+                    self.dispatch(node, outputs);
+                }
+            }
+        }
+    }
+
+    fn honest(&self) -> Vec<usize> {
+        self.validators
+            .iter()
+            .enumerate()
+            .filter(|(_, validator)| validator.behaviour == Behaviour::Honest)
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Next block of `index`, told apart from the other validators' by `tag`.
+    fn candidate(&self, index: usize, tag: u8) -> Block {
+        let node = &self.validators[index].node;
+        let pool = node.process_execution_transaction().unwrap();
+
+        let mut block = node.create_block_with_processed_tx_pool(pool);
+        block.header_mut().extra_data = [tag; 32].into();
+        block.set_hash();
+
+        block
+    }
+
+    fn dispatch(&mut self, from: usize, outputs: Vec<Output>) {
+        for output in outputs {
+            match output {
+                Output::Broadcast(message) => {
+                    for to in (0..self.validators.len()).filter(|to| *to != from) {
+                        self.send(to, message.clone());
+                    }
+                }
+                Output::Schedule { timeout, after } => self.push(
+                    self.now + after.as_millis() as u64,
+                    Event::Timeout {
+                        node: from,
+                        timeout,
+                    },
+                ),
+                Output::Decide { block, commit } => {
+                    let validator = &mut self.validators[from];
+                    validator
+                        .node
+                        .commit_block((*block).clone(), commit.clone())
+                        .unwrap();
+                    validator.decided.push((*block, commit));
+
+                    let height = validator.decided.len() as u64 + 1;
+
+                    if height <= self.heights {
+                        let candidate = self.candidate(from, from as u8);
+                        let outputs = self.validators[from].machine.start(height, candidate);
+                        self.dispatch(from, outputs);
+                    }
+                }
+            }
+        }
+    }
+
+    fn equivocate(&mut self, index: usize, message: Message) {
+        let (height, round) = match &message {
+            Message::Proposal(proposal) => (proposal.height, proposal.round),
+            Message::Vote(vote) => (vote.height, vote.round),
+            Message::Commit { commit, .. } => (commit.height + 1, 0),
+        };
+
+        for round in [round, round + 1] {
+            if self.set.proposer(height, round) as usize == index {
+                self.propose_twice(index, height, round);
+            }
+        }
+
+        if let Message::Proposal(proposal) = message {
+            self.vote_both_ways(index, height, round, proposal.block.hash());
+        }
+    }
+
+    /// Sends one block to the even validators and another one to the odd ones.
+    fn propose_twice(&mut self, index: usize, height: u64, round: u32) {
+        let Some(base) = self
+            .honest()
+            .into_iter()
+            .find(|honest| self.validators[*honest].decided.len() as u64 + 1 == height)
+        else {
+            return;
+        };
+
+        if !self.attacked.insert((index, height, round)) {
+            return;
+        }
+
+        for parity in 0..2 {
+            let block = self.candidate(base, 0xa0 + parity as u8);
+            let proposal = Proposal::sign(&self.validators[index].key, height, round, None, block);
+            let hash = proposal.block.hash();
+
+            self.send_to_half(index, parity, Message::Proposal(Box::new(proposal)));
+            self.send_votes(index, parity, height, round, Some(hash));
+        }
+    }
+
+    /// Votes for `hash` towards the even validators and nil towards the odd ones.
+    fn vote_both_ways(&mut self, index: usize, height: u64, round: u32, hash: Hash) {
+        if !self.attacked.insert((index, height, round)) {
+            return;
+        }
+
+        self.send_votes(index, 0, height, round, Some(hash));
+        self.send_votes(index, 1, height, round, None);
+    }
+
+    fn send_votes(
+        &mut self,
+        index: usize,
+        parity: usize,
+        height: u64,
+        round: u32,
+        hash: Option<Hash>,
+    ) {
+        for kind in [VoteKind::Prevote, VoteKind::Precommit] {
+            let vote = Vote::sign(
+                &self.validators[index].key,
+                index as u8,
+                kind,
+                height,
+                round,
+                hash,
+            );
+            self.send_to_half(index, parity, Message::Vote(vote));
+        }
+    }
+
+    fn send_to_half(&mut self, from: usize, parity: usize, message: Message) {
+        for to in (0..self.validators.len()).filter(|to| *to != from && to % 2 == parity) {
+            self.send(to, message.clone());
+        }
+    }
+
+    fn send(&mut self, to: usize, message: Message) {
+        let at = self.now + self.delay();
+        self.push(at, Event::Deliver { to, message });
+    }
+
+    fn push(&mut self, at: u64, event: Event) {
+        self.seq += 1;
+        self.events.insert(self.seq, event);
+        self.queue.push(Reverse((at, self.seq)));
+    }
+
+    // xorshift64
+    fn delay(&mut self) -> u64 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+
+        self.rng % (self.max_delay + 1)
+    }
+
+    /// Asserts that honest validators decided the same blocks with valid
+    /// certificates, stored by their nodes, and returns the decided rounds.
+    fn assert_safe(&self) -> Vec<(Hash, u32)> {
+        let honest = self.honest();
+        let reference = &self.validators[honest[0]];
+
+        for index in &honest {
+            let validator = &self.validators[*index];
+
+            for (height, (block, commit)) in (1..).zip(&validator.decided) {
+                let (expected, _) = &reference.decided[height as usize - 1];
+
+                assert_eq!(block.hash(), expected.hash(), "fork at height {height}");
+                assert_eq!(commit.height, height);
+                assert_eq!(commit.verify(&self.set, &block.hash()), Ok(()));
+                assert_eq!(
+                    validator.node.get_commit(height).unwrap().as_ref(),
+                    Some(commit)
+                );
+            }
+        }
+
+        reference
+            .decided
+            .iter()
+            .map(|(block, commit)| (block.hash(), commit.round))
+            .collect()
+    }
+}
+
+use Behaviour::{Crashed, Equivocating, Honest};
+
+#[test]
+fn decides_under_message_delay() {
+    let mut network = Network::new(&[Honest; 4], 7, 40, 100);
+    network.run(5);
+
+    let decided = network.assert_safe();
+    assert_eq!(decided.len(), 5);
+
+    // the same seed replays the same run
+    let mut replay = Network::new(&[Honest; 4], 7, 40, 100);
+    replay.run(5);
+    assert_eq!(replay.assert_safe(), decided);
+}
+
+#[test]
+fn skips_rounds_of_crashed_proposer() {
+    let mut network = Network::new(&[Honest, Honest, Honest, Crashed], 11, 40, 100);
+    network.run(6);
+
+    let decided = network.assert_safe();
+
+    // validator 3 proposes round 0 of height 3, so that height needs a second round
+    assert_eq!(decided[2].1, 1);
+    assert!(decided.iter().any(|(_, round)| *round == 0));
+}
+
+#[test]
+fn equivocating_validator_cannot_fork() {
+    for seed in 0..8 {
+        let mut network = Network::new(&[Honest, Equivocating, Honest, Honest], seed, 60, 100);
+        network.run(4);
+        network.assert_safe();
+        assert!(!network.attacked.is_empty());
+    }
+}
+
+#[test]
+fn stays_safe_when_delays_outlast_timeouts() {
+    for seed in 0..4 {
+        let mut network = Network::new(&[Honest, Honest, Equivocating, Honest], seed, 400, 50);
+        network.run(3);
+
+        let decided = network.assert_safe();
+        assert_eq!(decided.len(), 3);
+    }
+}
+
+#[test]
+fn node_requires_commit_certificate() {
+    let mut network = Network::new(&[Honest; 4], 3, 10, 100);
+    network.run(1);
+
+    let (block, commit) = network.validators[0].decided[0].clone();
+    let node = NodeManager::genesis_with_storage(StorageManager::in_memory().unwrap())
+        .unwrap()
+        .with_consensus(Bft::new(network.set.clone(), BftTimeouts::default()));
+
+    assert!(matches!(
+        node.import_block(block.clone()),
+        Err(NodeError::Consensus(ConsensusError::MissingCommit(1)))
+    ));
+
+    let mut partial = commit.clone();
+    partial.signatures.truncate(2);
+    assert!(matches!(
+        node.commit_block(block.clone(), partial),
+        Err(NodeError::Consensus(ConsensusError::Finality(_)))
+    ));
+
+    node.commit_block(block, commit.clone()).unwrap();
+    assert_eq!(node.get_commit(1).unwrap(), Some(commit));
+
+    // a certificate makes its block final without waiting for confirmations
+    assert_eq!(node.get_finalized_height(), 1);
+}
@@ -0,0 +1,125 @@
+mod tendermint;
+
+#[cfg(test)]
+mod harness;
+
+pub use tendermint::{BftTimeouts, Message, Output, Proposal, Step, Tendermint, Timeout};
+
+use k256::ecdsa::SigningKey;
+use rm_reth_types::{
+    block::block::{Block, Header},
+    finality::{CommitCertificate, ValidatorSet},
+    multisig::PublicKey,
+};
+
+use crate::consensus::{ConsensusEngine, ConsensusError, ElectedSets};
+
+/// Difficulty of every BFT block, blocks are final so there are no forks to weigh.
+pub const BFT_DIFFICULTY: u32 = 1;
+
+/// Tendermint style BFT among the validators staking elected for each epoch, or
+/// the configured ones.
+///
+/// Blocks carry no seal, they are final once a quorum of validators precommitted
+/// them, which the [`CommitCertificate`] stored next to the block proves. The
+/// rounds that produce it are run by [`Tendermint`], which the block producer of
+/// a node given the key of a validator drives.
+pub struct Bft {
+    validators: ValidatorSet,
+    elected: ElectedSets<ValidatorSet>,
+    timeouts: BftTimeouts,
+    signer: Option<SigningKey>,
+}
+
+impl Bft {
+    pub fn new(validators: ValidatorSet, timeouts: BftTimeouts) -> Self {
+        Self {
+            validators,
+            elected: ElectedSets::default(),
+            timeouts,
+            signer: None,
+        }
+    }
+
+    /// Sets the key this node votes and proposes with.
+    pub fn with_signer(mut self, signer: SigningKey) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+
+    /// State machine taking part in the rounds from block `height` with the signer
+    /// key, if this node was given one.
+    pub fn validator(&self, height: u64) -> Result<Option<Tendermint>, ConsensusError> {
+        self.signer
+            .clone()
+            .map(|key| Tendermint::new(self.validators(height), key, self.timeouts))
+            .transpose()
+    }
+
+    /// Validators agreeing on block `height`.
+    pub fn validators(&self, height: u64) -> ValidatorSet {
+        self.elected
+            .at(height)
+            .unwrap_or_else(|| self.validators.clone())
+    }
+
+    #[inline]
+    pub fn timeouts(&self) -> &BftTimeouts {
+        &self.timeouts
+    }
+}
+
+impl ConsensusEngine for Bft {
+    fn name(&self) -> &'static str {
+        "bft"
+    }
+
+    fn prepare_header(&self, _parent: &Header, header: &mut Header) {
+        header.difficulty = BFT_DIFFICULTY;
+    }
+
+    fn seal(&self, _block: &mut Block) -> Result<(), ConsensusError> {
+        Ok(())
+    }
+
+    fn verify_seal(&self, block: &Block) -> Result<(), ConsensusError> {
+        if block.header().difficulty != BFT_DIFFICULTY {
+            return Err(ConsensusError::InvalidDifficulty {
+                expected: BFT_DIFFICULTY,
+                got: block.header().difficulty,
+            });
+        }
+
+        if !block.seal.is_empty() {
+            return Err(ConsensusError::InvalidSeal);
+        }
+
+        Ok(())
+    }
+
+    fn verify_commit(
+        &self,
+        block: &Block,
+        commit: Option<&CommitCertificate>,
+    ) -> Result<(), ConsensusError> {
+        let commit = commit.ok_or(ConsensusError::MissingCommit(block.id()))?;
+
+        if commit.height != block.id() {
+            return Err(ConsensusError::CommitHeight {
+                expected: block.id(),
+                got: commit.height,
+            });
+        }
+
+        Ok(commit.verify(&self.validators(block.id()), &block.hash())?)
+    }
+
+    fn bft(&self) -> Option<&Bft> {
+        Some(self)
+    }
+
+    fn elect(&self, from_height: u64, validators: &[PublicKey]) {
+        self.elected
+            .elect(from_height, ValidatorSet::new(validators.to_vec()).ok());
+    }
+}
@@ -0,0 +1,620 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    time::Duration,
+};
+
+use k256::ecdsa::{Signature, SigningKey, signature::Signer};
+use parity_scale_codec::{Decode, Encode};
+use rm_reth_types::{
+    block::block::Block,
+    bytes::FixedBytes,
+    finality::{CommitCertificate, ValidatorSet, Vote, VoteKind},
+    hash::Hash,
+    multisig::PublicKey,
+};
+
+use crate::consensus::ConsensusError;
+
+const PROPOSAL_DOMAIN: &[u8] = b"rm-reth/bft-proposal";
+
+/// Messages of later heights kept until the validator gets there.
+const MAX_FUTURE_MESSAGES: usize = 4096;
+
+/// How long each step waits for messages before giving up on the round.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BftTimeouts {
+    pub propose: Duration,
+    pub prevote: Duration,
+    pub precommit: Duration,
+    /// Added to every timeout each round, so that rounds eventually outlast the
+    /// message delays.
+    pub delta: Duration,
+}
+
+impl BftTimeouts {
+    pub fn new(base: Duration) -> Self {
+        Self {
+            propose: base,
+            prevote: base,
+            precommit: base,
+            delta: base / 2,
+        }
+    }
+
+    pub fn duration(&self, step: Step, round: u32) -> Duration {
+        let base = match step {
+            Step::Propose => self.propose,
+            Step::Prevote => self.prevote,
+            Step::Precommit => self.precommit,
+        };
+
+        base + self.delta * round
+    }
+}
+
+impl Default for BftTimeouts {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(1))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Step {
+    Propose,
+    Prevote,
+    Precommit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Timeout {
+    pub height: u64,
+    pub round: u32,
+    pub step: Step,
+}
+
+/// Block proposed by the proposer of a round, with the round it was last seen
+/// with a quorum of prevotes, if any.
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct Proposal {
+    pub height: u64,
+    pub round: u32,
+    pub valid_round: Option<u32>,
+    pub block: Block,
+    pub signature: FixedBytes<64>,
+}
+
+impl Proposal {
+    pub fn signing_hash(
+        height: u64,
+        round: u32,
+        valid_round: Option<u32>,
+        block_hash: &Hash,
+    ) -> Hash {
+        let mut buf = PROPOSAL_DOMAIN.to_vec();
+        (height, round, valid_round, block_hash).encode_to(&mut buf);
+
+        Hash::hash(&buf)
+    }
+
+    pub fn sign(
+        key: &SigningKey,
+        height: u64,
+        round: u32,
+        valid_round: Option<u32>,
+        block: Block,
+    ) -> Self {
+        let message = Self::signing_hash(height, round, valid_round, &block.hash());
+        let signature: Signature = key.sign(message.as_slice());
+
+        Self {
+            height,
+            round,
+            valid_round,
+            block,
+            signature: FixedBytes(signature.to_bytes().into()),
+        }
+    }
+
+    /// Whether the proposal is signed by the proposer of its round.
+    pub fn verify(&self, validators: &ValidatorSet) -> bool {
+        let message = Self::signing_hash(
+            self.height,
+            self.round,
+            self.valid_round,
+            &self.block.hash(),
+        );
+
+        validators
+            .get(validators.proposer(self.height, self.round))
+            .is_some_and(|key| key.verify(&message, &self.signature))
+    }
+}
+
+#[derive(Debug, Clone, Encode, Decode)]
+pub enum Message {
+    Proposal(Box<Proposal>),
+    Vote(Vote),
+    /// Decided block of a height, so that validators left behind catch up.
+    Commit {
+        block: Box<Block>,
+        commit: CommitCertificate,
+    },
+}
+
+impl Message {
+    pub fn height(&self) -> u64 {
+        match self {
+            Message::Proposal(proposal) => proposal.height,
+            Message::Vote(vote) => vote.height,
+            Message::Commit { commit, .. } => commit.height,
+        }
+    }
+}
+
+/// What the driver of a [`Tendermint`] has to do after feeding it an input.
+#[derive(Debug, Clone)]
+pub enum Output {
+    /// Send to every other validator.
+    Broadcast(Message),
+    /// Call [`Tendermint::handle_timeout`] with `timeout` once `after` elapsed.
+    Schedule { timeout: Timeout, after: Duration },
+    /// `block` is final, the driver stores it and starts the next height.
+    Decide {
+        block: Box<Block>,
+        commit: CommitCertificate,
+    },
+}
+
+/// Tendermint consensus of one validator, as a state machine without I/O.
+///
+/// Every height goes through rounds of propose, prevote and precommit. A block
+/// is decided once more than two thirds of the validators precommitted it in the
+/// same round. A validator precommitting a block locks on it and only prevotes
+/// another one after seeing a quorum of prevotes for it in a later round, which
+/// keeps two quorums from deciding different blocks while at most a third of the
+/// validators is faulty.
+///
+/// Follows "The latest gossip on BFT consensus" (Buchman, Kwon, Milosevic).
+pub struct Tendermint {
+    validators: ValidatorSet,
+    key: SigningKey,
+    index: u8,
+    timeouts: BftTimeouts,
+
+    height: u64,
+    round: u32,
+    step: Step,
+    // true until `start` and again once the height is decided
+    decided: bool,
+    candidate: Block,
+    locked: Option<(u32, Block)>,
+    valid: Option<(u32, Block)>,
+    proposals: HashMap<u32, Proposal>,
+    votes: HashMap<(u32, VoteKind), BTreeMap<u8, Vote>>,
+    future: Vec<Message>,
+
+    // rules that only fire once per round
+    prevote_timeout: bool,
+    precommit_timeout: bool,
+    polka: bool,
+}
+
+impl Tendermint {
+    pub fn new(
+        validators: ValidatorSet,
+        key: SigningKey,
+        timeouts: BftTimeouts,
+    ) -> Result<Self, ConsensusError> {
+        let public = PublicKey::from_signing_key(&key);
+        let index = validators
+            .index_of(&public)
+            .ok_or(ConsensusError::UnknownSigner(public))?;
+
+        Ok(Self {
+            validators,
+            key,
+            index,
+            timeouts,
+            height: 0,
+            round: 0,
+            step: Step::Propose,
+            decided: true,
+            // replaced by the first `start`
+            candidate: Block::genesis(),
+            locked: None,
+            valid: None,
+            proposals: HashMap::new(),
+            votes: HashMap::new(),
+            future: vec![],
+            prevote_timeout: false,
+            precommit_timeout: false,
+            polka: false,
+        })
+    }
+
+    /// Replaces the validators of the heights started from now on. Fails if the
+    /// key of this validator is not one of them.
+    pub fn set_validators(&mut self, validators: ValidatorSet) -> Result<(), ConsensusError> {
+        let public = PublicKey::from_signing_key(&self.key);
+
+        self.index = validators
+            .index_of(&public)
+            .ok_or(ConsensusError::UnknownSigner(public))?;
+        self.validators = validators;
+
+        Ok(())
+    }
+
+    #[inline]
+    pub fn index(&self) -> u8 {
+        self.index
+    }
+
+    #[inline]
+    pub fn height(&self) -> u64 {
+        self.height
+    }
+
+    #[inline]
+    pub fn round(&self) -> u32 {
+        self.round
+    }
+
+    #[inline]
+    pub fn step(&self) -> Step {
+        self.step
+    }
+
+    #[inline]
+    pub fn is_decided(&self) -> bool {
+        self.decided
+    }
+
+    /// Starts agreeing on block `height`, proposing `candidate` when it is this
+    /// validator's turn. Heights have to be started in order, once the previous one
+    /// is decided.
+    pub fn start(&mut self, height: u64, candidate: Block) -> Vec<Output> {
+        self.height = height;
+        self.decided = false;
+        self.candidate = candidate;
+        self.locked = None;
+        self.valid = None;
+        self.proposals.clear();
+        self.votes.clear();
+
+        let mut out = vec![];
+        self.start_round(0, &mut out);
+
+        for message in std::mem::take(&mut self.future) {
+            if message.height() > height {
+                self.future.push(message);
+            } else if message.height() == height && !self.decided {
+                self.receive(message, &mut out);
+            }
+        }
+
+        self.process(&mut out);
+
+        out
+    }
+
+    pub fn handle_message(&mut self, message: Message) -> Vec<Output> {
+        let mut out = vec![];
+
+        if message.height() > self.height {
+            if self.future.len() < MAX_FUTURE_MESSAGES {
+                self.future.push(message);
+            }
+        } else if message.height() == self.height && !self.decided {
+            self.receive(message, &mut out);
+        }
+
+        out
+    }
+
+    pub fn handle_timeout(&mut self, timeout: Timeout) -> Vec<Output> {
+        let mut out = vec![];
+
+        if self.decided || timeout.height != self.height || timeout.round != self.round {
+            return out;
+        }
+
+        match timeout.step {
+            Step::Propose if self.step == Step::Propose => {
+                self.vote(VoteKind::Prevote, None, &mut out)
+            }
+            Step::Prevote if self.step == Step::Prevote => {
+                self.vote(VoteKind::Precommit, None, &mut out)
+            }
+            Step::Precommit => self.start_round(self.round + 1, &mut out),
+            _ => return out,
+        }
+
+        self.process(&mut out);
+
+        out
+    }
+
+    fn receive(&mut self, message: Message, out: &mut Vec<Output>) {
+        let recorded = match message {
+            Message::Proposal(proposal) => self.record_proposal(*proposal),
+            Message::Vote(vote) => self.record_vote(vote),
+            Message::Commit { block, commit } => {
+                self.catch_up(*block, commit, out);
+                false
+            }
+        };
+
+        if recorded {
+            self.process(out);
+        }
+    }
+
+    fn record_proposal(&mut self, proposal: Proposal) -> bool {
+        if self.proposals.contains_key(&proposal.round) || !proposal.verify(&self.validators) {
+            return false;
+        }
+
+        self.proposals.insert(proposal.round, proposal);
+
+        true
+    }
+
+    // the first vote of a validator counts, an equivocating second one is dropped
+    fn record_vote(&mut self, vote: Vote) -> bool {
+        if vote.verify(&self.validators).is_err() {
+            return false;
+        }
+
+        let votes = self.votes.entry((vote.round, vote.kind)).or_default();
+
+        if votes.contains_key(&vote.validator) {
+            return false;
+        }
+
+        votes.insert(vote.validator, vote);
+
+        true
+    }
+
+    fn catch_up(&mut self, block: Block, commit: CommitCertificate, out: &mut Vec<Output>) {
+        if !self.valid_block(&block) || commit.verify(&self.validators, &block.hash()).is_err() {
+            return;
+        }
+
+        self.decided = true;
+        out.push(Output::Decide {
+            block: Box::new(block),
+            commit,
+        });
+    }
+
+    fn start_round(&mut self, round: u32, out: &mut Vec<Output>) {
+        self.round = round;
+        self.step = Step::Propose;
+        self.prevote_timeout = false;
+        self.precommit_timeout = false;
+        self.polka = false;
+
+        if self.validators.proposer(self.height, round) != self.index {
+            self.schedule(Step::Propose, out);
+            return;
+        }
+
+        let (valid_round, block) = match &self.valid {
+            Some((valid_round, block)) => (Some(*valid_round), block.clone()),
+            None => (None, self.candidate.clone()),
+        };
+
+        let proposal = Proposal::sign(&self.key, self.height, round, valid_round, block);
+
+        self.proposals.insert(round, proposal.clone());
+        out.push(Output::Broadcast(Message::Proposal(Box::new(proposal))));
+    }
+
+    /// Applies rules until none fires any more.
+    fn process(&mut self, out: &mut Vec<Output>) {
+        while !self.decided && self.apply_rule(out) {}
+    }
+
+    fn apply_rule(&mut self, out: &mut Vec<Output>) -> bool {
+        let round = self.round;
+
+        // a quorum of precommits for a proposed block decides it, in any round
+        let decision = self.proposals.iter().find_map(|(round, proposal)| {
+            let hash = Some(proposal.block.hash());
+
+            (self.quorum_for(*round, VoteKind::Precommit, &hash)
+                && self.valid_block(&proposal.block))
+            .then_some(*round)
+        });
+
+        if let Some(round) = decision {
+            self.decide(round, out);
+            return true;
+        }
+
+        // enough validators moved on that at least one of them is honest
+        if let Some(later) = self.later_round() {
+            self.start_round(later, out);
+            return true;
+        }
+
+        if self.step == Step::Propose
+            && let Some(prevote) = self.prevote_for_proposal()
+        {
+            self.vote(VoteKind::Prevote, prevote, out);
+            return true;
+        }
+
+        if self.step == Step::Prevote
+            && !self.prevote_timeout
+            && self.votes(round, VoteKind::Prevote).count() >= self.validators.quorum()
+        {
+            self.prevote_timeout = true;
+            self.schedule(Step::Prevote, out);
+            return true;
+        }
+
+        if self.step >= Step::Prevote
+            && !self.polka
+            && let Some(block) = self.polka_block()
+        {
+            self.polka = true;
+
+            if self.step == Step::Prevote {
+                self.locked = Some((round, block.clone()));
+                self.vote(VoteKind::Precommit, Some(block.hash()), out);
+            }
+
+            self.valid = Some((round, block));
+            return true;
+        }
+
+        if self.step == Step::Prevote && self.quorum_for(round, VoteKind::Prevote, &None) {
+            self.vote(VoteKind::Precommit, None, out);
+            return true;
+        }
+
+        if !self.precommit_timeout
+            && self.votes(round, VoteKind::Precommit).count() >= self.validators.quorum()
+        {
+            self.precommit_timeout = true;
+            self.schedule(Step::Precommit, out);
+            return true;
+        }
+
+        false
+    }
+
+    /// Prevote for the proposal of the current round, `None` while it cannot be
+    /// judged yet and `Some(None)` for a nil prevote.
+    fn prevote_for_proposal(&self) -> Option<Option<Hash>> {
+        let proposal = self.proposals.get(&self.round)?;
+        let hash = proposal.block.hash();
+        let valid = self.valid_block(&proposal.block);
+
+        let accept = match proposal.valid_round {
+            None => {
+                valid
+                    && self
+                        .locked
+                        .as_ref()
+                        .is_none_or(|(_, locked)| locked.hash() == hash)
+            }
+            Some(valid_round)
+                if valid_round < self.round
+                    && self.quorum_for(valid_round, VoteKind::Prevote, &Some(hash)) =>
+            {
+                valid
+                    && self.locked.as_ref().is_none_or(|(locked_round, locked)| {
+                        *locked_round <= valid_round || locked.hash() == hash
+                    })
+            }
+            Some(_) => return None,
+        };
+
+        Some(accept.then_some(hash))
+    }
+
+    /// Proposed block of the current round with a quorum of prevotes.
+    fn polka_block(&self) -> Option<Block> {
+        let proposal = self.proposals.get(&self.round)?;
+        let hash = Some(proposal.block.hash());
+
+        (self.quorum_for(self.round, VoteKind::Prevote, &hash) && self.valid_block(&proposal.block))
+            .then(|| proposal.block.clone())
+    }
+
+    fn later_round(&self) -> Option<u32> {
+        let mut voters: BTreeMap<u32, BTreeSet<u8>> = BTreeMap::new();
+
+        for ((round, _), votes) in &self.votes {
+            if *round > self.round {
+                voters.entry(*round).or_default().extend(votes.keys());
+            }
+        }
+
+        voters
+            .into_iter()
+            .rev()
+            .find(|(_, voters)| voters.len() >= self.validators.honest_threshold())
+            .map(|(round, _)| round)
+    }
+
+    fn decide(&mut self, round: u32, out: &mut Vec<Output>) {
+        let block = self.proposals[&round].block.clone();
+        let commit = CommitCertificate::from_votes(
+            self.height,
+            round,
+            block.hash(),
+            self.votes(round, VoteKind::Precommit),
+        );
+
+        self.decided = true;
+
+        out.push(Output::Broadcast(Message::Commit {
+            block: Box::new(block.clone()),
+            commit: commit.clone(),
+        }));
+        out.push(Output::Decide {
+            block: Box::new(block),
+            commit,
+        });
+    }
+
+    fn vote(&mut self, kind: VoteKind, block_hash: Option<Hash>, out: &mut Vec<Output>) {
+        let vote = Vote::sign(
+            &self.key,
+            self.index,
+            kind,
+            self.height,
+            self.round,
+            block_hash,
+        );
+
+        self.votes
+            .entry((self.round, kind))
+            .or_default()
+            .insert(self.index, vote.clone());
+        self.step = match kind {
+            VoteKind::Prevote => Step::Prevote,
+            VoteKind::Precommit => Step::Precommit,
+        };
+
+        out.push(Output::Broadcast(Message::Vote(vote)));
+    }
+
+    fn schedule(&self, step: Step, out: &mut Vec<Output>) {
+        out.push(Output::Schedule {
+            timeout: Timeout {
+                height: self.height,
+                round: self.round,
+                step,
+            },
+            after: self.timeouts.duration(step, self.round),
+        });
+    }
+
+    fn votes(&self, round: u32, kind: VoteKind) -> impl Iterator<Item = &Vote> {
+        self.votes
+            .get(&(round, kind))
+            .into_iter()
+            .flat_map(|votes| votes.values())
+    }
+
+    fn quorum_for(&self, round: u32, kind: VoteKind, block_hash: &Option<Hash>) -> bool {
+        self.votes(round, kind)
+            .filter(|vote| vote.block_hash == *block_hash)
+            .count()
+            >= self.validators.quorum()
+    }
+
+    /// Blocks of this height on the same parent as the local candidate, whose hash
+    /// matches their content.
+    fn valid_block(&self, block: &Block) -> bool {
+        block.id() == self.height
+            && block.get_hash() == block.hash()
+            && self.candidate.header().prev_block == block.header().prev_block
+    }
+}
@@ -0,0 +1,21 @@
+use rm_reth_types::block::block::Block;
+
+use crate::consensus::{ConsensusEngine, ConsensusError};
+
+/// Accepts every block without a seal, for local development and tests.
+#[derive(Debug, Default, Clone)]
+pub struct Dev;
+
+impl ConsensusEngine for Dev {
+    fn name(&self) -> &'static str {
+        "dev"
+    }
+
+    fn seal(&self, _block: &mut Block) -> Result<(), ConsensusError> {
+        Ok(())
+    }
+
+    fn verify_seal(&self, _block: &Block) -> Result<(), ConsensusError> {
+        Ok(())
+    }
+}
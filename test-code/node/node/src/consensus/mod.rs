@@ -0,0 +1,207 @@
+pub mod bft;
+pub mod dev;
+pub mod poa;
+pub mod pow;
+
+use std::{collections::BTreeMap, sync::RwLock};
+
+use config::ConsensusConfig;
+use k256::ecdsa::SigningKey;
+use rm_reth_types::{
+    block::block::{Block, Header},
+    bytes::FixedBytes,
+    finality::{CommitCertificate, FinalityError, ValidatorSet},
+    multisig::PublicKey,
+};
+
+use crate::consensus::{
+    bft::{Bft, BftTimeouts},
+    dev::Dev,
+    poa::Poa,
+    pow::Pow,
+};
+
+/// Reason a block could not be sealed or its seal was rejected.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum ConsensusError {
+    #[error("malformed seal")]
+    InvalidSeal,
+
+    #[error("no signing key configured")]
+    MissingSigner,
+
+    #[error("local signer is not the proposer of block {0}")]
+    NotProposer(u64),
+
+    #[error("unknown signer: {0:?}")]
+    UnknownSigner(PublicKey),
+
+    #[error("signer is not the proposer of block {height}")]
+    WrongProposer { height: u64 },
+
+    #[error("invalid seal signature")]
+    InvalidSignature,
+
+    #[error("unexpected difficulty (expected: {expected}, got: {got})")]
+    InvalidDifficulty { expected: u32, got: u32 },
+
+    #[error("insufficient work (required: {required} zero bits, got: {got})")]
+    InsufficientWork { required: u32, got: u32 },
+
+    #[error("invalid consensus config: {0}")]
+    InvalidConfig(String),
+
+    #[error("block {0} needs a commit certificate")]
+    MissingCommit(u64),
+
+    #[error("commit certificate is for block {got}, not {expected}")]
+    CommitHeight { expected: u64, got: u64 },
+
+    #[error("finality error: ({0})")]
+    Finality(#[from] FinalityError),
+}
+
+/// Block production rules, so [`crate::manager::NodeManager`] does not depend on
+/// how blocks are agreed on.
+///
+/// A block goes through [`ConsensusEngine::prepare_header`] when it is built, its
+/// hash is set, and [`ConsensusEngine::seal`] stores the seal next to the hash,
+/// outside of what it covers. Every imported block, including the node's own, is
+/// checked with [`ConsensusEngine::fork_choice`], [`ConsensusEngine::verify_seal`]
+/// and [`ConsensusEngine::verify_commit`].
+pub trait ConsensusEngine: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Fills the consensus fields of `header`, a child of `parent`.
+    fn prepare_header(&self, _parent: &Header, _header: &mut Header) {}
+
+    fn seal(&self, block: &mut Block) -> Result<(), ConsensusError>;
+
+    fn verify_seal(&self, block: &Block) -> Result<(), ConsensusError>;
+
+    /// Checks the finality proof imported with `block`. Engines without finality
+    /// ignore it.
+    fn verify_commit(
+        &self,
+        _block: &Block,
+        _commit: Option<&CommitCertificate>,
+    ) -> Result<(), ConsensusError> {
+        Ok(())
+    }
+
+    /// Whether `candidate` should become the head instead of `head`. Defaults to
+    /// the longest chain.
+    fn fork_choice(&self, head: &Header, candidate: &Header) -> bool {
+        candidate.block_id > head.block_id
+    }
+
+    /// The engine as [`Bft`], whose blocks are agreed on in rounds run next to the
+    /// node instead of being sealed by it.
+    fn bft(&self) -> Option<&Bft> {
+        None
+    }
+
+    /// Takes the validators the staking registry elected for the blocks from
+    /// `from_height` on, replacing the ones elected for later blocks. An empty set
+    /// leaves the blocks to the configured validators. Engines without validators
+    /// ignore it.
+    fn elect(&self, _from_height: u64, _validators: &[PublicKey]) {}
+}
+
+/// Validator sets handed to [`ConsensusEngine::elect`], by the first height they
+/// sign. `None` stands for the configured validators.
+#[derive(Debug)]
+pub(crate) struct ElectedSets<T>(RwLock<BTreeMap<u64, Option<T>>>);
+
+impl<T> Default for ElectedSets<T> {
+    fn default() -> Self {
+        Self(RwLock::new(BTreeMap::new()))
+    }
+}
+
+impl<T: Clone> ElectedSets<T> {
+    pub(crate) fn elect(&self, from_height: u64, validators: Option<T>) {
+        let mut sets = self.0.write().expect("elected sets lock poisoned");
+
+        sets.split_off(&from_height);
+        sets.insert(from_height, validators);
+    }
+
+    /// Set elected for `height`, `None` if the configured validators sign it.
+    pub(crate) fn at(&self, height: u64) -> Option<T> {
+        self.0
+            .read()
+            .expect("elected sets lock poisoned")
+            .range(..=height)
+            .next_back()
+            .and_then(|(_, validators)| validators.clone())
+    }
+}
+
+/// Builds the engine selected by `config`.
+pub fn from_config(config: &ConsensusConfig) -> Result<Box<dyn ConsensusEngine>, ConsensusError> {
+    match config {
+        ConsensusConfig::Pow { difficulty } => Ok(Box::new(Pow::new(*difficulty))),
+        ConsensusConfig::Dev => Ok(Box::new(Dev)),
+        ConsensusConfig::Poa { validators, signer } => {
+            let validators = validators
+                .iter()
+                .map(|key| parse_public_key(key))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let poa = Poa::new(validators);
+
+            match signer {
+                Some(signer) => Ok(Box::new(poa.with_signer(parse_signing_key(signer)?))),
+                None => Ok(Box::new(poa)),
+            }
+        }
+        ConsensusConfig::Bft {
+            validators,
+            signer,
+            timeout_ms,
+        } => {
+            let validators = validators
+                .iter()
+                .map(|key| parse_public_key(key))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let validators = ValidatorSet::new(validators)
+                .map_err(|e| ConsensusError::InvalidConfig(e.to_string()))?;
+            let timeouts = BftTimeouts::new(std::time::Duration::from_millis(*timeout_ms));
+
+            let mut bft = Bft::new(validators, timeouts);
+
+            if let Some(signer) = signer {
+                bft = bft.with_signer(parse_signing_key(signer)?);
+
+                // a signer outside the set could never vote
+                bft.validator(0)?;
+            }
+
+            Ok(Box::new(bft))
+        }
+    }
+}
+
+fn parse_public_key(value: &str) -> Result<PublicKey, ConsensusError> {
+    let invalid = || ConsensusError::InvalidConfig(format!("invalid validator key {value}"));
+
+    let bytes: [u8; 33] = hex::decode(value.trim_start_matches("0x"))
+        .map_err(|_| invalid())?
+        .try_into()
+        .map_err(|_| invalid())?;
+
+    let key = PublicKey(FixedBytes(bytes));
+    key.verifying_key().ok_or_else(invalid)?;
+
+    Ok(key)
+}
+
+fn parse_signing_key(value: &str) -> Result<SigningKey, ConsensusError> {
+    let invalid = || ConsensusError::InvalidConfig("invalid signer key".into());
+
+    let bytes = hex::decode(value.trim_start_matches("0x")).map_err(|_| invalid())?;
+
+    SigningKey::from_slice(&bytes).map_err(|_| invalid())
+}
@@ -0,0 +1,144 @@
+use k256::ecdsa::{
+    Signature, SigningKey,
+    signature::{Signer, Verifier},
+};
+use parity_scale_codec::{Decode, DecodeAll, Encode};
+use rm_reth_types::{
+    block::block::{Block, Header},
+    bytes::FixedBytes,
+    hash::Hash,
+    multisig::PublicKey,
+};
+
+use crate::consensus::{ConsensusEngine, ConsensusError, ElectedSets};
+
+/// Seal of a proof-of-authority block: the proposer and its signature over the
+/// block hash.
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+pub struct PoaSeal {
+    pub signer: PublicKey,
+    pub signature: FixedBytes<64>,
+}
+
+impl PoaSeal {
+    pub fn sign(key: &SigningKey, hash: &Hash) -> Self {
+        let signature: Signature = key.sign(hash.as_slice());
+
+        Self {
+            signer: PublicKey::from_signing_key(key),
+            signature: FixedBytes(signature.to_bytes().into()),
+        }
+    }
+}
+
+/// Difficulty of every proof-of-authority block, the chain length decides forks.
+pub const POA_DIFFICULTY: u32 = 1;
+
+/// Proof of authority with validators taking turns by height.
+///
+/// Block `h` has to be signed by `validators[h % validators.len()]`, from the set
+/// staking elected for `h` or else the configured one. A node only produces
+/// blocks when it was given the key of a validator.
+pub struct Poa {
+    validators: Vec<PublicKey>,
+    elected: ElectedSets<Vec<PublicKey>>,
+    signer: Option<SigningKey>,
+}
+
+impl Poa {
+    pub fn new(validators: Vec<PublicKey>) -> Self {
+        Self {
+            validators,
+            elected: ElectedSets::default(),
+            signer: None,
+        }
+    }
+
+    /// Sets the key this node signs its own blocks with.
+    pub fn with_signer(mut self, signer: SigningKey) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+
+    /// Validators taking turns at block `height`.
+    pub fn validators(&self, height: u64) -> Vec<PublicKey> {
+        self.elected
+            .at(height)
+            .unwrap_or_else(|| self.validators.clone())
+    }
+
+    /// Validator expected to propose block `height`.
+    pub fn proposer(&self, height: u64) -> Option<PublicKey> {
+        proposer(&self.validators(height), height)
+    }
+}
+
+fn proposer(validators: &[PublicKey], height: u64) -> Option<PublicKey> {
+    if validators.is_empty() {
+        return None;
+    }
+
+    validators
+        .get((height % validators.len() as u64) as usize)
+        .copied()
+}
+
+impl ConsensusEngine for Poa {
+    fn name(&self) -> &'static str {
+        "poa"
+    }
+
+    fn prepare_header(&self, _parent: &Header, header: &mut Header) {
+        header.difficulty = POA_DIFFICULTY;
+    }
+
+    fn seal(&self, block: &mut Block) -> Result<(), ConsensusError> {
+        let signer = self.signer.as_ref().ok_or(ConsensusError::MissingSigner)?;
+
+        if self.proposer(block.id()) != Some(PublicKey::from_signing_key(signer)) {
+            return Err(ConsensusError::NotProposer(block.id()));
+        }
+
+        block.seal = PoaSeal::sign(signer, &block.hash()).encode();
+
+        Ok(())
+    }
+
+    fn verify_seal(&self, block: &Block) -> Result<(), ConsensusError> {
+        if block.header().difficulty != POA_DIFFICULTY {
+            return Err(ConsensusError::InvalidDifficulty {
+                expected: POA_DIFFICULTY,
+                got: block.header().difficulty,
+            });
+        }
+
+        let seal = PoaSeal::decode_all(&mut block.seal.as_slice())
+            .map_err(|_| ConsensusError::InvalidSeal)?;
+
+        let validators = self.validators(block.id());
+
+        if !validators.contains(&seal.signer) {
+            return Err(ConsensusError::UnknownSigner(seal.signer));
+        }
+
+        if proposer(&validators, block.id()) != Some(seal.signer) {
+            return Err(ConsensusError::WrongProposer { height: block.id() });
+        }
+
+        let key = seal
+            .signer
+            .verifying_key()
+            .ok_or(ConsensusError::UnknownSigner(seal.signer))?;
+        let signature = Signature::from_slice(seal.signature.as_slice())
+            .map_err(|_| ConsensusError::InvalidSignature)?;
+
+        key.verify(block.hash().as_slice(), &signature)
+            .map_err(|_| ConsensusError::InvalidSignature)
+    }
+    fn elect(&self, from_height: u64, validators: &[PublicKey]) {
+        self.elected.elect(
+            from_height,
+            (!validators.is_empty()).then(|| validators.to_vec()),
+        );
+    }
+}
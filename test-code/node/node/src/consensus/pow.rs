@@ -0,0 +1,95 @@
+use rm_reth_types::{
+    block::block::{Block, Header},
+    hash::Hash,
+};
+
+use crate::consensus::{ConsensusEngine, ConsensusError};
+
+/// Number of leading zero bits of `hash`.
+pub fn leading_zero_bits(hash: &Hash) -> u32 {
+    let mut bits = 0;
+
+    for byte in hash.as_slice() {
+        bits += byte.leading_zeros();
+
+        if *byte != 0 {
+            break;
+        }
+    }
+
+    bits
+}
+
+/// Proof of work over the block extra data, which miners use as nonce.
+///
+/// The block hash needs `difficulty` leading zero bits. Sealing searches the
+/// first 8 bytes of the extra data, read as a little endian counter, until it
+/// does. Blocks carry no seal.
+#[derive(Debug, Default, Clone)]
+pub struct Pow {
+    difficulty: u32,
+}
+
+impl Pow {
+    pub fn new(difficulty: u32) -> Self {
+        Self { difficulty }
+    }
+
+    #[inline]
+    pub fn difficulty(&self) -> u32 {
+        self.difficulty
+    }
+
+    fn check_work(&self, block: &Block) -> Result<(), ConsensusError> {
+        let got = leading_zero_bits(&block.hash());
+
+        if got < self.difficulty {
+            return Err(ConsensusError::InsufficientWork {
+                required: self.difficulty,
+                got,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl ConsensusEngine for Pow {
+    fn name(&self) -> &'static str {
+        "pow"
+    }
+
+    fn prepare_header(&self, _parent: &Header, header: &mut Header) {
+        header.difficulty = self.difficulty;
+    }
+
+    fn seal(&self, block: &mut Block) -> Result<(), ConsensusError> {
+        let mut extra_data = block.header().extra_data;
+        let mut nonce = u64::from_le_bytes(extra_data.0[..8].try_into().unwrap());
+
+        while self.check_work(block).is_err() {
+            nonce = nonce.wrapping_add(1);
+            extra_data.0[..8].copy_from_slice(&nonce.to_le_bytes());
+
+            block.header_mut().extra_data = extra_data;
+            block.set_hash();
+        }
+
+        Ok(())
+    }
+
+    fn verify_seal(&self, block: &Block) -> Result<(), ConsensusError> {
+        if !block.seal.is_empty() {
+            return Err(ConsensusError::InvalidSeal);
+        }
+
+        if block.header().difficulty != self.difficulty {
+            return Err(ConsensusError::InvalidDifficulty {
+                expected: self.difficulty,
+                got: block.header().difficulty,
+            });
+        }
+
+        self.check_work(block)
+    }
+}
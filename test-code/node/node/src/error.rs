@@ -1,5 +1,9 @@
+use rm_reth_types::asset::AssetId;
 use storage::error::StorageError;
 
+use crate::consensus::ConsensusError;
+use vm::error::VmError;
+
 #[derive(Debug, thiserror::Error)]
 pub enum NodeError {
     #[error("initialize error: ({0})")]
@@ -17,9 +21,48 @@ pub enum NodeError {
     #[error("storage error: ({0})")]
     StorageError(#[from] StorageError),
 
-    #[error("invaild extra data while mining block")]
-    InvalidExtraData,
+    #[error("block hash does not match its content")]
+    InvalidBlockHash,
+
+    #[error("unexpected block (expected id: {expected}, got: {got})")]
+    UnexpectedBlock { expected: u64, got: u64 },
+
+    #[error("block {0} is not preferred over the current head")]
+    StaleBlock(u64),
+
+    #[error("block {0} does not extend the current head")]
+    UnknownParent(u64),
+
+    #[error("block {0} cannot be reverted")]
+    IrreversibleBlock(u64),
+
+    #[error("block {id} is at or below the finalized height {finalized}")]
+    BelowFinalized { id: u64, finalized: u64 },
+
+    #[error("block {0} does not match the checkpoint at its height")]
+    CheckpointMismatch(u64),
+
+    #[error("invalid checkpoint hash at height {0}")]
+    InvalidCheckpoint(u64),
+
+    #[error("block {0} was pruned")]
+    BlockPruned(u64),
+
+    #[error("consensus error: ({0})")]
+    Consensus(#[from] ConsensusError),
 
     #[error("mempool full error")]
     MempoolFull,
+
+    #[error("invalid log range: (from: {from}, to: {to})")]
+    InvalidLogRange { from: u64, to: u64 },
+
+    #[error("invalid block range: (from: {from}, to: {to})")]
+    InvalidBlockRange { from: u64, to: u64 },
+
+    #[error("unknown asset: ({0})")]
+    UnknownAsset(AssetId),
+
+    #[error("invalid transaction: ({0})")]
+    InvalidTransaction(#[from] VmError),
 }
@@ -0,0 +1,78 @@
+use std::sync::Arc;
+
+use rm_reth_types::{
+    block::block::Block,
+    hash::Hash,
+    peers::{Peer, PeerId},
+};
+use tokio::sync::broadcast;
+
+use crate::consensus::bft::Message;
+
+/// Events kept for subscribers lagging behind, past which they miss the oldest.
+pub const EVENT_CAPACITY: usize = 1024;
+
+/// Change of the node state other components may react to.
+///
+/// Events are published in the order the changes happen: the blocks of the
+/// chain are announced one after the other by increasing id, a reorganisation
+/// before the blocks of the new branch, a transaction is accepted before the
+/// block including it.
+#[derive(Debug, Clone)]
+pub enum NodeEvent {
+    /// Block stored as the new head.
+    NewBlock(Arc<Block>),
+    /// Hash of a transaction admitted into the mempool.
+    TxAccepted(Hash),
+    /// Hash of a transaction taken out of the mempool that will not be included,
+    /// the block built with it having failed.
+    TxDropped(Hash),
+    /// The blocks above `ancestor` were replaced, `retracted` holding their
+    /// hashes oldest first. The blocks of the new branch follow as
+    /// [`NodeEvent::NewBlock`]s.
+    Reorg {
+        ancestor: u64,
+        retracted: Vec<Hash>,
+    },
+    /// BFT message of this validator, to be sent to every other one. Theirs are
+    /// handed back through [`NodeManager::receive_consensus_message`].
+    ///
+    /// [`NodeManager::receive_consensus_message`]: crate::manager::NodeManager::receive_consensus_message
+    Consensus(Arc<Message>),
+    PeerConnected(Peer),
+    PeerDisconnected(PeerId),
+}
+
+/// Fans [`NodeEvent`]s out to every subscriber.
+///
+/// Publishing never waits: a subscriber falling more than [`EVENT_CAPACITY`]
+/// events behind gets [`broadcast::error::RecvError::Lagged`] and is expected to
+/// resynchronise or give up.
+#[derive(Debug)]
+pub struct EventBus {
+    sender: broadcast::Sender<NodeEvent>,
+}
+
+impl EventBus {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+
+        Self { sender }
+    }
+
+    pub fn publish(&self, event: NodeEvent) {
+        // without subscribers there is nobody to tell
+        let _ = self.sender.send(event);
+    }
+
+    /// Receives the events published from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<NodeEvent> {
+        self.sender.subscribe()
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new(EVENT_CAPACITY)
+    }
+}
@@ -0,0 +1,120 @@
+use std::collections::BTreeMap;
+
+use config::FinalityConfig;
+use rm_reth_types::hash::Hash;
+
+use crate::error::NodeError;
+
+/// Rules deciding when an imported block becomes final.
+///
+/// A block is final once it is buried `confirmation_depth` blocks below the head,
+/// it is a checkpoint of the chain spec or a commit certificate proves it final.
+/// Final blocks far enough below the finalized height may then be pruned.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Finality {
+    confirmation_depth: Option<u64>,
+    checkpoints: BTreeMap<u64, Hash>,
+    retain_blocks: Option<u64>,
+}
+
+impl Finality {
+    pub fn new(
+        confirmation_depth: Option<u64>,
+        checkpoints: impl IntoIterator<Item = (u64, Hash)>,
+    ) -> Self {
+        Self {
+            confirmation_depth,
+            checkpoints: checkpoints.into_iter().collect(),
+            retain_blocks: None,
+        }
+    }
+
+    /// Prunes the blocks more than `retain` blocks below the finalized height
+    /// after every import.
+    pub fn with_retain_blocks(mut self, retain: u64) -> Self {
+        self.retain_blocks = Some(retain);
+        self
+    }
+
+    pub fn from_config(config: &FinalityConfig) -> Result<Self, NodeError> {
+        let checkpoints = config
+            .checkpoints
+            .iter()
+            .map(|(height, hash)| {
+                let bytes: [u8; 32] = hex::decode(hash.trim_start_matches("0x"))
+                    .ok()
+                    .and_then(|bytes| bytes.try_into().ok())
+                    .ok_or(NodeError::InvalidCheckpoint(*height))?;
+
+                Ok((*height, bytes.into()))
+            })
+            .collect::<Result<Vec<_>, NodeError>>()?;
+
+        Ok(Self {
+            retain_blocks: config.retain_blocks,
+            ..Self::new(config.confirmation_depth, checkpoints)
+        })
+    }
+
+    #[inline]
+    pub fn confirmation_depth(&self) -> Option<u64> {
+        self.confirmation_depth
+    }
+
+    #[inline]
+    pub fn retain_blocks(&self) -> Option<u64> {
+        self.retain_blocks
+    }
+
+    /// Hash the block at `height` must have, if it is a checkpoint.
+    #[inline]
+    pub fn checkpoint(&self, height: u64) -> Option<&Hash> {
+        self.checkpoints.get(&height)
+    }
+
+    /// Height final once the block at `height` is the head, `committed` telling
+    /// whether a commit certificate came with it.
+    pub fn finalized_by(&self, height: u64, committed: bool) -> u64 {
+        if committed || self.checkpoints.contains_key(&height) {
+            return height;
+        }
+
+        self.confirmation_depth
+            .map_or(0, |depth| height.saturating_sub(depth))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finalizes_by_depth_checkpoint_or_commit() {
+        let config = FinalityConfig {
+            confirmation_depth: Some(10),
+            checkpoints: vec![(5, format!("0x{}", "ab".repeat(32)))],
+            retain_blocks: Some(100),
+        };
+
+        let finality = Finality::from_config(&config).unwrap();
+        assert_eq!(finality.checkpoint(5), Some(&[0xab; 32].into()));
+        assert_eq!(finality.retain_blocks(), Some(100));
+
+        assert_eq!(finality.finalized_by(4, false), 0);
+        assert_eq!(finality.finalized_by(5, false), 5);
+        assert_eq!(finality.finalized_by(7, true), 7);
+        assert_eq!(finality.finalized_by(25, false), 15);
+
+        assert_eq!(Finality::default().finalized_by(25, false), 0);
+
+        let config = FinalityConfig {
+            confirmation_depth: None,
+            checkpoints: vec![(3, "abcd".into())],
+            retain_blocks: None,
+        };
+        assert!(matches!(
+            Finality::from_config(&config),
+            Err(NodeError::InvalidCheckpoint(3))
+        ));
+    }
+}
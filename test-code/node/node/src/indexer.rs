@@ -0,0 +1,95 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::RwLock,
+};
+
+use rm_reth_types::hash::Hash;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::events::NodeEvent;
+
+/// Position of a transaction in the chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TxLocation {
+    pub block_id: u64,
+    pub index: usize,
+}
+
+#[derive(Debug, Default)]
+struct Index {
+    locations: HashMap<Hash, TxLocation>,
+    blocks: BTreeMap<u64, Vec<Hash>>,
+}
+
+/// In-memory index of the transactions of the chain by hash, following the
+/// [`crate::events::EventBus`].
+///
+/// Only the blocks announced while following are indexed.
+#[derive(Debug, Default)]
+pub struct TxIndexer {
+    index: RwLock<Index>,
+}
+
+impl TxIndexer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, hash: &Hash) -> Option<TxLocation> {
+        self.index.read().unwrap().locations.get(hash).copied()
+    }
+
+    /// Id of the last block indexed.
+    pub fn head(&self) -> Option<u64> {
+        let index = self.index.read().unwrap();
+
+        index.blocks.last_key_value().map(|(id, _)| *id)
+    }
+
+    pub fn apply(&self, event: &NodeEvent) {
+        let mut index = self.index.write().unwrap();
+
+        match event {
+            NodeEvent::NewBlock(block) => {
+                let hashes: Vec<_> = block.data().tx_pool.iter().map(|tx| tx.hash()).collect();
+
+                for (position, hash) in hashes.iter().enumerate() {
+                    index.locations.insert(
+                        *hash,
+                        TxLocation {
+                            block_id: block.id(),
+                            index: position,
+                        },
+                    );
+                }
+
+                index.blocks.insert(block.id(), hashes);
+            }
+            NodeEvent::Reorg { ancestor, .. } => {
+                let retracted = index.blocks.split_off(&(ancestor + 1));
+
+                for hash in retracted.values().flatten() {
+                    index.locations.remove(hash);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Indexes the blocks announced on `events` until the bus closes.
+    ///
+    /// Fails with [`RecvError::Lagged`] once blocks were missed, the index then
+    /// having to be rebuilt.
+    pub async fn follow(
+        &self,
+        mut events: broadcast::Receiver<NodeEvent>,
+    ) -> Result<(), RecvError> {
+        loop {
+            match events.recv().await {
+                Ok(event) => self.apply(&event),
+                Err(RecvError::Closed) => return Ok(()),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
@@ -1,17 +1,32 @@
+pub mod consensus;
 pub mod error;
+pub mod events;
+pub mod finality;
+pub mod indexer;
 pub mod manager;
-// pub mod mining;
+pub mod producer;
 
 #[cfg(test)]
 mod test {
     use std::sync::Arc;
 
     use rand::{Rng, SeedableRng, rngs::StdRng};
-    use rm_reth_types::{Address, int::Uint256, tx::transaction::Transaction};
-    use storage::StorageManager;
+    use rm_reth_types::{
+        Address,
+        asset::{AssetId, AssetOp},
+        int::Uint256,
+        log::{Log, LogFilter},
+        tx::{
+            kind::{TransferLeg, TxKind},
+            transaction::Transaction,
+        },
+    };
+    use storage::{StorageManager, TableId};
     use tokio::time::{Duration, interval};
 
-    use crate::{error::NodeError, manager::NodeManager};
+    use vm::{error::VmError, execute::transfer_topic, gas::MAX_TRANSFER_LEGS};
+
+    use crate::{error::NodeError, events::NodeEvent, manager::NodeManager};
 
     fn addr(id: u8) -> Address {
         [id; 20].into()
@@ -128,4 +143,807 @@ mod test {
             "Total balance should be conserved"
         );
     }
+
+    #[test]
+    fn test_get_logs_uses_block_bloom() {
+        let node = NodeManager::genesis_with_storage(StorageManager::in_memory().unwrap()).unwrap();
+
+        node.mint(&addr(1), &Uint256::from(1000)).unwrap();
+
+        // block 1: 1 -> 2, block 2: 1 -> 3
+        for to in [addr(2), addr(3)] {
+            node.push_transaction(Transaction::new(addr(1), to, Uint256::from(10), vec![]))
+                .unwrap();
+
+            let tx_pool = node.process_execution_transaction().unwrap();
+            let block = node.create_block_with_processed_tx_pool(tx_pool);
+            node.mine_with_block(block, [0u8; 32].into()).unwrap();
+        }
+
+        let to_topic = |id| Some(Log::address_topic(&addr(id)));
+
+        let all = node
+            .get_logs(&LogFilter {
+                from: 0,
+                to: 2,
+                address: Some(addr(1)),
+                topics: vec![Some(transfer_topic())],
+            })
+            .unwrap();
+
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].block_id, 1);
+        assert_eq!(all[1].block_id, 2);
+
+        let only_to_3 = node
+            .get_logs(&LogFilter {
+                from: 0,
+                to: 2,
+                address: None,
+                topics: vec![None, None, to_topic(3)],
+            })
+            .unwrap();
+
+        assert_eq!(only_to_3.len(), 1);
+        assert_eq!(only_to_3[0].block_id, 2);
+
+        let block_1 = node.get_block(1).unwrap().unwrap();
+        assert!(
+            !LogFilter {
+                address: Some(addr(9)),
+                ..Default::default()
+            }
+            .may_match(&block_1.header().logs_bloom)
+        );
+
+        assert!(matches!(
+            node.get_logs(&LogFilter {
+                from: 2,
+                to: 1,
+                ..Default::default()
+            }),
+            Err(NodeError::InvalidLogRange { .. })
+        ));
+    }
+
+    #[test]
+    fn test_asset_ledger_persists_across_blocks() {
+        let node = NodeManager::genesis_with_storage(StorageManager::in_memory().unwrap()).unwrap();
+        let gold = AssetId::derive(&addr(1), "GOLD");
+
+        let blocks = [
+            vec![AssetOp::Issue {
+                symbol: "GOLD".into(),
+                decimals: 2,
+                initial_supply: Uint256::from(100),
+            }],
+            vec![
+                AssetOp::Transfer {
+                    asset: gold,
+                    to: addr(2),
+                    amount: Uint256::from(40),
+                },
+                AssetOp::Mint {
+                    asset: gold,
+                    to: addr(3),
+                    amount: Uint256::from(5),
+                },
+            ],
+        ];
+
+        for ops in blocks {
+            for op in ops {
+                node.push_transaction(Transaction::asset(addr(1), op))
+                    .unwrap();
+            }
+
+            let tx_pool = node.process_execution_transaction().unwrap();
+            let block = node.create_block_with_processed_tx_pool(tx_pool);
+            node.mine_with_block(block, [0u8; 32].into()).unwrap();
+        }
+
+        let metadata = node.get_asset(&gold).unwrap().unwrap();
+        assert_eq!(metadata.issuer, addr(1));
+        assert_eq!(metadata.total_supply, Uint256::from(105));
+
+        let balance = |id| node.get_asset_balance(&addr(id), &gold).unwrap();
+        assert_eq!(balance(1), Uint256::from(60));
+        assert_eq!(balance(2), Uint256::from(40));
+        assert_eq!(balance(3), Uint256::from(5));
+
+        let block_2 = node.get_block(2).unwrap().unwrap();
+        assert!(
+            block_2
+                .data()
+                .receipts
+                .iter()
+                .all(|receipt| receipt.success)
+        );
+        assert_eq!(block_2.data().asset_balances.len(), 3);
+    }
+
+    #[test]
+    fn test_mint_requires_authorised_minter() {
+        let node = NodeManager::genesis_with_storage(StorageManager::in_memory().unwrap()).unwrap();
+
+        node.add_minter(&addr(9)).unwrap();
+
+        for minter in [addr(9), addr(8)] {
+            node.push_transaction(Transaction::with_kind(
+                minter,
+                addr(1),
+                Uint256::from(500),
+                TxKind::Mint,
+            ))
+            .unwrap();
+        }
+
+        let tx_pool = node.process_execution_transaction().unwrap();
+        let block = node.create_block_with_processed_tx_pool(tx_pool);
+
+        let receipts = &block.data().receipts;
+        assert!(receipts[0].success);
+        assert!(!receipts[1].success);
+
+        node.mine_with_block(block, [0u8; 32].into()).unwrap();
+
+        let balance = node
+            .storage()
+            .get_ref(TableId::Balance)
+            .to_balance()
+            .get_or_default(&addr(1))
+            .unwrap();
+        assert_eq!(balance, Uint256::from(500));
+    }
+
+    #[test]
+    fn test_multi_transfer_pays_all_legs_in_one_receipt() {
+        let node = NodeManager::genesis_with_storage(StorageManager::in_memory().unwrap()).unwrap();
+
+        node.mint(&addr(1), &Uint256::from(1000)).unwrap();
+
+        let legs: Vec<TransferLeg> = (2..12)
+            .map(|id| TransferLeg {
+                to: addr(id),
+                amount: Uint256::from(id as u64),
+            })
+            .collect();
+
+        node.push_transaction(Transaction::multi_transfer(addr(1), legs.clone()))
+            .unwrap();
+
+        let oversized = vec![legs[0].clone(); MAX_TRANSFER_LEGS + 1];
+        assert!(matches!(
+            node.push_transaction(Transaction::multi_transfer(addr(1), oversized)),
+            Err(NodeError::InvalidTransaction(VmError::TooManyLegs { .. }))
+        ));
+
+        let tx_pool = node.process_execution_transaction().unwrap();
+        let block = node.create_block_with_processed_tx_pool(tx_pool);
+
+        assert_eq!(block.data().receipts.len(), 1);
+        assert_eq!(block.data().receipts[0].legs, legs);
+
+        node.mine_with_block(block, [0u8; 32].into()).unwrap();
+
+        let balance_db = node.storage().get_ref(TableId::Balance).to_balance();
+        assert_eq!(balance_db.get(&addr(1)).unwrap(), Some(Uint256::from(935)));
+        assert_eq!(balance_db.get(&addr(11)).unwrap(), Some(Uint256::from(11)));
+    }
+
+    #[test]
+    fn test_parallel_executor_builds_the_same_blocks() {
+        use vm::parallel::ParallelExecutor;
+
+        let node = |executor| {
+            let node = NodeManager::genesis_with_storage(StorageManager::in_memory().unwrap())
+                .unwrap()
+                .with_executor(executor);
+            for id in 1..=4 {
+                node.mint(&addr(id), &Uint256::from(100)).unwrap();
+            }
+            node
+        };
+
+        let sequential = node(None);
+        let parallel = node(Some(ParallelExecutor::new(4).with_min_chunk(1)));
+
+        let mut rng = StdRng::seed_from_u64(3);
+        let txs: Vec<_> = (0..60)
+            .map(|_| {
+                let amount = Uint256::from(rng.random_range(0..80u64));
+                Transaction::new(
+                    addr(rng.random_range(1..=4)),
+                    addr(rng.random_range(1..=6)),
+                    amount,
+                    vec![],
+                )
+            })
+            .collect();
+
+        for node in [&sequential, &parallel] {
+            for tx in &txs {
+                node.push_transaction(tx.clone()).unwrap();
+            }
+        }
+
+        let receipts = [&sequential, &parallel].map(|node| {
+            let tx_pool = node.process_execution_transaction().unwrap();
+            let block = node.create_block_with_processed_tx_pool(tx_pool);
+            let receipts = block.data().receipts.clone();
+
+            node.mine_with_block(block, [0u8; 32].into()).unwrap();
+            receipts
+        });
+
+        assert_eq!(receipts[0], receipts[1]);
+        assert!(receipts[0].iter().any(|receipt| !receipt.success));
+
+        for id in 1..=6 {
+            let balance = |node: &NodeManager| {
+                node.storage()
+                    .snapshot()
+                    .unwrap()
+                    .balance(&addr(id))
+                    .unwrap()
+            };
+            assert_eq!(balance(&sequential), balance(&parallel));
+        }
+    }
+
+    #[test]
+    fn test_multisig_accounts_are_registered_on_chain() {
+        use k256::ecdsa::SigningKey;
+        use rm_reth_types::multisig::{MultisigAccount, MultisigError, MultisigWitness, PublicKey};
+
+        let node = NodeManager::genesis_with_storage(StorageManager::in_memory().unwrap()).unwrap();
+
+        let keys: Vec<_> = (1..=3)
+            .map(|id| SigningKey::from_bytes(&[id; 32].into()).unwrap())
+            .collect();
+        let account =
+            MultisigAccount::new(2, keys.iter().map(PublicKey::from_signing_key).collect())
+                .unwrap();
+        let treasury = account.address();
+
+        node.mint(&addr(1), &Uint256::from(1000)).unwrap();
+        node.push_transaction(Transaction::multisig_create(
+            addr(1),
+            account.clone(),
+            Uint256::from(600),
+        ))
+        .unwrap();
+
+        let tx_pool = node.process_execution_transaction().unwrap();
+        let block = node.create_block_with_processed_tx_pool(tx_pool);
+        node.mine_with_block(block, [0u8; 32].into()).unwrap();
+
+        let unsigned = Transaction::new(treasury, addr(2), Uint256::from(600), vec![]);
+        assert!(matches!(
+            node.push_transaction(unsigned.clone()),
+            Err(NodeError::InvalidTransaction(VmError::Multisig(
+                MultisigError::MissingWitness(_)
+            )))
+        ));
+
+        let message = unsigned.signing_hash(0).unwrap();
+        let mut witness = MultisigWitness::new(account, 0);
+        witness.cosign(&keys[0], &message).unwrap();
+        witness.cosign(&keys[1], &message).unwrap();
+
+        let signed = unsigned.with_witness(witness).unwrap();
+        node.push_transaction(signed.clone()).unwrap();
+
+        let tx_pool = node.process_execution_transaction().unwrap();
+        let block = node.create_block_with_processed_tx_pool(tx_pool);
+        assert!(block.data().receipts[0].success);
+        node.mine_with_block(block, [0u8; 32].into()).unwrap();
+
+        let snapshot = node.storage().snapshot().unwrap();
+        assert_eq!(snapshot.balance(&addr(2)).unwrap(), Uint256::from(600));
+        assert_eq!(snapshot.nonce(&treasury).unwrap(), 1);
+
+        assert!(matches!(
+            node.push_transaction(signed),
+            Err(NodeError::InvalidTransaction(VmError::Multisig(
+                MultisigError::InvalidNonce {
+                    expected: 1,
+                    got: 0
+                }
+            )))
+        ));
+    }
+
+    #[test]
+    fn test_poa_seals_round_robin_and_rejects_foreign_signers() {
+        use k256::ecdsa::SigningKey;
+        use parity_scale_codec::Encode;
+        use rm_reth_types::multisig::PublicKey;
+
+        use crate::consensus::{
+            ConsensusError,
+            poa::{Poa, PoaSeal},
+        };
+
+        let keys: Vec<_> = (1..=3)
+            .map(|id| SigningKey::from_bytes(&[id; 32].into()).unwrap())
+            .collect();
+        let validators: Vec<_> = keys[..2].iter().map(PublicKey::from_signing_key).collect();
+
+        let producer = NodeManager::genesis_with_storage(StorageManager::in_memory().unwrap())
+            .unwrap()
+            .with_consensus(Poa::new(validators.clone()).with_signer(keys[1].clone()));
+        let follower = NodeManager::genesis_with_storage(StorageManager::in_memory().unwrap())
+            .unwrap()
+            .with_consensus(Poa::new(validators));
+
+        // block 1 belongs to validators[1]
+        let tx_pool = producer.process_execution_transaction().unwrap();
+        let block = producer.create_block_with_processed_tx_pool(tx_pool);
+        producer.mine_with_block(block, [0u8; 32].into()).unwrap();
+
+        let sealed = producer.get_block(1).unwrap().unwrap();
+
+        for (signer, expected) in [
+            (&keys[0], ConsensusError::WrongProposer { height: 1 }),
+            (
+                &keys[2],
+                ConsensusError::UnknownSigner(PublicKey::from_signing_key(&keys[2])),
+            ),
+        ] {
+            let mut forged = sealed.clone();
+            forged.seal = PoaSeal::sign(signer, &forged.hash()).encode();
+
+            assert!(matches!(
+                follower.import_block(forged),
+                Err(NodeError::Consensus(err)) if err == expected
+            ));
+        }
+
+        let mut tampered = sealed.clone();
+        tampered.seal[40] ^= 1;
+        assert!(matches!(
+            follower.import_block(tampered),
+            Err(NodeError::Consensus(ConsensusError::InvalidSignature))
+        ));
+
+        follower.import_block(sealed).unwrap();
+        assert_eq!(follower.get_block(1).unwrap().unwrap().id(), 1);
+
+        // block 2 belongs to validators[0], so the producer has to wait
+        let tx_pool = producer.process_execution_transaction().unwrap();
+        let block = producer.create_block_with_processed_tx_pool(tx_pool);
+        assert!(matches!(
+            producer.mine_with_block(block, [0u8; 32].into()),
+            Err(NodeError::Consensus(ConsensusError::NotProposer(2)))
+        ));
+    }
+
+    #[test]
+    fn test_poa_follows_the_elected_validators() {
+        use k256::ecdsa::SigningKey;
+        use parity_scale_codec::Encode;
+        use rm_reth_types::{multisig::PublicKey, staking::prove_possession};
+
+        use crate::consensus::{
+            ConsensusError,
+            poa::{Poa, PoaSeal},
+        };
+
+        let keys: Vec<_> = (1..=2)
+            .map(|id| SigningKey::from_bytes(&[id; 32].into()).unwrap())
+            .collect();
+        let public_key = PublicKey::from_signing_key(&keys[1]);
+
+        let validators = vec![PublicKey::from_signing_key(&keys[0])];
+        let node = NodeManager::genesis_with_storage(StorageManager::in_memory().unwrap())
+            .unwrap()
+            .with_consensus(Poa::new(validators).with_signer(keys[0].clone()));
+
+        let stake = |id: u8| {
+            Transaction::with_kind(
+                addr(id),
+                addr(id),
+                Uint256::from(100),
+                TxKind::Stake {
+                    public_key,
+                    proof: prove_possession(&keys[1], &addr(id)),
+                },
+            )
+        };
+
+        node.mint(&addr(2), &Uint256::from(1000)).unwrap();
+        node.mint(&addr(3), &Uint256::from(1000)).unwrap();
+        node.push_transaction(stake(2)).unwrap();
+
+        // blocks 1..=99 are the configured validator's, the last one elects addr(2)
+        for _ in 1..100 {
+            let tx_pool = node.process_execution_transaction().unwrap();
+            let block = node.create_block_with_processed_tx_pool(tx_pool);
+            node.mine_with_block(block, [0u8; 32].into()).unwrap();
+        }
+
+        // the key belongs to addr(2) now, whoever else holds it
+        let simulation = node.simulate_transaction(&stake(3)).unwrap();
+        assert_eq!(simulation.failure, Some(VmError::StakeKeyTaken(addr(2))));
+
+        let tx_pool = node.process_execution_transaction().unwrap();
+        let block = node.create_block_with_processed_tx_pool(tx_pool);
+        assert!(matches!(
+            node.mine_with_block(block, [0u8; 32].into()),
+            Err(NodeError::Consensus(ConsensusError::NotProposer(100)))
+        ));
+
+        let tx_pool = node.process_execution_transaction().unwrap();
+        let mut block = node.create_block_with_processed_tx_pool(tx_pool);
+        block.set_hash();
+        block.seal = PoaSeal::sign(&keys[1], &block.hash()).encode();
+        node.import_block(block).unwrap();
+
+        assert_eq!(node.height(), 100);
+    }
+
+    #[test]
+    fn test_consensus_engine_from_config() {
+        use config::ConsensusConfig;
+
+        use crate::consensus::{ConsensusError, from_config};
+
+        // compressed secp256k1 generator point
+        let key = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798".to_string();
+
+        for (config, name) in [
+            (ConsensusConfig::Pow { difficulty: 4 }, "pow"),
+            (ConsensusConfig::Dev, "dev"),
+            (
+                ConsensusConfig::Poa {
+                    validators: vec![key],
+                    signer: None,
+                },
+                "poa",
+            ),
+        ] {
+            assert_eq!(from_config(&config).unwrap().name(), name);
+        }
+
+        let invalid = ConsensusConfig::Poa {
+            validators: vec!["02ff".into()],
+            signer: None,
+        };
+        assert!(matches!(
+            from_config(&invalid),
+            Err(ConsensusError::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_pow_requires_work_and_rejects_stale_blocks() {
+        use crate::{
+            consensus::{
+                ConsensusError,
+                pow::{Pow, leading_zero_bits},
+            },
+            producer::mine_block,
+        };
+
+        let node = NodeManager::genesis_with_storage(StorageManager::in_memory().unwrap())
+            .unwrap()
+            .with_consensus(Pow::new(8));
+
+        // a block without enough work is refused
+        let tx_pool = node.process_execution_transaction().unwrap();
+        let mut lazy = node.create_block_with_processed_tx_pool(tx_pool);
+        assert_eq!(lazy.header().difficulty, 8);
+
+        for nonce in 0u64.. {
+            let mut extra_data = [0u8; 32];
+            extra_data[..8].copy_from_slice(&nonce.to_le_bytes());
+
+            lazy.header_mut().extra_data = extra_data.into();
+            lazy.set_hash();
+
+            if leading_zero_bits(&lazy.hash()) < 8 {
+                break;
+            }
+        }
+
+        assert!(matches!(
+            node.import_block(lazy),
+            Err(NodeError::Consensus(ConsensusError::InsufficientWork { .. }))
+        ));
+
+        // sealing searches the nonce
+        assert_eq!(mine_block(&node, [0u8; 32].into()).unwrap(), 1);
+        assert_eq!(mine_block(&node, [0u8; 32].into()).unwrap(), 2);
+
+        let mined = node.get_block(1).unwrap().unwrap();
+        assert!(leading_zero_bits(&mined.hash()) >= 8);
+        assert!(matches!(
+            node.import_block(mined),
+            Err(NodeError::StaleBlock(1))
+        ));
+    }
+
+    #[test]
+    fn test_finalized_height_refuses_reorgs_and_bounds_pruning() {
+        use crate::{consensus::dev::Dev, finality::Finality};
+
+        let mine = |node: &NodeManager| {
+            let tx_pool = node.process_execution_transaction().unwrap();
+            let block = node.create_block_with_processed_tx_pool(tx_pool);
+            node.mine_with_block(block, [0u8; 32].into())
+        };
+
+        let node = NodeManager::genesis_with_storage(StorageManager::in_memory().unwrap())
+            .unwrap()
+            .with_consensus(Dev)
+            .with_finality(Finality::new(Some(2), [(4, [0xee; 32].into())]));
+
+        assert_eq!(node.get_finalized_height(), 0);
+
+        for _ in 1..=3 {
+            mine(&node).unwrap();
+        }
+        assert_eq!(node.get_finalized_height(), 1);
+
+        // block 4 is not the one of the chain spec
+        assert!(matches!(mine(&node), Err(NodeError::CheckpointMismatch(4))));
+
+        let final_block = node.get_block(1).unwrap().unwrap();
+        assert!(matches!(
+            node.import_block(final_block),
+            Err(NodeError::BelowFinalized {
+                id: 1,
+                finalized: 1
+            })
+        ));
+
+        let path = std::env::temp_dir().join(format!("pruning-{}.redb", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let storage = StorageManager::create_or_open(&path).unwrap();
+        let node = NodeManager::genesis_with_storage(storage)
+            .unwrap()
+            .with_consensus(Dev)
+            .with_finality(Finality::new(Some(2), []).with_retain_blocks(1));
+
+        for _ in 1..=6 {
+            mine(&node).unwrap();
+        }
+        assert_eq!(node.get_finalized_height(), 4);
+
+        // imports pruned the final blocks at least one below the finalized height
+        assert!(node.get_block(0).unwrap().is_some());
+        assert!(node.get_block(2).unwrap().is_none());
+        assert!(node.get_block(3).unwrap().is_some());
+        assert_eq!(node.prune_blocks(1).unwrap(), 3);
+        drop(node);
+
+        // both heights outlive a restart
+        let storage = StorageManager::create_or_open(&path).unwrap();
+        let node = NodeManager::open_with_storage(storage)
+            .unwrap()
+            .with_consensus(Dev);
+        assert_eq!(node.height(), 6);
+        assert_eq!(node.get_finalized_height(), 4);
+
+        let filter = LogFilter {
+            from: 2,
+            to: 6,
+            address: None,
+            topics: vec![],
+        };
+        assert!(matches!(
+            node.get_logs(&filter),
+            Err(NodeError::BlockPruned(2))
+        ));
+
+        // and the chain goes on from the stored head
+        mine(&node).unwrap();
+        let head = node.get_block(6).unwrap().unwrap();
+        assert_eq!(node.get_header(7).unwrap().unwrap().prev_block, head.hash());
+
+        drop(node);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_events_announce_transactions_and_blocks() {
+        let node = NodeManager::genesis_with_storage(StorageManager::in_memory().unwrap()).unwrap();
+        node.mint(&addr(1), &Uint256::from(1000)).unwrap();
+
+        let mut events = node.events().subscribe();
+
+        let tx = Transaction::new(addr(1), addr(2), Uint256::from(10), vec![]);
+        node.push_transaction(tx.clone()).unwrap();
+
+        let tx_pool = node.process_execution_transaction().unwrap();
+        let block = node.create_block_with_processed_tx_pool(tx_pool);
+        node.mine_with_block(block, [0u8; 32].into()).unwrap();
+
+        let Ok(NodeEvent::TxAccepted(hash)) = events.try_recv() else {
+            panic!("expected the admitted transaction first");
+        };
+        assert_eq!(hash, tx.hash());
+
+        let Ok(NodeEvent::NewBlock(block)) = events.try_recv() else {
+            panic!("expected the mined block");
+        };
+        assert_eq!(block.id(), 1);
+        assert_eq!(block.data().tx_pool, vec![tx]);
+
+        assert!(events.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_longer_branch_reorganises_the_chain() {
+        use crate::consensus::dev::Dev;
+
+        let node = || {
+            let node = NodeManager::genesis_with_storage(StorageManager::in_memory().unwrap())
+                .unwrap()
+                .with_consensus(Dev);
+            node.mint(&addr(1), &Uint256::from(1000)).unwrap();
+            node
+        };
+        let mine = |node: &NodeManager, to: u8, tag: u8| {
+            node.push_transaction(Transaction::new(addr(1), addr(to), Uint256::from(100), vec![]))
+                .unwrap();
+            let tx_pool = node.process_execution_transaction().unwrap();
+            let block = node.create_block_with_processed_tx_pool(tx_pool);
+            node.mine_with_block(block, [tag; 32].into()).unwrap();
+            node.get_latest_block().unwrap().unwrap()
+        };
+        let balance = |node: &NodeManager, id: u8| {
+            node.storage().snapshot().unwrap().balance(&addr(id)).unwrap()
+        };
+
+        let (chain, fork) = (node(), node());
+
+        let retracted: Vec<_> = (0..2).map(|_| mine(&chain, 2, 0).get_hash()).collect();
+        let branch: Vec<_> = (0..3).map(|_| mine(&fork, 3, 1)).collect();
+
+        let mut events = chain.events().subscribe();
+
+        // shorter than the chain, the branch is only kept
+        for block in &branch[..2] {
+            assert!(matches!(
+                chain.import_block(block.clone()),
+                Err(NodeError::StaleBlock(_))
+            ));
+        }
+        assert_eq!(chain.get_block(1).unwrap().unwrap().get_hash(), retracted[0]);
+
+        chain.import_block(branch[2].clone()).unwrap();
+
+        let Ok(NodeEvent::Reorg { ancestor, retracted: hashes }) = events.try_recv() else {
+            panic!("expected the reorganisation first");
+        };
+        assert_eq!(ancestor, 0);
+        assert_eq!(hashes, retracted);
+
+        for block in &branch {
+            let Ok(NodeEvent::NewBlock(new)) = events.try_recv() else {
+                panic!("expected the blocks of the new branch");
+            };
+            assert_eq!(new.get_hash(), block.get_hash());
+        }
+        assert!(events.try_recv().is_err());
+
+        // the state of the old branch is gone
+        assert_eq!(chain.height(), 3);
+        assert_eq!(balance(&chain, 2), Uint256::zero());
+        assert_eq!(balance(&chain, 3), Uint256::from(300));
+        assert_eq!(balance(&chain, 1), balance(&fork, 1));
+
+        assert_eq!(mine(&chain, 2, 0).header().prev_block, branch[2].get_hash());
+    }
+
+    #[tokio::test]
+    async fn test_events_arrive_in_chain_order() {
+        use rm_reth_types::socket::SocketAddrCodec;
+
+        use crate::{indexer::TxIndexer, producer::produce_block};
+
+        let node = NodeManager::genesis_with_storage(StorageManager::in_memory().unwrap()).unwrap();
+        node.mint(&addr(1), &Uint256::from(1000)).unwrap();
+
+        let indexer = Arc::new(TxIndexer::new());
+        let following = tokio::spawn({
+            let indexer = indexer.clone();
+            let events = node.events().subscribe();
+            async move { indexer.follow(events).await }
+        });
+
+        let mut events = node.events().subscribe();
+
+        let peer = (7, SocketAddrCodec("127.0.0.1:30303".parse().unwrap()));
+        node.connect_peer(peer);
+        node.connect_peer(peer);
+        assert!(node.disconnect_peer(7));
+        assert!(!node.disconnect_peer(7));
+
+        let txs: Vec<_> = (0..3)
+            .map(|amount| Transaction::new(addr(1), addr(2), Uint256::from(amount + 1), vec![]))
+            .collect();
+
+        for tx in &txs {
+            node.push_transaction(tx.clone()).unwrap();
+            produce_block(&node).unwrap();
+        }
+
+        assert!(
+            matches!(events.try_recv(), Ok(NodeEvent::PeerConnected(connected)) if connected == peer)
+        );
+        assert!(matches!(
+            events.try_recv(),
+            Ok(NodeEvent::PeerDisconnected(7))
+        ));
+
+        for (id, tx) in (1..).zip(&txs) {
+            let Ok(NodeEvent::TxAccepted(hash)) = events.try_recv() else {
+                panic!("expected the transaction of block {id} first");
+            };
+            assert_eq!(hash, tx.hash());
+
+            let Ok(NodeEvent::NewBlock(block)) = events.try_recv() else {
+                panic!("expected block {id}");
+            };
+            assert_eq!(block.id(), id);
+            assert_eq!(
+                block.header().prev_block,
+                node.get_block(id - 1).unwrap().unwrap().get_hash()
+            );
+        }
+
+        assert!(events.try_recv().is_err());
+
+        // the indexer saw the same blocks, in the same order
+        while indexer.head() != Some(3) {
+            tokio::task::yield_now().await;
+        }
+
+        for (id, tx) in (1..).zip(&txs) {
+            let location = indexer.get(&tx.hash()).unwrap();
+            assert_eq!((location.block_id, location.index), (id, 0));
+        }
+
+        // a reorganisation retracts the replaced blocks from the index
+        indexer.apply(&NodeEvent::Reorg {
+            ancestor: 1,
+            retracted: vec![],
+        });
+        assert_eq!(indexer.head(), Some(1));
+        assert!(indexer.get(&txs[0].hash()).is_some());
+        assert!(indexer.get(&txs[2].hash()).is_none());
+
+        drop(node);
+        assert!(following.await.unwrap().is_ok());
+    }
+
+    #[test]
+    fn test_failed_blocks_drop_their_transactions() {
+        use crate::{finality::Finality, producer::produce_block};
+
+        // block 1 cannot match the checkpoint, so it fails to be imported
+        let node = NodeManager::genesis_with_storage(StorageManager::in_memory().unwrap())
+            .unwrap()
+            .with_finality(Finality::new(None, [(1, [0xee; 32].into())]));
+        node.mint(&addr(1), &Uint256::from(1000)).unwrap();
+
+        let mut events = node.events().subscribe();
+
+        let tx = Transaction::new(addr(1), addr(2), Uint256::from(10), vec![]);
+        node.push_transaction(tx.clone()).unwrap();
+
+        assert!(matches!(
+            produce_block(&node),
+            Err(NodeError::CheckpointMismatch(1))
+        ));
+        assert_eq!(node.mempool().len(), 0);
+
+        assert!(matches!(events.try_recv(), Ok(NodeEvent::TxAccepted(hash)) if hash == tx.hash()));
+        assert!(matches!(events.try_recv(), Ok(NodeEvent::TxDropped(hash)) if hash == tx.hash()));
+        assert!(events.try_recv().is_err());
+    }
 }
@@ -1,48 +1,153 @@
-use arc_swap::ArcSwap;
+use arc_swap::{ArcSwap, ArcSwapOption};
+use config::get_config;
 use rm_reth_types::{
-    Address, block::block::Block, bytes::FixedBytes, hash::Hash, int::Uint256, peers::PeerPool, tx::{queue::TransactionQueue, transaction::Transaction}
+    Address,
+    asset::{AssetId, AssetMetadata},
+    block::block::{Block, Header},
+    bytes::FixedBytes,
+    finality::CommitCertificate,
+    hash::Hash,
+    htlc::Htlc,
+    int::Uint256,
+    log::{LogEntry, LogFilter},
+    peers::{Peer, PeerId, PeerPool},
+    staking::{EpochValidators, ValidatorRecord},
+    tx::{pool_helper::TxPoolHelper, queue::TransactionQueue, transaction::Transaction},
+    vesting::VestingStatus,
 };
-use storage::{StorageManager, TableId, error::StorageError};
-use vm::VmPool;
+use storage::{StorageManager, TableId, WriteBatch, error::StorageError};
+use tokio::sync::{Notify, broadcast};
+use vm::{VmPool, parallel::ParallelExecutor, simulate::Simulation};
 
-use std::sync::{
-    Arc,
-    atomic::{AtomicU64, Ordering},
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
 };
 
-use crate::error::NodeError;
+use crate::{
+    consensus::{self, ConsensusEngine, bft::Message},
+    error::NodeError,
+    events::{EVENT_CAPACITY, EventBus, NodeEvent},
+    finality::Finality,
+};
+
+/// Maximum number of blocks a single `get_logs` call may scan.
+pub const MAX_LOG_RANGE: u64 = 10_000;
+
+/// Maximum number of blocks a single `get_block_range` call may return.
+pub const MAX_BLOCK_RANGE: u64 = 100;
+
+/// Blocks kept off the chain in case their branch overtakes it.
+pub const MAX_SIDE_BLOCKS: usize = 256;
+
+/// Occupancy of the mempool, which cannot be listed without draining it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MempoolStatus {
+    pub pending: usize,
+    pub capacity: usize,
+    /// Bytes of the transactions admitted through `push_transaction`.
+    pub pending_size: u64,
+    /// Whether a full block worth of transactions is waiting.
+    pub ready: bool,
+}
 
 pub struct NodeManager {
     storage: StorageManager,
     current_block_id: AtomicU64,
     prev_block_hash: ArcSwap<Hash>,
+    head: ArcSwap<Header>,
+    last_import: ArcSwapOption<Instant>,
+    block_time: ArcSwapOption<Duration>,
     mempool: TransactionQueue,
     max_mempool_size: usize,
-    peer_pool: PeerPool
+    // bytes of the transactions admitted through `push_transaction` still queued
+    pending_size: AtomicU64,
+    block_ready: Notify,
+    peer_pool: PeerPool,
+    consensus: Box<dyn ConsensusEngine>,
+    finality: Finality,
+    finalized: AtomicU64,
+    // held while a block is checked and written, so the head it was checked
+    // against cannot move in between
+    import_lock: Mutex<()>,
+    // blocks below this id, genesis aside, were pruned
+    pruned: AtomicU64,
+    events: EventBus,
+    // BFT messages of the other validators, for the rounds the producer runs
+    consensus_inbox: broadcast::Sender<Message>,
+    // valid blocks above the finalized height that are not on the chain, by hash
+    side_blocks: Mutex<HashMap<Hash, (Block, Option<CommitCertificate>)>>,
+    // runs the transactions of the blocks this node builds, if not sequentially
+    executor: Option<ParallelExecutor>,
 }
 
 impl NodeManager {
-    pub fn new(block_id: u64) -> Self {
-        Self {
-            storage: StorageManager::new_default().unwrap(),
+    pub fn new(block_id: u64) -> Result<Self, NodeError> {
+        Ok(Self {
+            storage: StorageManager::new_default()?,
             current_block_id: AtomicU64::new(block_id),
             prev_block_hash: ArcSwap::new(Arc::new(Hash::empty())),
+            head: ArcSwap::new(Arc::new(Header {
+                block_id: block_id.saturating_sub(1),
+                ..Header::empty()
+            })),
+            last_import: ArcSwapOption::empty(),
+            block_time: ArcSwapOption::empty(),
             mempool: TransactionQueue::new(100),
             max_mempool_size: 100,
+            pending_size: AtomicU64::new(0),
+            block_ready: Notify::new(),
             peer_pool: PeerPool::new(),
-        }
+            consensus: consensus::from_config(&get_config().consensus)?,
+            finality: Finality::from_config(&get_config().finality)?,
+            finalized: AtomicU64::new(0),
+            import_lock: Mutex::new(()),
+            pruned: AtomicU64::new(0),
+            events: EventBus::default(),
+            consensus_inbox: broadcast::channel(EVENT_CAPACITY).0,
+            side_blocks: Mutex::new(HashMap::new()),
+            executor: get_config()
+                .execution
+                .parallel_workers
+                .map(ParallelExecutor::new),
+        })
     }
 
     pub fn genesis() -> Result<Self, NodeError> {
+        Self::genesis_with_storage(StorageManager::new_default()?)
+    }
+
+    pub fn genesis_with_storage(storage: StorageManager) -> Result<Self, NodeError> {
         let genesis_block = Block::genesis();
 
         let block = Self {
-            storage: StorageManager::new_default().unwrap(),
+            storage,
             current_block_id: AtomicU64::new(1),
             prev_block_hash: ArcSwap::new(Arc::new(genesis_block.hash())),
+            head: ArcSwap::new(Arc::new(genesis_block.header().clone())),
+            last_import: ArcSwapOption::empty(),
+            block_time: ArcSwapOption::empty(),
             mempool: TransactionQueue::new(100),
             max_mempool_size: 100,
+            pending_size: AtomicU64::new(0),
+            block_ready: Notify::new(),
             peer_pool: PeerPool::new(),
+            consensus: consensus::from_config(&get_config().consensus)?,
+            finality: Finality::from_config(&get_config().finality)?,
+            finalized: AtomicU64::new(0),
+            import_lock: Mutex::new(()),
+            pruned: AtomicU64::new(0),
+            events: EventBus::default(),
+            consensus_inbox: broadcast::channel(EVENT_CAPACITY).0,
+            side_blocks: Mutex::new(HashMap::new()),
+            executor: get_config()
+                .execution
+                .parallel_workers
+                .map(ParallelExecutor::new),
         };
 
         block
@@ -55,6 +160,62 @@ impl NodeManager {
         Ok(block)
     }
 
+    /// Resumes the chain stored in `storage`, from genesis if it holds none yet.
+    ///
+    /// The latest stored block becomes the head, and the finalized height and
+    /// pruned blocks are the ones recorded by the imports and pruning before.
+    pub fn open_with_storage(storage: StorageManager) -> Result<Self, NodeError> {
+        let (head, finalized, pruned) = {
+            let snapshot = storage.snapshot()?;
+
+            (
+                snapshot.latest_block()?,
+                snapshot.finalized_height()?,
+                snapshot.pruned_height()?,
+            )
+        };
+
+        let node = Self::genesis_with_storage(storage)?;
+
+        if let Some(head) = head {
+            node.current_block_id
+                .store(head.id() + 1, Ordering::Release);
+            node.prev_block_hash.store(Arc::new(head.hash()));
+            node.head.store(Arc::new(head.header().clone()));
+        }
+
+        node.finalized.store(finalized, Ordering::Release);
+        node.pruned.store(pruned, Ordering::Release);
+        node.elect_validators();
+
+        Ok(node)
+    }
+
+    /// Replaces the consensus engine blocks are sealed and validated with.
+    pub fn with_consensus(mut self, consensus: impl ConsensusEngine + 'static) -> Self {
+        self.consensus = Box::new(consensus);
+        self.elect_validators();
+        self
+    }
+
+    /// Replaces the rules deciding when imported blocks become final.
+    pub fn with_finality(mut self, finality: Finality) -> Self {
+        self.finality = finality;
+        self
+    }
+
+    /// Runs the transactions of the blocks this node builds on `executor`, or
+    /// one after the other if `None`.
+    pub fn with_executor(mut self, executor: Option<ParallelExecutor>) -> Self {
+        self.executor = executor;
+        self
+    }
+
+    #[inline]
+    pub fn consensus(&self) -> &dyn ConsensusEngine {
+        self.consensus.as_ref()
+    }
+
     #[inline]
     pub fn mempool(&self) -> &TransactionQueue {
         &self.mempool
@@ -65,6 +226,10 @@ impl NodeManager {
         &self.storage
     }
 
+    pub fn events(&self) -> &EventBus {
+        &self.events
+    }
+
     #[inline]
     pub fn current_block_id(&self) -> &AtomicU64 {
         &self.current_block_id
@@ -75,6 +240,26 @@ impl NodeManager {
         &self.peer_pool
     }
 
+    /// Adds `peer` to the peer pool, announcing it unless it was known already.
+    pub fn connect_peer(&self, peer: Peer) {
+        let (id, addr) = peer;
+
+        if self.peer_pool.insert(id, addr).is_none() {
+            self.events.publish(NodeEvent::PeerConnected(peer));
+        }
+    }
+
+    /// Removes the peer `id` from the peer pool, returning whether it was known.
+    pub fn disconnect_peer(&self, id: PeerId) -> bool {
+        let known = self.peer_pool.remove(&id).is_some();
+
+        if known {
+            self.events.publish(NodeEvent::PeerDisconnected(id));
+        }
+
+        known
+    }
+
     pub fn mint(&self, addr: &Address, value: &Uint256) -> Result<(), StorageError> {
         self.storage
             .get_ref(storage::TableId::Balance)
@@ -83,10 +268,27 @@ impl NodeManager {
         Ok(())
     }
 
+    /// Allows `addr` to send [`TxKind::Mint`](rm_reth_types::tx::kind::TxKind::Mint)
+    /// transactions.
+    pub fn add_minter(&self, addr: &Address) -> Result<(), StorageError> {
+        self.storage
+            .get_ref(storage::TableId::Minter)
+            .to_minter()
+            .insert(addr, &true)?;
+        Ok(())
+    }
+
     pub fn process_execution_transaction(&self) -> Result<VmPool<'_>, NodeError> {
         let mut txs = Vec::with_capacity(100);
 
         while let Some(tx) = self.mempool.pop() {
+            let size = tx.size() as u64;
+            let _ = self
+                .pending_size
+                .fetch_update(Ordering::AcqRel, Ordering::Acquire, |pending| {
+                    Some(pending.saturating_sub(size))
+                });
+
             txs.push(tx);
 
             if txs.len() >= self.max_mempool_size {
@@ -94,10 +296,20 @@ impl NodeManager {
             }
         }
 
-        let mut pool = VmPool::from_tx_pool(&self.storage, &txs)
-            .map_err(|e| NodeError::ProcessBlockError(e.into()))?;
+        let block_height = self.current_block_id.load(Ordering::Acquire);
+
+        let mut pool = match VmPool::from_tx_pool(&self.storage, &txs) {
+            Ok(pool) => pool.at_height(block_height),
+            Err(e) => {
+                self.drop_transactions(&txs);
+                return Err(NodeError::ProcessBlockError(e.into()));
+            }
+        };
 
-        pool.process_tx(&txs);
+        match &self.executor {
+            Some(executor) => pool.process_tx_parallel(&txs, executor),
+            None => pool.process_tx(&txs),
+        }
 
         Ok(pool)
     }
@@ -106,16 +318,36 @@ impl NodeManager {
         let prev_id = self.current_block_id.load(Ordering::Acquire);
 
         let VmPool {
-            tx_pool, tokens, ..
+            tx_pool,
+            tokens,
+            receipts,
+            assets,
+            htlcs,
+            vesting,
+            staking,
+            multisig,
+            ..
         } = tx_pool;
 
+        let (assets, asset_balances) = assets.into_block_parts();
+        let (vesting, vesting_offers) = vesting.into_block_parts();
+
         let prev_block_hash = self.prev_block_hash.load();
 
-        let block = Block::new()
+        let mut block = Block::new()
             .with_block_id(prev_id)
             .set_prev_hash(**prev_block_hash)
             .with_transactions(&tx_pool)
-            .with_vm_processed(tokens);
+            .with_vm_processed(tokens)
+            .with_assets(assets, asset_balances)
+            .with_htlcs(htlcs.into_block_parts())
+            .with_vesting(vesting, vesting_offers)
+            .with_validators(staking.into_block_parts())
+            .with_multisigs(multisig.into_block_parts())
+            .with_receipts(receipts);
+
+        self.consensus
+            .prepare_header(&self.head.load(), block.header_mut());
 
         block
     }
@@ -129,47 +361,697 @@ impl NodeManager {
 
         block.set_hash();
 
-        // TODO: Mining verify
+        self.consensus.seal(&mut block)?;
+
+        self.import_block(block)
+    }
+
+    /// Appends `block` to the chain once the consensus engine prefers it over the
+    /// head and its seal is valid.
+    ///
+    /// A preferred block on another branch reorganises the chain: the blocks above
+    /// the common ancestor are reverted, [`NodeEvent::Reorg`] announces them and
+    /// the blocks of the new branch follow as [`NodeEvent::NewBlock`]s. A valid
+    /// block that is not preferred is refused as stale but kept, so that its
+    /// branch can later overtake the chain. Blocks at or below the finalized
+    /// height are refused before anything else, whichever branch they are on.
+    ///
+    /// The blocks and everything they carry are written in a single write
+    /// transaction, and the head only advances once that transaction committed.
+    pub fn import_block(&self, block: Block) -> Result<(), NodeError> {
+        self.import(block, None)
+    }
+
+    /// Imports `block` as [`NodeManager::import_block`] does, together with the
+    /// certificate proving it final, which is stored next to it.
+    pub fn commit_block(&self, block: Block, commit: CommitCertificate) -> Result<(), NodeError> {
+        self.import(block, Some(commit))
+    }
+
+    fn import(&self, block: Block, commit: Option<CommitCertificate>) -> Result<(), NodeError> {
+        let _guard = self.import_lock.lock().expect("import lock poisoned");
+
+        let finalized = self.finalized.load(Ordering::Acquire);
+
+        if block.id() <= finalized {
+            return Err(NodeError::BelowFinalized {
+                id: block.id(),
+                finalized,
+            });
+        }
+
+        if block.header().prev_block != **self.prev_block_hash.load() {
+            return self.import_branch(block, commit, finalized);
+        }
+
+        if !self
+            .consensus
+            .fork_choice(&self.head.load(), block.header())
+        {
+            return Err(NodeError::StaleBlock(block.id()));
+        }
+
+        let expected = self.current_block_id.load(Ordering::Acquire);
+
+        if block.id() != expected {
+            return Err(NodeError::UnexpectedBlock {
+                expected,
+                got: block.id(),
+            });
+        }
+
+        self.check_block(&block, commit.as_ref())?;
+
+        let batch = self.storage.batch()?;
+        let branch = [(block, commit)];
+
+        let finalized_by = self.write_branch(&batch, &branch, finalized)?;
+
+        batch.commit()?;
+
+        let [(block, _)] = branch;
+        self.advance(&block, finalized_by);
+
+        self.events.publish(NodeEvent::NewBlock(Arc::new(block)));
+        self.prune_after_import();
+
+        Ok(())
+    }
+
+    /// Imports `block`, a child of another block than the head.
+    ///
+    /// Its parent has to be a block of the chain or one kept from an earlier
+    /// import on its branch. If the consensus engine prefers it over the head,
+    /// the blocks above the common ancestor are reverted and the branch becomes
+    /// the chain, otherwise the block is kept in case its branch later does.
+    fn import_branch(
+        &self,
+        block: Block,
+        commit: Option<CommitCertificate>,
+        finalized: u64,
+    ) -> Result<(), NodeError> {
+        let id = block.id();
+
+        self.check_block(&block, commit.as_ref())?;
+
+        if self
+            .get_block(id)?
+            .is_some_and(|stored| stored.get_hash() == block.get_hash())
+        {
+            return Err(NodeError::StaleBlock(id));
+        }
+
+        let mut side_blocks = self.side_blocks.lock().expect("side blocks lock poisoned");
+
+        // the branch down to a block of the chain, newest first
+        let mut branch = vec![(block, commit)];
+
+        let ancestor = loop {
+            let tip = &branch[branch.len() - 1].0;
+            let parent_id = tip
+                .id()
+                .checked_sub(1)
+                .ok_or(NodeError::UnknownParent(id))?;
+            let parent_hash = tip.header().prev_block;
+
+            if self
+                .get_block(parent_id)?
+                .is_some_and(|parent| parent.get_hash() == parent_hash)
+            {
+                break parent_id;
+            }
+
+            match side_blocks.get(&parent_hash) {
+                Some(parent) if parent.0.id() == parent_id => branch.push(parent.clone()),
+                _ => return Err(NodeError::UnknownParent(id)),
+            }
+        };
+
+        if !self
+            .consensus
+            .fork_choice(&self.head.load(), branch[0].0.header())
+        {
+            if side_blocks.len() < MAX_SIDE_BLOCKS {
+                let (block, commit) = branch.swap_remove(0);
+                side_blocks.insert(block.get_hash(), (block, commit));
+            }
+
+            return Err(NodeError::StaleBlock(id));
+        }
+
+        if ancestor < finalized {
+            return Err(NodeError::BelowFinalized {
+                id: ancestor + 1,
+                finalized,
+            });
+        }
+
+        branch.reverse();
+
+        let head = self.current_block_id.load(Ordering::Acquire) - 1;
+        let retracted = (ancestor + 1..=head)
+            .map(|id| self.get_block(id)?.ok_or(NodeError::IrreversibleBlock(id)))
+            .collect::<Result<Vec<_>, NodeError>>()?;
+
+        let batch = self.storage.batch()?;
+
+        for block in retracted.iter().rev() {
+            if !batch.revert_block(block.id())? {
+                return Err(NodeError::IrreversibleBlock(block.id()));
+            }
+        }
+
+        let finalized_by = self.write_branch(&batch, &branch, finalized)?;
+
+        batch.commit()?;
+
+        for (block, _) in &branch {
+            side_blocks.remove(&block.get_hash());
+        }
+
+        // the old branch may still overtake the new one
+        for block in &retracted {
+            if side_blocks.len() < MAX_SIDE_BLOCKS {
+                side_blocks.insert(block.get_hash(), (block.clone(), None));
+            }
+        }
+
+        drop(side_blocks);
+
+        let tip = &branch[branch.len() - 1].0;
+        self.advance(tip, finalized_by);
+
+        self.events.publish(NodeEvent::Reorg {
+            ancestor,
+            retracted: retracted.iter().map(Block::get_hash).collect(),
+        });
+
+        for (block, _) in branch {
+            self.events.publish(NodeEvent::NewBlock(Arc::new(block)));
+        }
+
+        self.prune_after_import();
+
+        Ok(())
+    }
+
+    /// Checks what `block` proves on its own, whichever branch it is on.
+    fn check_block(
+        &self,
+        block: &Block,
+        commit: Option<&CommitCertificate>,
+    ) -> Result<(), NodeError> {
         if block.get_hash() != block.hash() {
-            return Err(NodeError::InvalidExtraData);
+            return Err(NodeError::InvalidBlockHash);
         }
 
+        self.consensus.verify_seal(block)?;
+        self.consensus.verify_commit(block, commit)?;
+
+        if let Some(checkpoint) = self.finality.checkpoint(block.id())
+            && *checkpoint != block.get_hash()
+        {
+            return Err(NodeError::CheckpointMismatch(block.id()));
+        }
+
+        Ok(())
+    }
+
+    /// Writes the blocks of `branch`, oldest first, with what each overwrote,
+    /// and returns the finalized height once they are in.
+    fn write_branch(
+        &self,
+        batch: &WriteBatch<'_>,
+        branch: &[(Block, Option<CommitCertificate>)],
+        finalized: u64,
+    ) -> Result<u64, NodeError> {
+        let mut finalized_by = finalized;
+
+        for (block, commit) in branch {
+            self.write_block(batch, block)?;
+
+            if let Some(commit) = commit {
+                batch.insert_commit(block.id(), commit)?;
+            }
+
+            batch.save_undo(block.id())?;
+
+            finalized_by =
+                finalized_by.max(self.finality.finalized_by(block.id(), commit.is_some()));
+        }
+
+        if finalized_by > finalized {
+            batch.set_finalized_height(finalized_by)?;
+
+            // final blocks are never reverted
+            batch.remove_undo(0..=finalized_by)?;
+        }
+
+        Ok(finalized_by)
+    }
+
+    /// Makes `head`, just written, the head of the chain.
+    fn advance(&self, head: &Block, finalized_by: u64) {
         self.current_block_id
-            .fetch_add(1, std::sync::atomic::Ordering::AcqRel);
+            .store(head.id() + 1, Ordering::Release);
 
-        self.prev_block_hash.store(Arc::new(block.get_hash()));
+        self.prev_block_hash.store(Arc::new(head.get_hash()));
+        self.head.store(Arc::new(head.header().clone()));
+        self.elect_validators();
 
-        self.insert_block_into_storage(&block)?;
+        let now = Instant::now();
+        if let Some(last) = self.last_import.swap(Some(Arc::new(now))) {
+            self.block_time.store(Some(Arc::new(now.duration_since(*last))));
+        }
 
-        Ok(())
+        if self.finalized.fetch_max(finalized_by, Ordering::AcqRel) < finalized_by {
+            self.side_blocks
+                .lock()
+                .expect("side blocks lock poisoned")
+                .retain(|_, (block, _)| block.id() > finalized_by);
+        }
+    }
+
+    fn prune_after_import(&self) {
+        // the block is in, failing to prune only keeps old blocks around longer
+        if let Some(retain) = self.finality.retain_blocks()
+            && let Err(e) = self.prune_blocks(retain)
+        {
+            tracing::warn!(error = %e, "block pruning failed");
+        }
+    }
+
+    /// Time between the imports of the last two blocks, once two were imported.
+    pub fn last_block_time(&self) -> Option<Duration> {
+        self.block_time.load_full().map(|time| *time)
+    }
+
+    /// Highest block id that can no longer be replaced, genesis being final from
+    /// the start.
+    #[inline]
+    pub fn get_finalized_height(&self) -> u64 {
+        self.finalized.load(Ordering::Acquire)
+    }
+
+    /// Deletes the stored blocks and blooms more than `retain` blocks below the
+    /// finalized height, keeping genesis, and returns the lowest non genesis id
+    /// still stored.
+    ///
+    /// Tentative blocks are never pruned, state tables and commit certificates
+    /// are kept. The horizon is stored with the deletions, so pruned blocks are
+    /// still told apart from missing ones after a restart.
+    pub fn prune_blocks(&self, retain: u64) -> Result<u64, NodeError> {
+        let horizon = self.get_finalized_height().saturating_sub(retain);
+        let from = self.pruned.load(Ordering::Acquire).max(1);
+
+        if horizon <= from {
+            return Ok(from);
+        }
+
+        let batch = self.storage.batch()?;
+
+        batch.remove_blocks(from..horizon)?;
+        batch.set_pruned_height(horizon)?;
+        batch.commit()?;
+
+        self.pruned.fetch_max(horizon, Ordering::AcqRel);
+
+        Ok(horizon)
     }
 
     pub fn insert_block_into_storage(&self, block: &Block) -> Result<(), StorageError> {
-        self.storage
-            .get_ref(storage::TableId::Balance)
-            .to_balance()
-            .multi_insert(block.data().tokens.iter().map(|balance| balance.split()))?;
+        let batch = self.storage.batch()?;
 
-        // storage
-        //     .get_ref(storage::TableId::Nonce)
-        //     .to_nonce()
-        //     .insert(&self.block.id(), &self.block)?;
+        self.write_block(&batch, block)?;
 
-        self.storage
-            .get_ref(storage::TableId::Block)
-            .to_block()
-            .insert(&block.id(), &block)?;
+        batch.commit()
+    }
+
+    /// Writes `block`, its receipts and log bloom and the state it carries to
+    /// `batch`, rotating the validator set at the end of an epoch.
+    fn write_block(&self, batch: &WriteBatch<'_>, block: &Block) -> Result<(), StorageError> {
+        let data = block.data();
+
+        batch.insert_balances(data.tokens.iter().map(|balance| balance.split()))?;
+
+        let assets: Vec<_> = data
+            .assets
+            .iter()
+            .map(|metadata| (metadata.id(), metadata))
+            .collect();
+
+        batch.insert_assets(assets.iter().map(|(id, metadata)| (id, *metadata)))?;
+
+        let asset_balances: Vec<_> = data
+            .asset_balances
+            .iter()
+            .map(|balance| ((balance.addr, balance.asset), &balance.amount))
+            .collect();
+
+        batch.insert_asset_balances(asset_balances.iter().map(|(key, amount)| (key, *amount)))?;
+
+        batch.insert_htlcs(data.htlcs.iter().map(|htlc| (&htlc.id, htlc)))?;
+
+        batch.insert_vesting(
+            data.vesting
+                .iter()
+                .map(|schedule| (&schedule.beneficiary, schedule)),
+        )?;
+
+        batch.insert_vesting_offers(data.vesting_offers.iter().map(|offer| (&offer.id, offer)))?;
+
+        batch.insert_validators(
+            data.validators
+                .iter()
+                .map(|record| (&record.address, record)),
+        )?;
+
+        let multisigs: Vec<_> = data
+            .multisigs
+            .iter()
+            .map(|record| (record.address(), record))
+            .collect();
+
+        batch.insert_multisigs(
+            multisigs
+                .iter()
+                .map(|(addr, record)| (addr, &record.account)),
+        )?;
+        batch.insert_nonces(multisigs.iter().map(|(addr, record)| (addr, &record.nonce)))?;
+
+        self.rotate_validators(batch, block.id())?;
+
+        batch.insert_bloom(block.id(), &block.header().logs_bloom)?;
+        batch.insert_block(block.id(), block)?;
 
         Ok(())
     }
 
     pub fn push_transaction(&self, tx: Transaction) -> Result<(), NodeError> {
+        // oversized batches and badly signed multisig spends would only waste a mempool slot
+        vm::gas::check_limits(&tx)?;
+
+        let snapshot = self.storage.snapshot()?;
+        vm::multisig::MultisigLedger::load(&snapshot, std::slice::from_ref(&tx))?
+            .authorize_pending(&tx)?;
+
+        let size = tx.size() as u64;
+        let hash = tx.hash();
+
         self.mempool.push(tx).map_err(|_| NodeError::MempoolFull)?;
+        self.pending_size.fetch_add(size, Ordering::AcqRel);
+        self.events.publish(NodeEvent::TxAccepted(hash));
+
+        if self.mempool_ready() {
+            self.block_ready.notify_one();
+        }
+
         Ok(())
     }
 
+    /// Announces `txs`, taken out of the mempool, as never to be included.
+    pub fn drop_transactions(&self, txs: &[Transaction]) {
+        for tx in txs {
+            self.events.publish(NodeEvent::TxDropped(tx.hash()));
+        }
+    }
+
+    /// Puts `txs`, taken out of the mempool for a block that was not chosen, back
+    /// into it. Those no longer fitting are announced as dropped.
+    pub fn requeue_transactions(&self, txs: Vec<Transaction>) {
+        for tx in txs {
+            let size = tx.size() as u64;
+            let hash = tx.hash();
+
+            match self.mempool.push(tx) {
+                Ok(()) => {
+                    self.pending_size.fetch_add(size, Ordering::AcqRel);
+                }
+                Err(_) => self.events.publish(NodeEvent::TxDropped(hash)),
+            }
+        }
+    }
+
+    /// Hands `message` of another validator to the BFT rounds of this node.
+    pub fn receive_consensus_message(&self, message: Message) {
+        // without a validator running there is nobody to tell
+        let _ = self.consensus_inbox.send(message);
+    }
+
+    /// Receives the messages handed to [`NodeManager::receive_consensus_message`]
+    /// from now on.
+    pub fn consensus_messages(&self) -> broadcast::Receiver<Message> {
+        self.consensus_inbox.subscribe()
+    }
+
+    /// Whether the mempool holds a full block worth of transactions, by count or
+    /// by the size thresholds of [`TxPoolHelper`].
+    pub fn mempool_ready(&self) -> bool {
+        self.mempool.len() >= self.max_mempool_size
+            || TxPoolHelper::is_ready(self.pending_size.load(Ordering::Acquire))
+    }
+
+    /// Resolves once a transaction admission made the mempool ready, or right
+    /// away if one did since the last call.
+    pub async fn block_ready(&self) {
+        self.block_ready.notified().await
+    }
+
+    /// Executes `tx` against a snapshot of the current state without touching
+    /// the mempool or storage.
+    pub fn simulate_transaction(&self, tx: &Transaction) -> Result<Simulation, NodeError> {
+        let snapshot = self.storage.snapshot()?;
+
+        let height = self.current_block_id.load(Ordering::Acquire);
+
+        Ok(vm::simulate::simulate(&snapshot, tx, height)?)
+    }
+
+    /// Returns the logs matching `filter`, in chain order.
+    ///
+    /// Blocks whose bloom rules out the filter are skipped without being loaded.
+    pub fn get_logs(&self, filter: &LogFilter) -> Result<Vec<LogEntry>, NodeError> {
+        if filter.from > filter.to || filter.to - filter.from >= MAX_LOG_RANGE {
+            return Err(NodeError::InvalidLogRange {
+                from: filter.from,
+                to: filter.to,
+            });
+        }
+
+        let pruned = self.pruned.load(Ordering::Acquire);
+
+        if filter.from < pruned {
+            return Err(NodeError::BlockPruned(filter.from));
+        }
+
+        let snapshot = self.storage.snapshot()?;
+        let mut entries = vec![];
+
+        for block_id in filter.from..=filter.to {
+            match snapshot.bloom(block_id)? {
+                Some(bloom) if filter.may_match(&bloom) => {}
+                _ => continue,
+            }
+
+            let Some(block) = snapshot.block(block_id)? else {
+                continue;
+            };
+
+            let logs = block
+                .data()
+                .receipts
+                .iter()
+                .flat_map(|receipt| receipt.logs.iter().map(|log| (receipt.tx_hash, log)));
+
+            for (log_index, (tx_hash, log)) in logs.enumerate() {
+                if filter.matches(log) {
+                    entries.push(LogEntry {
+                        block_id,
+                        tx_hash,
+                        log_index: log_index as u32,
+                        log: log.clone(),
+                    });
+                }
+            }
+        }
+
+        Ok(entries)
+    }
+
+    pub fn get_asset(&self, id: &AssetId) -> Result<Option<AssetMetadata>, StorageError> {
+        self.storage.get_ref(TableId::Asset).to_asset().get(id)
+    }
+
+    pub fn get_asset_balance(&self, addr: &Address, id: &AssetId) -> Result<Uint256, StorageError> {
+        self.storage
+            .get_ref(TableId::AssetBalance)
+            .to_asset_balance()
+            .get_or_default(&(*addr, *id))
+    }
+
+    pub fn get_total_supply(&self, id: &AssetId) -> Result<Uint256, NodeError> {
+        let metadata = self.get_asset(id)?.ok_or(NodeError::UnknownAsset(*id))?;
+        Ok(metadata.total_supply)
+    }
+
+    pub fn get_htlc(&self, id: &Hash) -> Result<Option<Htlc>, StorageError> {
+        self.storage.get_ref(TableId::Htlc).to_htlc().get(id)
+    }
+
+    /// Returns the open locks `addr` is the sender or the recipient of.
+    pub fn get_open_htlcs(&self, addr: &Address) -> Result<Vec<Htlc>, StorageError> {
+        self.storage.snapshot()?.open_htlcs(addr)
+    }
+
+    /// Splits the native balance of `addr` into the locked and spendable parts, as
+    /// seen by the next block.
+    pub fn get_vesting_status(&self, addr: &Address) -> Result<VestingStatus, StorageError> {
+        let snapshot = self.storage.snapshot()?;
+        let height = self.current_block_id.load(Ordering::Acquire);
+
+        Ok(VestingStatus::new(
+            height,
+            snapshot.balance(addr)?,
+            snapshot.vesting(addr)?,
+        ))
+    }
+
     pub fn get_block(&self, id: u64) -> Result<Option<Block>, StorageError> {
         let block = self.storage.get_ref(TableId::Block).to_block().get(&id)?;
         Ok(block)
     }
+
+    /// Id of the head block.
+    #[inline]
+    pub fn height(&self) -> u64 {
+        self.current_block_id.load(Ordering::Acquire).saturating_sub(1)
+    }
+
+    pub fn get_latest_block(&self) -> Result<Option<Block>, StorageError> {
+        self.get_block(self.height())
+    }
+
+    pub fn get_header(&self, id: u64) -> Result<Option<Header>, StorageError> {
+        Ok(self.get_block(id)?.map(|block| block.header().clone()))
+    }
+
+    /// Returns the blocks `from..=to`, the range being cut at the head.
+    pub fn get_block_range(&self, from: u64, to: u64) -> Result<Vec<Block>, NodeError> {
+        if from > to || to - from >= MAX_BLOCK_RANGE {
+            return Err(NodeError::InvalidBlockRange { from, to });
+        }
+
+        if from < self.pruned.load(Ordering::Acquire) {
+            return Err(NodeError::BlockPruned(from));
+        }
+
+        let snapshot = self.storage.snapshot()?;
+        let to = to.min(self.height());
+
+        (from..=to)
+            .map(|id| snapshot.block(id)?.ok_or(NodeError::BlockNotExist(id)))
+            .collect()
+    }
+
+    pub fn mempool_status(&self) -> MempoolStatus {
+        MempoolStatus {
+            pending: self.mempool.len(),
+            capacity: self.mempool.capacity(),
+            pending_size: self.pending_size.load(Ordering::Acquire),
+            ready: self.mempool_ready(),
+        }
+    }
+
+    /// Freezes the validator set of the next epoch once `height` is the last block
+    /// of an epoch, from the registry as of that block.
+    fn rotate_validators(&self, batch: &WriteBatch<'_>, height: u64) -> Result<(), StorageError> {
+        let epoch_length = get_config().staking.epoch_length.max(1);
+
+        if !(height + 1).is_multiple_of(epoch_length) {
+            return Ok(());
+        }
+
+        // the registry as written by the block being imported
+        let validators = select_validators((height + 1) / epoch_length, &batch.validators()?);
+
+        batch.insert_epoch(&validators)
+    }
+
+    /// Hands the consensus engine the stored validator sets of the epoch of the next
+    /// block and of the one after, which drops what a reverted branch elected.
+    fn elect_validators(&self) {
+        let epoch_length = get_config().staking.epoch_length.max(1);
+        let epoch = self.current_epoch();
+
+        let sets = self
+            .storage
+            .snapshot()
+            .and_then(|snapshot| Ok([snapshot.epoch(epoch)?, snapshot.epoch(epoch + 1)?]));
+
+        let sets = match sets {
+            Ok(sets) => sets,
+            Err(e) => {
+                tracing::warn!(epoch, error = %e, "cannot load the elected validators");
+                return;
+            }
+        };
+
+        for (epoch, set) in (epoch..).zip(sets) {
+            let keys: Vec<_> = set
+                .iter()
+                .flat_map(|set| &set.validators)
+                .map(|validator| validator.public_key)
+                .collect();
+
+            self.consensus.elect(epoch * epoch_length, &keys);
+        }
+    }
+
+    fn select_validators(&self, epoch: u64) -> Result<EpochValidators, StorageError> {
+        Ok(select_validators(
+            epoch,
+            &self.storage.snapshot()?.validators()?,
+        ))
+    }
+
+    /// Epoch of the next block.
+    pub fn current_epoch(&self) -> u64 {
+        let epoch_length = get_config().staking.epoch_length.max(1);
+
+        self.current_block_id.load(Ordering::Acquire) / epoch_length
+    }
+
+    /// Validator set of the current epoch, empty before the first rotation.
+    pub fn current_validators(&self) -> Result<EpochValidators, StorageError> {
+        let epoch = self.current_epoch();
+
+        Ok(self.storage.snapshot()?.epoch(epoch)?.unwrap_or(EpochValidators {
+            epoch,
+            validators: vec![],
+        }))
+    }
+
+    /// Validator set the next epoch would get from the registry as it is now.
+    pub fn next_validators(&self) -> Result<EpochValidators, StorageError> {
+        self.select_validators(self.current_epoch() + 1)
+    }
+
+    pub fn get_stake(&self, addr: &Address) -> Result<Option<ValidatorRecord>, StorageError> {
+        self.storage.snapshot()?.validator(addr)
+    }
+
+    /// Certificate proving block `id` final, for engines with finality.
+    pub fn get_commit(&self, id: u64) -> Result<Option<CommitCertificate>, StorageError> {
+        let commit = self.storage.get_ref(TableId::Commit).to_commit().get(&id)?;
+        Ok(commit)
+    }
+}
+
+fn select_validators(epoch: u64, records: &[ValidatorRecord]) -> EpochValidators {
+    let (max_validators, min_stake) = {
+        let config = get_config();
+        (config.staking.max_validators, config.staking.min_stake)
+    };
+
+    EpochValidators::select(epoch, records, max_validators, &Uint256::from(min_stake))
 }
@@ -0,0 +1,490 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    sync::{Arc, Mutex, atomic::Ordering},
+    time::Duration,
+};
+
+use config::ProducerConfig;
+use rm_reth_types::{block::block::Block, bytes::FixedBytes};
+use tokio::{
+    sync::{broadcast::error::RecvError, mpsc, oneshot},
+    task::JoinHandle,
+    time::{MissedTickBehavior, interval, sleep},
+};
+
+use crate::{
+    consensus::bft::{Output, Tendermint},
+    error::NodeError,
+    events::NodeEvent,
+    manager::NodeManager,
+};
+
+/// Background task building a block every `interval_ms`, or as soon as the
+/// mempool holds a full block, instead of waiting for `MineBlock` commands.
+///
+/// Under BFT, blocks are only imported once the validators agreed on them, so a
+/// producer given a validator key takes part in the rounds instead.
+pub struct BlockProducer {
+    node: Arc<NodeManager>,
+    config: ProducerConfig,
+    running: Mutex<Option<Running>>,
+}
+
+struct Running {
+    stop: oneshot::Sender<()>,
+    task: JoinHandle<()>,
+}
+
+impl BlockProducer {
+    pub fn new(node: Arc<NodeManager>, config: ProducerConfig) -> Self {
+        Self {
+            node,
+            config,
+            running: Mutex::new(None),
+        }
+    }
+
+    #[inline]
+    pub fn config(&self) -> &ProducerConfig {
+        &self.config
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running
+            .lock()
+            .expect("producer lock poisoned")
+            .as_ref()
+            .is_some_and(|running| !running.task.is_finished())
+    }
+
+    /// Spawns the producer on the current tokio runtime. Returns `false` if it
+    /// was already running.
+    pub fn start(&self) -> bool {
+        let mut running = self.running.lock().expect("producer lock poisoned");
+
+        if running
+            .as_ref()
+            .is_some_and(|running| !running.task.is_finished())
+        {
+            return false;
+        }
+
+        let (stop, stopped) = oneshot::channel();
+        let task = tokio::spawn(run(self.node.clone(), self.config.clone(), stopped));
+
+        *running = Some(Running { stop, task });
+
+        true
+    }
+
+    /// Stops the producer once the block it may be building is imported. Returns
+    /// `false` if it was not running.
+    pub async fn stop(&self) -> bool {
+        let Some(running) = self.running.lock().expect("producer lock poisoned").take() else {
+            return false;
+        };
+
+        let _ = running.stop.send(());
+        let _ = running.task.await;
+
+        true
+    }
+}
+
+async fn run(node: Arc<NodeManager>, config: ProducerConfig, mut stopped: oneshot::Receiver<()>) {
+    if let Some(bft) = node.consensus().bft() {
+        match bft.validator(node.current_block_id().load(Ordering::Acquire)) {
+            Ok(Some(machine)) => run_validator(node, machine, stopped).await,
+            Ok(None) => tracing::warn!("not a validator, BFT blocks are imported from the others"),
+            Err(e) => tracing::warn!(error = %e, "cannot take part in BFT rounds"),
+        }
+
+        return;
+    }
+
+    let mut ticker = interval(Duration::from_millis(config.interval_ms));
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    // the first tick completes immediately
+    ticker.tick().await;
+
+    loop {
+        let full = tokio::select! {
+            _ = &mut stopped => return,
+            _ = ticker.tick() => false,
+            _ = node.block_ready() => true,
+        };
+
+        if config.skip_empty && node.mempool().is_empty() {
+            continue;
+        }
+
+        match produce_block(&node) {
+            Ok(id) => tracing::debug!(id, full, "produced block"),
+            Err(e) => tracing::warn!(error = %e, "block production failed"),
+        }
+
+        if full {
+            ticker.reset();
+        }
+    }
+}
+
+/// Takes part in the rounds of every height with `machine`.
+///
+/// Messages for the other validators are published as [`NodeEvent::Consensus`],
+/// theirs are read from [`NodeManager::consensus_messages`]. A decided block is
+/// imported with its commit certificate before the next height starts, and the
+/// transactions of the local candidate it left out go back to the mempool.
+async fn run_validator(
+    node: Arc<NodeManager>,
+    mut machine: Tendermint,
+    mut stopped: oneshot::Receiver<()>,
+) {
+    let mut inbox = node.consensus_messages();
+    let (timeouts, mut expired) = mpsc::unbounded_channel();
+
+    let Some(mut candidate) = bft_candidate(&node) else {
+        return;
+    };
+    let mut outputs = VecDeque::from(machine.start(candidate.id(), candidate.clone()));
+
+    loop {
+        while let Some(output) = outputs.pop_front() {
+            match output {
+                Output::Broadcast(message) => node
+                    .events()
+                    .publish(NodeEvent::Consensus(Arc::new(message))),
+                Output::Schedule { timeout, after } => {
+                    let timeouts = timeouts.clone();
+
+                    tokio::spawn(async move {
+                        sleep(after).await;
+                        let _ = timeouts.send(timeout);
+                    });
+                }
+                Output::Decide { block, commit } => {
+                    let id = block.id();
+                    let mut included = HashSet::new();
+
+                    match node.commit_block((*block).clone(), commit) {
+                        Ok(()) => {
+                            included.extend(block.data().tx_pool.iter().map(|tx| tx.hash()));
+                            tracing::debug!(id, "committed block");
+                        }
+                        Err(e) => tracing::warn!(id, error = %e, "decided block import failed"),
+                    }
+
+                    node.requeue_transactions(
+                        candidate
+                            .data()
+                            .tx_pool
+                            .iter()
+                            .filter(|tx| !included.contains(&tx.hash()))
+                            .cloned()
+                            .collect(),
+                    );
+
+                    let Some(next) = bft_candidate(&node) else {
+                        return;
+                    };
+                    if !follow_validators(&node, &mut machine, next.id()) {
+                        return;
+                    }
+                    candidate = next;
+                    outputs.extend(machine.start(candidate.id(), candidate.clone()));
+                }
+            }
+        }
+
+        let next = tokio::select! {
+            _ = &mut stopped => return,
+            message = inbox.recv() => match message {
+                Ok(message) => machine.handle_message(message),
+                Err(RecvError::Lagged(missed)) => {
+                    tracing::warn!(missed, "consensus messages lagged behind");
+                    vec![]
+                }
+                Err(RecvError::Closed) => return,
+            },
+            Some(timeout) = expired.recv() => machine.handle_timeout(timeout),
+        };
+
+        outputs.extend(next);
+    }
+}
+
+/// Moves `machine` to the validators of block `height`, which change with the
+/// epochs. Returns `false` once this node is not one of them.
+fn follow_validators(node: &NodeManager, machine: &mut Tendermint, height: u64) -> bool {
+    let Some(bft) = node.consensus().bft() else {
+        return false;
+    };
+
+    match machine.set_validators(bft.validators(height)) {
+        Ok(()) => true,
+        Err(e) => {
+            tracing::warn!(height, error = %e, "left the validator set, stopping BFT rounds");
+            false
+        }
+    }
+}
+
+/// Block of the mempool this validator proposes for the next height.
+fn bft_candidate(node: &NodeManager) -> Option<Block> {
+    let tx_pool = match node.process_execution_transaction() {
+        Ok(tx_pool) => tx_pool,
+        Err(e) => {
+            tracing::warn!(error = %e, "cannot build a BFT candidate");
+            return None;
+        }
+    };
+
+    let mut block = node.create_block_with_processed_tx_pool(tx_pool);
+    block.set_hash();
+
+    Some(block)
+}
+
+/// Builds a block out of the mempool, seals and imports it, returning its id.
+pub fn produce_block(node: &NodeManager) -> Result<u64, NodeError> {
+    mine_block(node, [0u8; 32].into())
+}
+
+/// [`produce_block`] with `extra_data` in the header.
+///
+/// The mempool transactions of a block failing to be imported are announced as
+/// dropped.
+pub fn mine_block(node: &NodeManager, extra_data: FixedBytes<32>) -> Result<u64, NodeError> {
+    let tx_pool = node.process_execution_transaction()?;
+
+    let block = node.create_block_with_processed_tx_pool(tx_pool);
+    let id = block.id();
+    let txs = block.data().tx_pool.clone();
+
+    if let Err(e) = node.mine_with_block(block, extra_data) {
+        node.drop_transactions(&txs);
+        return Err(e);
+    }
+
+    Ok(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::Ordering;
+
+    use k256::ecdsa::SigningKey;
+    use rm_reth_types::{
+        Address,
+        finality::ValidatorSet,
+        int::Uint256,
+        multisig::PublicKey,
+        tx::{kind::TransferLeg, transaction::Transaction},
+    };
+    use storage::StorageManager;
+    use tokio::{
+        sync::broadcast,
+        time::{sleep, timeout},
+    };
+
+    use super::*;
+    use crate::consensus::{
+        bft::{Bft, BftTimeouts},
+        dev::Dev,
+    };
+
+    fn addr(id: u8) -> Address {
+        [id; 20].into()
+    }
+
+    fn node() -> Arc<NodeManager> {
+        Arc::new(
+            NodeManager::genesis_with_storage(StorageManager::in_memory().unwrap())
+                .unwrap()
+                .with_consensus(Dev),
+        )
+    }
+
+    fn height(node: &NodeManager) -> u64 {
+        node.current_block_id().load(Ordering::Acquire) - 1
+    }
+
+    /// Waits for the next block announced on `events`.
+    async fn next_block(events: &mut broadcast::Receiver<NodeEvent>) -> Arc<Block> {
+        loop {
+            let event = timeout(Duration::from_secs(10), events.recv())
+                .await
+                .expect("no block imported")
+                .unwrap();
+
+            if let NodeEvent::NewBlock(block) = event {
+                return block;
+            }
+        }
+    }
+
+    // the clock is paused and only moves once every task waits on it, so the
+    // producer ticks at the same points of every run
+    #[tokio::test(start_paused = true)]
+    async fn produces_on_interval_and_skips_empty_blocks() {
+        let node = node();
+        let mut blocks = node.events().subscribe();
+        let producer = BlockProducer::new(
+            node.clone(),
+            ProducerConfig {
+                interval_ms: 20,
+                skip_empty: true,
+            },
+        );
+
+        assert!(producer.start());
+        assert!(!producer.start());
+
+        // five ticks with an empty mempool
+        sleep(Duration::from_millis(100)).await;
+        assert_eq!(height(&node), 0);
+
+        node.push_transaction(Transaction::new(addr(1), addr(2), Uint256::zero(), vec![]))
+            .unwrap();
+        assert_eq!(next_block(&mut blocks).await.id(), 1);
+
+        assert!(producer.stop().await);
+        assert!(!producer.stop().await);
+        assert!(!producer.is_running());
+
+        node.push_transaction(Transaction::new(addr(1), addr(2), Uint256::zero(), vec![]))
+            .unwrap();
+        sleep(Duration::from_millis(100)).await;
+        assert_eq!(height(&node), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn produces_early_once_mempool_is_ready() {
+        let node = node();
+        let mut blocks = node.events().subscribe();
+        let producer = BlockProducer::new(
+            node.clone(),
+            ProducerConfig {
+                interval_ms: 60_000,
+                skip_empty: true,
+            },
+        );
+        producer.start();
+
+        let legs: Vec<_> = (0..8)
+            .map(|id| TransferLeg {
+                to: addr(id),
+                amount: Uint256::from(1),
+            })
+            .collect();
+
+        let mut admitted = 0;
+        while !node.mempool_ready() {
+            node.push_transaction(Transaction::multi_transfer(addr(1), legs.clone()))
+                .unwrap();
+            admitted += 1;
+        }
+
+        // the size threshold triggers long before the mempool is full
+        assert!(admitted < 100);
+
+        let block = next_block(&mut blocks).await;
+        assert_eq!(block.id(), 1);
+        assert_eq!(block.data().tx_pool.len(), admitted);
+        assert!(node.mempool().is_empty());
+
+        producer.stop().await;
+    }
+
+    #[tokio::test]
+    async fn validators_commit_blocks_through_their_producers() {
+        let keys: Vec<_> = (1..=4u8)
+            .map(|id| SigningKey::from_bytes(&[id; 32].into()).unwrap())
+            .collect();
+        let set =
+            ValidatorSet::new(keys.iter().map(PublicKey::from_signing_key).collect()).unwrap();
+        let timeouts = BftTimeouts::new(Duration::from_millis(200));
+
+        let nodes: Vec<_> = keys
+            .into_iter()
+            .map(|key| {
+                Arc::new(
+                    NodeManager::genesis_with_storage(StorageManager::in_memory().unwrap())
+                        .unwrap()
+                        .with_consensus(Bft::new(set.clone(), timeouts).with_signer(key)),
+                )
+            })
+            .collect();
+
+        // every validator hears the messages of the others
+        for (index, node) in nodes.iter().enumerate() {
+            let mut events = node.events().subscribe();
+            let peers: Vec<_> = nodes
+                .iter()
+                .enumerate()
+                .filter(|(peer, _)| *peer != index)
+                .map(|(_, peer)| peer.clone())
+                .collect();
+
+            tokio::spawn(async move {
+                while let Ok(event) = events.recv().await {
+                    if let NodeEvent::Consensus(message) = event {
+                        for peer in &peers {
+                            peer.receive_consensus_message((*message).clone());
+                        }
+                    }
+                }
+            });
+        }
+
+        let mut blocks = nodes[0].events().subscribe();
+        let tx = Transaction::new(addr(1), addr(2), Uint256::zero(), vec![]);
+        for node in &nodes {
+            node.push_transaction(tx.clone()).unwrap();
+        }
+
+        let producers: Vec<_> = nodes
+            .iter()
+            .map(|node| BlockProducer::new(node.clone(), ProducerConfig::default()))
+            .collect();
+        for producer in &producers {
+            assert!(producer.start());
+        }
+
+        let mut included = false;
+        while height(&nodes[0]) < 3 {
+            included |= !next_block(&mut blocks).await.data().tx_pool.is_empty();
+        }
+
+        for producer in &producers {
+            assert!(producer.stop().await);
+        }
+
+        // included once, the candidates of the other validators giving it back
+        assert!(included);
+        for id in 2..=3 {
+            assert!(
+                nodes[0]
+                    .get_block(id)
+                    .unwrap()
+                    .unwrap()
+                    .data()
+                    .tx_pool
+                    .is_empty()
+            );
+        }
+
+        for id in 1..=3 {
+            let block = nodes[0].get_block(id).unwrap().unwrap();
+            let commit = nodes[0].get_commit(id).unwrap().unwrap();
+
+            commit.verify(&set, &block.hash()).unwrap();
+            for node in &nodes[1..] {
+                if let Some(other) = node.get_block(id).unwrap() {
+                    assert_eq!(other.hash(), block.hash());
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use std::fmt::Display;
+
+use dispatcher::error::{DispatchError, ErrorCode};
+use serde_json::{Value, json};
+
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+pub const INTERNAL_ERROR: i64 = -32603;
+
+/// JSON-RPC error object.
+///
+/// Protocol failures use the reserved negative codes, while dispatcher failures
+/// keep their [`dispatcher::error::ErrorCode`] as is, named in `data`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+    pub data: Option<Value>,
+}
+
+impl RpcError {
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    pub fn parse_error(e: impl Display) -> Self {
+        Self::new(PARSE_ERROR, format!("parse error: {e}"))
+    }
+
+    pub fn invalid_request(reason: impl Display) -> Self {
+        Self::new(INVALID_REQUEST, format!("invalid request: {reason}"))
+    }
+
+    pub fn method_not_found(method: &str) -> Self {
+        Self::new(METHOD_NOT_FOUND, format!("method not found: {method}"))
+    }
+
+    pub fn invalid_params(reason: impl Display) -> Self {
+        Self::new(INVALID_PARAMS, format!("invalid params: {reason}"))
+    }
+
+    pub fn internal(reason: impl Display) -> Self {
+        Self::new(INTERNAL_ERROR, format!("internal error: {reason}"))
+    }
+
+    /// Error carrying the dispatcher `code`.
+    pub fn dispatch(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code: code.as_u16().into(),
+            message: message.into(),
+            data: Some(json!({ "name": code.name() })),
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut error = json!({
+            "code": self.code,
+            "message": self.message,
+        });
+
+        if let Some(data) = &self.data {
+            error["data"] = data.clone();
+        }
+
+        error
+    }
+}
+
+impl From<DispatchError> for RpcError {
+    fn from(error: DispatchError) -> Self {
+        Self::dispatch(error.code(), error.to_string())
+    }
+}
@@ -0,0 +1,158 @@
+use dispatcher::{
+    command::{Command, Response},
+    error::DispatchError,
+};
+use serde_json::{Map, Value, json};
+use tower::{Service, ServiceExt};
+
+use crate::{
+    error::RpcError,
+    methods::{self, Call},
+};
+
+/// Request of a payload, answered under `id` unless it is a notification.
+struct Request {
+    id: Option<Value>,
+    call: Result<Call, RpcError>,
+}
+
+impl Request {
+    fn parse(request: Value) -> Self {
+        let Value::Object(mut request) = request else {
+            return Self::invalid(Value::Null, "expected an object");
+        };
+
+        let id = request.remove("id");
+
+        if request.get("jsonrpc").and_then(Value::as_str) != Some("2.0") {
+            return Self::invalid(id.unwrap_or_default(), "jsonrpc must be \"2.0\"");
+        }
+
+        let Some(method) = request.get("method").and_then(Value::as_str) else {
+            return Self::invalid(id.unwrap_or_default(), "method must be a string");
+        };
+
+        Self {
+            call: methods::parse_call(method, request.get("params")),
+            id,
+        }
+    }
+
+    /// Invalid requests are answered even without an id, under a `null` one.
+    fn invalid(id: Value, reason: &str) -> Self {
+        Self {
+            id: Some(id),
+            call: Err(RpcError::invalid_request(reason)),
+        }
+    }
+}
+
+/// Answers the JSON-RPC 2.0 `payload`, a single request or a batch of them,
+/// through the dispatcher `service`.
+///
+/// Returns `None` when nothing is to be sent back, the payload holding only
+/// notifications. The requests of a batch reach the dispatcher as one
+/// [`Command::Batch`].
+pub async fn handle<S>(payload: &[u8], service: S) -> Option<Value>
+where
+    S: Service<Command, Response = Response, Error = DispatchError>,
+{
+    match serde_json::from_slice(payload) {
+        Ok(payload) => handle_value(payload, service).await,
+        Err(e) => Some(reply(Value::Null, Err(RpcError::parse_error(e)))),
+    }
+}
+
+/// [`handle`] for a payload already parsed.
+pub async fn handle_value<S>(payload: Value, service: S) -> Option<Value>
+where
+    S: Service<Command, Response = Response, Error = DispatchError>,
+{
+    match payload {
+        Value::Array(requests) if requests.is_empty() => Some(reply(
+            Value::Null,
+            Err(RpcError::invalid_request("empty batch")),
+        )),
+        Value::Array(requests) => {
+            let requests = requests.into_iter().map(Request::parse).collect();
+            let replies = handle_batch(requests, service).await;
+
+            (!replies.is_empty()).then_some(Value::Array(replies))
+        }
+        request => handle_single(Request::parse(request), service).await,
+    }
+}
+
+async fn handle_single<S>(request: Request, service: S) -> Option<Value>
+where
+    S: Service<Command, Response = Response, Error = DispatchError>,
+{
+    let result = match request.call {
+        Ok(Call { command, render }) => methods::render(render, service.oneshot(command).await),
+        Err(e) => Err(e),
+    };
+
+    request.id.map(|id| reply(id, result))
+}
+
+async fn handle_batch<S>(requests: Vec<Request>, service: S) -> Vec<Value>
+where
+    S: Service<Command, Response = Response, Error = DispatchError>,
+{
+    let mut commands = vec![];
+    let mut renders = vec![];
+    let mut pending = Vec::with_capacity(requests.len());
+
+    for Request { id, call } in requests {
+        let call = call.map(|Call { command, render }| {
+            commands.push(command);
+            renders.push(render);
+        });
+
+        pending.push((id, call));
+    }
+
+    let results: Vec<Result<Value, RpcError>> = if commands.is_empty() {
+        vec![]
+    } else {
+        match service.oneshot(Command::Batch(commands)).await {
+            Ok(Response::Batch(results)) => renders
+                .into_iter()
+                .zip(results)
+                .map(|(render, result)| methods::render(render, result))
+                .collect(),
+            Ok(_) => vec![Err(RpcError::internal("unexpected batch response")); renders.len()],
+            // the batch failed as a whole, so does every call of it
+            Err(e) => vec![Err(e.into()); renders.len()],
+        }
+    };
+
+    let mut results = results.into_iter();
+
+    pending
+        .into_iter()
+        .filter_map(|(id, call)| {
+            let result = call.and_then(|()| {
+                results
+                    .next()
+                    .unwrap_or_else(|| Err(RpcError::internal("missing batch result")))
+            });
+
+            id.map(|id| reply(id, result))
+        })
+        .collect()
+}
+
+pub(crate) fn reply(id: Value, result: Result<Value, RpcError>) -> Value {
+    let mut reply = Map::new();
+    reply.insert("jsonrpc".into(), json!("2.0"));
+
+    match result {
+        Ok(result) => reply.insert("result".into(), result),
+        Err(e) => reply.insert("error".into(), e.to_json()),
+    };
+
+    reply.insert("id".into(), id);
+
+    Value::Object(reply)
+}
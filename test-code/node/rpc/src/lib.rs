@@ -0,0 +1,273 @@
+//! JSON-RPC 2.0 API over the dispatcher, for wallets and other external clients,
+//! served over HTTP and over WebSocket with subscriptions to node events.
+
+pub mod error;
+pub mod handler;
+pub mod methods;
+pub mod server;
+pub mod subscription;
+pub mod ws;
+
+pub use handler::handle;
+pub use server::serve_rpc;
+pub use ws::serve_ws;
+
+#[cfg(test)]
+mod test {
+    use std::{sync::Arc, time::Duration};
+
+    use dispatcher::{
+        command::Command,
+        service::{Dispatcher, build_dispatcher},
+    };
+    use futures_util::{SinkExt, StreamExt};
+    use node::manager::NodeManager;
+    use rm_reth_types::{Address, int::Uint256, tx::transaction::Transaction};
+    use serde_json::{Value, json};
+    use storage::StorageManager;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::{TcpListener, TcpStream},
+        time::timeout,
+    };
+    use tokio_tungstenite::{MaybeTlsStream, WebSocketStream, connect_async, tungstenite::Message};
+    use tower::ServiceExt;
+
+    use crate::{
+        error::{INVALID_PARAMS, INVALID_REQUEST, METHOD_NOT_FOUND, PARSE_ERROR},
+        handle, serve_rpc, serve_ws,
+    };
+
+    fn addr(id: u8) -> Address {
+        [id; 20].into()
+    }
+
+    fn funded_node() -> Arc<NodeManager> {
+        let node = NodeManager::genesis_with_storage(StorageManager::in_memory().unwrap()).unwrap();
+        node.mint(&addr(1), &Uint256::from(1000)).unwrap();
+        Arc::new(node)
+    }
+
+    #[tokio::test]
+    async fn test_requests_and_batches() {
+        let node = funded_node();
+        let service = build_dispatcher(Dispatcher::new(node.clone()), &Default::default());
+
+        let call = |payload: Value| {
+            let service = service.clone();
+            async move { handle(payload.to_string().as_bytes(), service).await }
+        };
+
+        let reply = call(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "rmreth_getBalance",
+            "params": [addr(1)],
+        }))
+        .await
+        .unwrap();
+        assert_eq!(reply["id"], 1);
+        assert_eq!(reply["result"], json!(Uint256::from(1000)));
+
+        let tx = Transaction::new(addr(1), addr(2), Uint256::from(10), vec![]);
+        let reply = call(json!({
+            "jsonrpc": "2.0",
+            "id": "tx",
+            "method": "rmreth_sendTransaction",
+            "params": [tx],
+        }))
+        .await
+        .unwrap();
+        assert_eq!(reply["result"], json!(tx.hash()));
+        assert_eq!(node.mempool().len(), 1);
+
+        let reply = call(json!({
+            "jsonrpc": "2.0",
+            "id": 2,
+            "method": "rmreth_getBlockByNumber",
+            "params": [7],
+        }))
+        .await
+        .unwrap();
+        assert_eq!(reply["result"], Value::Null);
+
+        // dispatcher failures keep their stable code
+        let reply = call(json!({
+            "jsonrpc": "2.0",
+            "id": 3,
+            "method": "rmreth_getLogs",
+            "params": [{ "from": 10, "to": 0, "address": null, "topics": [] }],
+        }))
+        .await
+        .unwrap();
+        assert_eq!(reply["error"]["code"], 300);
+        assert_eq!(reply["error"]["data"]["name"], "invalid_log_range");
+
+        let replies = call(json!([
+            { "jsonrpc": "2.0", "id": 1, "method": "rmreth_getNonce", "params": [addr(1)] },
+            { "jsonrpc": "2.0", "method": "rmreth_getNonce", "params": [addr(1)] },
+            { "jsonrpc": "2.0", "id": 3, "method": "rmreth_mineBlock" },
+            { "jsonrpc": "1.0", "id": 4, "method": "rmreth_blockNumber" },
+            { "jsonrpc": "2.0", "id": 5, "method": "rmreth_getBlockByNumber", "params": ["latest"] },
+        ]))
+        .await
+        .unwrap();
+
+        let Value::Array(replies) = replies else {
+            panic!("batch answered with a single reply");
+        };
+        let ids: Vec<_> = replies.iter().map(|reply| reply["id"].clone()).collect();
+        assert_eq!(ids, vec![json!(1), json!(3), json!(4), json!(5)]);
+        assert_eq!(replies[0]["result"], 0);
+        assert_eq!(replies[1]["error"]["code"], METHOD_NOT_FOUND);
+        assert_eq!(replies[2]["error"]["code"], INVALID_REQUEST);
+        assert_eq!(replies[3]["result"]["_inner"]["header"]["block_id"], 0);
+
+        let reply = handle(b"{", service.clone()).await.unwrap();
+        assert_eq!(reply["error"]["code"], PARSE_ERROR);
+        assert_eq!(reply["id"], Value::Null);
+
+        let reply = call(json!([])).await.unwrap();
+        assert_eq!(reply["error"]["code"], INVALID_REQUEST);
+
+        let notification = json!({ "jsonrpc": "2.0", "method": "rmreth_blockNumber" });
+        assert!(call(notification).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_http_server() {
+        let service = build_dispatcher(Dispatcher::new(funded_node()), &Default::default());
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(serve_rpc(listener, service));
+
+        let request = |raw: String| async move {
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+            stream.write_all(raw.as_bytes()).await.unwrap();
+
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            response
+        };
+
+        let body = json!({ "jsonrpc": "2.0", "id": 1, "method": "rmreth_blockNumber" }).to_string();
+        let response = request(format!(
+            "POST / HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{body}",
+            body.len()
+        ))
+        .await;
+
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        let (_, reply) = response.split_once("\r\n\r\n").unwrap();
+        let reply: Value = serde_json::from_str(reply).unwrap();
+        assert_eq!(reply, json!({ "jsonrpc": "2.0", "result": 0, "id": 1 }));
+
+        let response = request("GET / HTTP/1.1\r\nHost: localhost\r\n\r\n".into()).await;
+        assert!(response.starts_with("HTTP/1.1 405"));
+
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_ws_subscriptions() {
+        let node = funded_node();
+        let service = build_dispatcher(Dispatcher::new(node.clone()), &Default::default());
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(serve_ws(listener, service.clone(), node.clone()));
+
+        let (mut ws, _) = connect_async(url).await.unwrap();
+
+        for (id, params) in [
+            (1, json!(["newHeads"])),
+            (2, json!(["newPendingTransactions"])),
+            (3, json!(["balanceChanges", addr(2)])),
+        ] {
+            let reply = ws_call(
+                &mut ws,
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "method": "rmreth_subscribe",
+                    "params": params,
+                }),
+            )
+            .await;
+            assert_eq!(reply["result"], id);
+        }
+
+        let reply = ws_call(
+            &mut ws,
+            json!({ "jsonrpc": "2.0", "id": 4, "method": "rmreth_subscribe", "params": ["nope"] }),
+        )
+        .await;
+        assert_eq!(reply["error"]["code"], INVALID_PARAMS);
+
+        // plain methods are served as well
+        let reply = ws_call(
+            &mut ws,
+            json!({ "jsonrpc": "2.0", "id": 5, "method": "rmreth_blockNumber" }),
+        )
+        .await;
+        assert_eq!(reply["result"], 0);
+
+        let tx = Transaction::new(addr(1), addr(2), Uint256::from(10), vec![]);
+        service
+            .clone()
+            .oneshot(Command::SubmitTx(tx.clone()))
+            .await
+            .unwrap();
+        service
+            .clone()
+            .oneshot(Command::MineBlock([0u8; 32].into()))
+            .await
+            .unwrap();
+
+        let pending = next_json(&mut ws).await;
+        assert_eq!(pending["method"], "rmreth_subscription");
+        assert_eq!(pending["params"]["subscription"], 2);
+        assert_eq!(pending["params"]["result"], json!(tx.hash()));
+
+        let head = next_json(&mut ws).await;
+        assert_eq!(head["params"]["subscription"], 1);
+        assert_eq!(head["params"]["result"]["block_id"], 1);
+
+        let block = node.get_block(1).unwrap().unwrap();
+        assert_eq!(head["params"]["result"]["hash"], json!(block.get_hash()));
+
+        let balance = next_json(&mut ws).await;
+        assert_eq!(balance["params"]["subscription"], 3);
+        assert_eq!(
+            balance["params"]["result"]["balance"],
+            json!(Uint256::from(10))
+        );
+
+        let reply = ws_call(
+            &mut ws,
+            json!({ "jsonrpc": "2.0", "id": 6, "method": "rmreth_unsubscribe", "params": [1] }),
+        )
+        .await;
+        assert_eq!(reply["result"], true);
+
+        server.abort();
+    }
+
+    async fn ws_call(ws: &mut WebSocketStream<MaybeTlsStream<TcpStream>>, request: Value) -> Value {
+        ws.send(Message::Text(request.to_string().into()))
+            .await
+            .unwrap();
+        next_json(ws).await
+    }
+
+    async fn next_json(ws: &mut WebSocketStream<MaybeTlsStream<TcpStream>>) -> Value {
+        let message = timeout(Duration::from_secs(5), ws.next())
+            .await
+            .expect("no message within 5s")
+            .unwrap()
+            .unwrap();
+
+        serde_json::from_str(message.to_text().unwrap()).unwrap()
+    }
+}
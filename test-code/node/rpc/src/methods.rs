@@ -0,0 +1,234 @@
+use dispatcher::{
+    command::{Command, Response},
+    error::{DispatchError, ErrorCode},
+};
+use rm_reth_types::{hash::Hash, log::LogFilter, tx::transaction::Transaction};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::error::RpcError;
+
+/// Methods served, all taking positional params.
+pub const METHODS: &[&str] = &[
+    "rmreth_sendTransaction",
+    "rmreth_simulateTransaction",
+    "rmreth_getBalance",
+    "rmreth_getNonce",
+    "rmreth_blockNumber",
+    "rmreth_finalizedBlockNumber",
+    "rmreth_getBlockByNumber",
+    "rmreth_getHeaderByNumber",
+    "rmreth_getBlockRange",
+    "rmreth_getLogs",
+    "rmreth_getAsset",
+    "rmreth_getAssetBalance",
+    "rmreth_getTotalSupply",
+    "rmreth_getHtlc",
+    "rmreth_getOpenHtlcs",
+    "rmreth_getVesting",
+    "rmreth_getStake",
+    "rmreth_getValidators",
+    "rmreth_getNextValidators",
+    "rmreth_mempoolStatus",
+];
+
+/// How the result of a call is turned into JSON.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Render {
+    Value,
+    /// A missing block is `null` rather than an error.
+    OrNull,
+    /// The hash of the submitted transaction.
+    TxHash(Hash),
+}
+
+/// Request translated into a dispatcher command.
+#[derive(Debug)]
+pub(crate) struct Call {
+    pub command: Command,
+    pub render: Render,
+}
+
+impl Call {
+    fn value(command: Command) -> Self {
+        Self {
+            command,
+            render: Render::Value,
+        }
+    }
+
+    fn or_null(command: Command) -> Self {
+        Self {
+            command,
+            render: Render::OrNull,
+        }
+    }
+}
+
+struct Params<'a>(&'a [Value]);
+
+impl<'a> Params<'a> {
+    fn new(params: Option<&'a Value>) -> Result<Self, RpcError> {
+        match params {
+            None | Some(Value::Null) => Ok(Self(&[])),
+            Some(Value::Array(params)) => Ok(Self(params)),
+            Some(_) => Err(RpcError::invalid_params("expected an array")),
+        }
+    }
+
+    fn get<T: Deserialize<'a>>(&self, index: usize, name: &str) -> Result<T, RpcError> {
+        let param = self
+            .0
+            .get(index)
+            .ok_or_else(|| RpcError::invalid_params(format!("missing {name}")))?;
+
+        T::deserialize(param).map_err(|e| RpcError::invalid_params(format!("{name}: {e}")))
+    }
+
+    /// Block id, or the head block for `"latest"`.
+    fn block(&self, index: usize) -> Result<Option<u64>, RpcError> {
+        match self.0.get(index) {
+            Some(Value::String(tag)) if tag == "latest" => Ok(None),
+            _ => self.get(index, "block number").map(Some),
+        }
+    }
+}
+
+pub(crate) fn parse_call(method: &str, params: Option<&Value>) -> Result<Call, RpcError> {
+    let params = Params::new(params)?;
+
+    let command = match method {
+        "rmreth_sendTransaction" => {
+            let tx: Transaction = params.get(0, "transaction")?;
+
+            return Ok(Call {
+                render: Render::TxHash(tx.hash()),
+                command: Command::SubmitTx(tx),
+            });
+        }
+        "rmreth_simulateTransaction" => Command::SimulateTx(params.get(0, "transaction")?),
+        "rmreth_getBalance" => Command::GetBalance(params.get(0, "address")?),
+        "rmreth_getNonce" => Command::GetNonce(params.get(0, "address")?),
+        "rmreth_blockNumber" => Command::GetHeight,
+        "rmreth_finalizedBlockNumber" => Command::GetFinalizedHeight,
+        "rmreth_getBlockByNumber" => {
+            let command = match params.block(0)? {
+                Some(height) => Command::GetBlock(height),
+                None => Command::GetLatestBlock,
+            };
+
+            return Ok(Call::or_null(command));
+        }
+        "rmreth_getHeaderByNumber" => {
+            return Ok(Call::or_null(Command::GetHeader(
+                params.get(0, "block number")?,
+            )));
+        }
+        "rmreth_getBlockRange" => Command::GetBlockRange {
+            from: params.get(0, "from")?,
+            to: params.get(1, "to")?,
+        },
+        "rmreth_getLogs" => {
+            let LogFilter {
+                from,
+                to,
+                address,
+                topics,
+            } = params.get(0, "filter")?;
+
+            Command::GetLogs {
+                from,
+                to,
+                address,
+                topics,
+            }
+        }
+        "rmreth_getAsset" => Command::GetAsset(params.get(0, "asset")?),
+        "rmreth_getAssetBalance" => Command::GetAssetBalance {
+            address: params.get(0, "address")?,
+            asset: params.get(1, "asset")?,
+        },
+        "rmreth_getTotalSupply" => Command::GetTotalSupply(params.get(0, "asset")?),
+        "rmreth_getHtlc" => Command::GetHtlc(params.get(0, "id")?),
+        "rmreth_getOpenHtlcs" => Command::GetOpenHtlcs(params.get(0, "address")?),
+        "rmreth_getVesting" => Command::GetVesting(params.get(0, "address")?),
+        "rmreth_getStake" => Command::GetStake(params.get(0, "address")?),
+        "rmreth_getValidators" => Command::GetValidators,
+        "rmreth_getNextValidators" => Command::GetNextValidators,
+        "rmreth_mempoolStatus" => Command::GetMempool,
+        method => return Err(RpcError::method_not_found(method)),
+    };
+
+    Ok(Call::value(command))
+}
+
+pub(crate) fn render(
+    render: Render,
+    result: Result<Response, DispatchError>,
+) -> Result<Value, RpcError> {
+    match (render, result) {
+        (Render::OrNull, Err(e)) if e.code() == ErrorCode::BlockNotFound => Ok(Value::Null),
+        (_, Err(e)) => Err(e.into()),
+        (Render::TxHash(hash), Ok(_)) => to_json(&hash),
+        (_, Ok(response)) => response_to_json(response),
+    }
+}
+
+fn response_to_json(response: Response) -> Result<Value, RpcError> {
+    match response {
+        Response::Ok => Ok(Value::Bool(true)),
+        Response::TxReceipt(receipt) => Ok(json!({
+            "tx_hash": to_json(&receipt.tx_hash)?,
+            "success": receipt.success,
+        })),
+        Response::Block(block) => to_json(&block),
+        Response::Blocks(blocks) => to_json(&blocks),
+        Response::Header(header) => to_json(&header),
+        Response::Height(height) | Response::FinalizedHeight(height) => Ok(height.into()),
+        Response::Mempool(mempool) => Ok(json!({
+            "pending": mempool.pending,
+            "capacity": mempool.capacity,
+            "pending_size": mempool.pending_size,
+            "ready": mempool.ready,
+        })),
+        Response::GetBalance(balance)
+        | Response::GetAssetBalance(balance)
+        | Response::TotalSupply(balance) => to_json(&balance),
+        Response::GetNonce(nonce) => Ok(nonce.into()),
+        Response::Simulation(simulation) => {
+            let balance_changes: Vec<_> = simulation
+                .balance_changes
+                .iter()
+                .map(|change| {
+                    json!({
+                        "addr": change.addr,
+                        "asset": change.asset,
+                        "before": change.before,
+                        "after": change.after,
+                    })
+                })
+                .collect();
+
+            Ok(json!({
+                "gas_used": simulation.gas_used,
+                "nonce": simulation.nonce,
+                "balance_changes": balance_changes,
+                "logs": simulation.logs,
+                "failure": simulation.failure.map(|failure| failure.to_string()),
+            }))
+        }
+        Response::Logs(logs) => to_json(&logs),
+        Response::Asset(asset) => to_json(&asset),
+        Response::Htlc(htlc) => to_json(&htlc),
+        Response::Htlcs(htlcs) => to_json(&htlcs),
+        Response::Vesting(vesting) => to_json(&vesting),
+        Response::Stake(stake) => to_json(&stake),
+        Response::Validators(validators) => to_json(&validators),
+        Response::Error { code, message } => Err(RpcError::dispatch(code, message)),
+        Response::Batch(_) => Err(RpcError::internal("unexpected batch response")),
+    }
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<Value, RpcError> {
+    serde_json::to_value(value).map_err(RpcError::internal)
+}
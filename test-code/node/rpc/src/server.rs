@@ -0,0 +1,118 @@
+use dispatcher::{
+    command::{Command, Response},
+    error::DispatchError,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+use tower::Service;
+
+use crate::handler::handle;
+
+/// Largest request body accepted, in bytes.
+pub const MAX_BODY_SIZE: usize = 1024 * 1024;
+
+// largest request line and headers accepted
+const MAX_HEAD_SIZE: usize = 8 * 1024;
+
+/// Answers JSON-RPC 2.0 requests POSTed to `listener` through the dispatcher
+/// `service` until the task is dropped, one request per connection.
+pub async fn serve_rpc<S>(listener: TcpListener, service: S)
+where
+    S: Service<Command, Response = Response, Error = DispatchError> + Clone + Send + 'static,
+    S::Future: Send,
+{
+    loop {
+        let (stream, _) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                tracing::warn!(error = %e, "rpc accept failed");
+                continue;
+            }
+        };
+
+        let service = service.clone();
+
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(stream, service).await {
+                tracing::debug!(error = %e, "rpc request failed");
+            }
+        });
+    }
+}
+
+async fn handle_connection<S>(mut stream: TcpStream, service: S) -> std::io::Result<()>
+where
+    S: Service<Command, Response = Response, Error = DispatchError>,
+{
+    let mut request = Vec::new();
+    let mut buf = [0u8; 4096];
+
+    let head_len = loop {
+        if let Some(end) = request.windows(4).position(|window| window == b"\r\n\r\n") {
+            break end + 4;
+        }
+
+        let read = stream.read(&mut buf).await?;
+
+        if read == 0 {
+            return Ok(());
+        }
+
+        if request.len() + read > MAX_HEAD_SIZE {
+            return write_response(&mut stream, "431 Request Header Fields Too Large", "").await;
+        }
+
+        request.extend_from_slice(&buf[..read]);
+    };
+
+    let head = String::from_utf8_lossy(&request[..head_len]);
+
+    if !head.starts_with("POST ") {
+        return write_response(&mut stream, "405 Method Not Allowed", "").await;
+    }
+
+    let content_length = head.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        name.eq_ignore_ascii_case("content-length")
+            .then(|| value.trim().parse::<usize>().ok())?
+    });
+
+    let Some(content_length) = content_length else {
+        return write_response(&mut stream, "411 Length Required", "").await;
+    };
+
+    if content_length > MAX_BODY_SIZE {
+        return write_response(&mut stream, "413 Payload Too Large", "").await;
+    }
+
+    let mut body = request.split_off(head_len);
+    body.truncate(content_length);
+
+    while body.len() < content_length {
+        let read = stream.read(&mut buf).await?;
+
+        if read == 0 {
+            return Ok(());
+        }
+
+        let wanted = (content_length - body.len()).min(read);
+        body.extend_from_slice(&buf[..wanted]);
+    }
+
+    match handle(&body, service).await {
+        Some(reply) => write_response(&mut stream, "200 OK", &reply.to_string()).await,
+        None => write_response(&mut stream, "204 No Content", "").await,
+    }
+}
+
+async fn write_response(stream: &mut TcpStream, status: &str, body: &str) -> std::io::Result<()> {
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
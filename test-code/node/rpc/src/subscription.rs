@@ -0,0 +1,76 @@
+use node::events::NodeEvent;
+use rm_reth_types::Address;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::error::RpcError;
+
+/// Feed a WebSocket client subscribed to with `rmreth_subscribe`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Subscription {
+    /// Header and hash of every new head.
+    NewHeads,
+    /// Hash of every transaction admitted into the mempool.
+    NewPendingTransactions,
+    /// Hash of every transaction dropped from the mempool without being included.
+    DroppedTransactions,
+    /// Native balance of the address after every block changing it.
+    BalanceChanges(Address),
+}
+
+impl Subscription {
+    /// Parses the `rmreth_subscribe` params, the kind followed by its arguments.
+    pub fn parse(params: Option<&Value>) -> Result<Self, RpcError> {
+        let params = match params {
+            Some(Value::Array(params)) => params.as_slice(),
+            _ => return Err(RpcError::invalid_params("expected an array")),
+        };
+
+        match params.first().and_then(Value::as_str) {
+            Some("newHeads") => Ok(Self::NewHeads),
+            Some("newPendingTransactions") => Ok(Self::NewPendingTransactions),
+            Some("droppedTransactions") => Ok(Self::DroppedTransactions),
+            Some("balanceChanges") => {
+                let address = params
+                    .get(1)
+                    .ok_or_else(|| RpcError::invalid_params("missing address"))?;
+
+                Address::deserialize(address)
+                    .map(Self::BalanceChanges)
+                    .map_err(|e| RpcError::invalid_params(format!("address: {e}")))
+            }
+            Some(kind) => Err(RpcError::invalid_params(format!(
+                "unknown subscription: {kind}"
+            ))),
+            None => Err(RpcError::invalid_params("missing subscription kind")),
+        }
+    }
+
+    /// Result to notify for `event`, if the subscription is interested in it.
+    pub fn notification(&self, event: &NodeEvent) -> Option<Value> {
+        match (self, event) {
+            (Self::NewHeads, NodeEvent::NewBlock(block)) => {
+                let mut head = serde_json::to_value(block.header()).ok()?;
+                head["hash"] = json!(block.get_hash());
+
+                Some(head)
+            }
+            (Self::NewPendingTransactions, NodeEvent::TxAccepted(hash))
+            | (Self::DroppedTransactions, NodeEvent::TxDropped(hash)) => Some(json!(hash)),
+            (Self::BalanceChanges(address), NodeEvent::NewBlock(block)) => {
+                let balance = block
+                    .data()
+                    .tokens
+                    .iter()
+                    .find(|balance| balance.addr == *address)?;
+
+                Some(json!({
+                    "block_id": block.id(),
+                    "address": address,
+                    "balance": balance.amount,
+                }))
+            }
+            _ => None,
+        }
+    }
+}
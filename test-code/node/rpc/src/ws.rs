@@ -0,0 +1,193 @@
+use std::{collections::BTreeMap, io, sync::Arc, time::Duration};
+
+use dispatcher::{
+    command::{Command, Response},
+    error::DispatchError,
+};
+use futures_util::{SinkExt, StreamExt};
+use node::{events::NodeEvent, manager::NodeManager};
+use serde_json::{Value, json};
+use tokio::{
+    net::{TcpListener, TcpStream},
+    sync::broadcast::error::RecvError,
+    time::timeout,
+};
+use tokio_tungstenite::{
+    WebSocketStream, accept_async,
+    tungstenite::{
+        self, Message,
+        protocol::{CloseFrame, frame::coding::CloseCode},
+    },
+};
+use tower::Service;
+
+use crate::{
+    error::RpcError,
+    handler::{handle_value, reply},
+    subscription::Subscription,
+};
+
+/// Subscriptions a single connection may hold at once.
+pub const MAX_SUBSCRIPTIONS: usize = 32;
+
+/// Time a client gets to take a message before it is disconnected.
+pub const SEND_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Serves JSON-RPC 2.0 over WebSocket connections accepted on `listener`, with
+/// `rmreth_subscribe` and `rmreth_unsubscribe` on top of the methods of
+/// [`crate::handle`], until the task is dropped.
+///
+/// Subscriptions are fed by the [`NodeManager`] event bus. The node never waits
+/// for subscribers: a client falling too far behind it, or taking longer than
+/// [`SEND_TIMEOUT`] to receive a message, is disconnected.
+pub async fn serve_ws<S>(listener: TcpListener, service: S, node: Arc<NodeManager>)
+where
+    S: Service<Command, Response = Response, Error = DispatchError> + Clone + Send + 'static,
+    S::Future: Send,
+{
+    loop {
+        let (stream, _) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                tracing::warn!(error = %e, "ws accept failed");
+                continue;
+            }
+        };
+
+        let service = service.clone();
+        let node = node.clone();
+
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(stream, service, &node).await {
+                tracing::debug!(error = %e, "ws connection failed");
+            }
+        });
+    }
+}
+
+#[derive(Default)]
+struct Subscriptions {
+    active: BTreeMap<u64, Subscription>,
+    next_id: u64,
+}
+
+impl Subscriptions {
+    fn subscribe(&mut self, params: Option<&Value>) -> Result<Value, RpcError> {
+        if self.active.len() >= MAX_SUBSCRIPTIONS {
+            return Err(RpcError::invalid_request("too many subscriptions"));
+        }
+
+        let subscription = Subscription::parse(params)?;
+
+        self.next_id += 1;
+        self.active.insert(self.next_id, subscription);
+
+        Ok(self.next_id.into())
+    }
+
+    fn unsubscribe(&mut self, params: Option<&Value>) -> Result<Value, RpcError> {
+        let id = params
+            .and_then(|params| params.get(0))
+            .and_then(Value::as_u64)
+            .ok_or_else(|| RpcError::invalid_params("expected a subscription id"))?;
+
+        Ok(self.active.remove(&id).is_some().into())
+    }
+
+    fn notifications(&self, event: &NodeEvent) -> Vec<Value> {
+        self.active
+            .iter()
+            .filter_map(|(id, subscription)| {
+                let result = subscription.notification(event)?;
+
+                Some(json!({
+                    "jsonrpc": "2.0",
+                    "method": "rmreth_subscription",
+                    "params": { "subscription": id, "result": result },
+                }))
+            })
+            .collect()
+    }
+}
+
+async fn handle_connection<S>(
+    stream: TcpStream,
+    service: S,
+    node: &NodeManager,
+) -> Result<(), tungstenite::Error>
+where
+    S: Service<Command, Response = Response, Error = DispatchError> + Clone,
+{
+    let mut ws = accept_async(stream).await?;
+    let mut events = node.events().subscribe();
+    let mut subscriptions = Subscriptions::default();
+
+    loop {
+        tokio::select! {
+            message = ws.next() => {
+                let text = match message {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Close(_))) | None => return Ok(()),
+                    // pings are answered by tungstenite itself
+                    Some(Ok(_)) => continue,
+                    Some(Err(e)) => return Err(e),
+                };
+
+                let reply = match serde_json::from_str(text.as_str()) {
+                    Ok(request) => handle_request(request, &mut subscriptions, service.clone()).await,
+                    Err(e) => Some(reply(Value::Null, Err(RpcError::parse_error(e)))),
+                };
+
+                if let Some(reply) = reply {
+                    send(&mut ws, reply).await?;
+                }
+            }
+            event = events.recv() => match event {
+                Ok(event) => {
+                    for notification in subscriptions.notifications(&event) {
+                        send(&mut ws, notification).await?;
+                    }
+                }
+                Err(RecvError::Lagged(missed)) => {
+                    let frame = CloseFrame {
+                        code: CloseCode::Policy,
+                        reason: format!("lagging behind, {missed} events missed").into(),
+                    };
+
+                    return ws.close(Some(frame)).await;
+                }
+                Err(RecvError::Closed) => return ws.close(None).await,
+            },
+        }
+    }
+}
+
+async fn handle_request<S>(
+    request: Value,
+    subscriptions: &mut Subscriptions,
+    service: S,
+) -> Option<Value>
+where
+    S: Service<Command, Response = Response, Error = DispatchError>,
+{
+    let result = match request.get("method").and_then(Value::as_str) {
+        Some("rmreth_subscribe") => subscriptions.subscribe(request.get("params")),
+        Some("rmreth_unsubscribe") => subscriptions.unsubscribe(request.get("params")),
+        _ => return handle_value(request, service).await,
+    };
+
+    let id = request.get("id")?.clone();
+
+    Some(reply(id, result))
+}
+
+async fn send(
+    ws: &mut WebSocketStream<TcpStream>,
+    message: Value,
+) -> Result<(), tungstenite::Error> {
+    let message = Message::Text(message.to_string().into());
+
+    timeout(SEND_TIMEOUT, ws.send(message))
+        .await
+        .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "client too slow"))?
+}
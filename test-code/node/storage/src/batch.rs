@@ -0,0 +1,305 @@
+use std::{
+    cell::RefCell,
+    ops::{Range, RangeInclusive},
+};
+
+use redb::{Key, ReadableTable, TableDefinition, TableHandle, Value, WriteTransaction};
+use rm_reth_types::{
+    Address,
+    asset::{AssetId, AssetMetadata},
+    block::block::Block,
+    bloom::Bloom,
+    finality::CommitCertificate,
+    hash::Hash,
+    htlc::Htlc,
+    int::Uint256,
+    multisig::MultisigAccount,
+    staking::{EpochValidators, ValidatorRecord, key_id},
+    vesting::{VestingOffer, VestingSchedule},
+};
+
+use crate::{
+    error::StorageError,
+    schema::{DbSchema, FINALIZED_KEY, PRUNED_KEY},
+    undo::{BlockUndo, UndoEntry},
+};
+
+/// Writes to several tables that become visible together.
+///
+/// Every write goes through the same write transaction, so nothing is observed
+/// before [`WriteBatch::commit`] returns, and dropping the batch discards it.
+/// Reads see the writes of the batch itself.
+///
+/// The values the `insert_*` methods overwrite are recorded, so that
+/// [`WriteBatch::save_undo`] can keep them for a block that may later be
+/// reverted.
+pub struct WriteBatch<'a> {
+    schema: &'a DbSchema,
+    txn: WriteTransaction,
+    undo: RefCell<Vec<UndoEntry>>,
+}
+
+impl<'a> WriteBatch<'a> {
+    pub(crate) fn new(schema: &'a DbSchema, txn: WriteTransaction) -> Self {
+        Self {
+            schema,
+            txn,
+            undo: RefCell::new(vec![]),
+        }
+    }
+
+    pub fn commit(self) -> Result<(), StorageError> {
+        Ok(self.txn.commit()?)
+    }
+
+    pub fn insert_block(&self, id: u64, block: &Block) -> Result<(), StorageError> {
+        self.insert_all(self.schema.block, [(&id, block)])
+    }
+
+    pub fn insert_bloom(&self, id: u64, bloom: &Bloom) -> Result<(), StorageError> {
+        self.insert_all(self.schema.bloom, [(&id, bloom)])
+    }
+
+    pub fn insert_commit(&self, id: u64, commit: &CommitCertificate) -> Result<(), StorageError> {
+        self.insert_all(self.schema.commit, [(&id, commit)])
+    }
+
+    /// Deletes the blocks and blooms of `ids`.
+    pub fn remove_blocks(&self, ids: Range<u64>) -> Result<(), StorageError> {
+        self.txn
+            .open_table(self.schema.block)?
+            .retain_in(ids.clone(), |_, _| false)?;
+        self.txn
+            .open_table(self.schema.bloom)?
+            .retain_in(ids, |_, _| false)?;
+
+        Ok(())
+    }
+
+    /// Records the highest block id that can no longer be replaced.
+    pub fn set_finalized_height(&self, height: u64) -> Result<(), StorageError> {
+        self.txn
+            .open_table(self.schema.chain)?
+            .insert(FINALIZED_KEY, height)?;
+
+        Ok(())
+    }
+
+    /// Records that the blocks below `height`, genesis aside, were pruned.
+    pub fn set_pruned_height(&self, height: u64) -> Result<(), StorageError> {
+        self.txn
+            .open_table(self.schema.chain)?
+            .insert(PRUNED_KEY, height)?;
+
+        Ok(())
+    }
+
+    /// Keeps what the writes since the last call overwrote as the undo of block
+    /// `id`.
+    pub fn save_undo(&self, id: u64) -> Result<(), StorageError> {
+        let undo = BlockUndo(self.undo.take());
+
+        self.txn.open_table(self.schema.undo)?.insert(&id, &undo)?;
+
+        Ok(())
+    }
+
+    /// Drops the undo of `ids`, whose blocks can no longer be reverted.
+    pub fn remove_undo(&self, ids: RangeInclusive<u64>) -> Result<(), StorageError> {
+        self.txn
+            .open_table(self.schema.undo)?
+            .retain_in(ids, |_, _| false)?;
+
+        Ok(())
+    }
+
+    /// Puts back everything block `id` overwrote, newest first, which removes
+    /// the block itself. Returns `false` if no undo was kept for it.
+    pub fn revert_block(&self, id: u64) -> Result<bool, StorageError> {
+        let Some(undo) = self
+            .txn
+            .open_table(self.schema.undo)?
+            .remove(&id)?
+            .map(|undo| undo.value())
+        else {
+            return Ok(false);
+        };
+
+        for entry in undo.0.iter().rev() {
+            self.restore(entry)?;
+        }
+
+        Ok(true)
+    }
+
+    pub fn insert_epoch(&self, validators: &EpochValidators) -> Result<(), StorageError> {
+        self.insert_all(self.schema.epoch, [(&validators.epoch, validators)])
+    }
+
+    pub fn insert_balances<'b>(
+        &self,
+        items: impl IntoIterator<Item = (&'b Address, &'b Uint256)>,
+    ) -> Result<(), StorageError> {
+        self.insert_all(self.schema.balance, items)
+    }
+
+    pub fn insert_nonces<'b>(
+        &self,
+        items: impl IntoIterator<Item = (&'b Address, &'b u64)>,
+    ) -> Result<(), StorageError> {
+        self.insert_all(self.schema.nonce, items)
+    }
+
+    pub fn insert_assets<'b>(
+        &self,
+        items: impl IntoIterator<Item = (&'b AssetId, &'b AssetMetadata)>,
+    ) -> Result<(), StorageError> {
+        self.insert_all(self.schema.asset, items)
+    }
+
+    pub fn insert_asset_balances<'b>(
+        &self,
+        items: impl IntoIterator<Item = (&'b (Address, AssetId), &'b Uint256)>,
+    ) -> Result<(), StorageError> {
+        self.insert_all(self.schema.asset_balance, items)
+    }
+
+    /// Writes locks along with the index from their sender and recipient to them.
+    pub fn insert_htlcs<'b>(
+        &self,
+        items: impl IntoIterator<Item = (&'b Hash, &'b Htlc)>,
+    ) -> Result<(), StorageError> {
+        let items: Vec<_> = items.into_iter().collect();
+        let parties: Vec<_> = items
+            .iter()
+            .flat_map(|(id, htlc)| [(htlc.sender, **id), (htlc.recipient, **id)])
+            .collect();
+
+        self.insert_all(self.schema.htlc, items)?;
+        self.insert_all(
+            self.schema.htlc_by_address,
+            parties.iter().map(|key| (key, &())),
+        )
+    }
+
+    pub fn insert_vesting<'b>(
+        &self,
+        items: impl IntoIterator<Item = (&'b Address, &'b VestingSchedule)>,
+    ) -> Result<(), StorageError> {
+        self.insert_all(self.schema.vesting, items)
+    }
+
+    pub fn insert_vesting_offers<'b>(
+        &self,
+        items: impl IntoIterator<Item = (&'b Hash, &'b VestingOffer)>,
+    ) -> Result<(), StorageError> {
+        self.insert_all(self.schema.vesting_offer, items)
+    }
+
+    /// Writes registry entries along with the index from their keys to them.
+    pub fn insert_validators<'b>(
+        &self,
+        items: impl IntoIterator<Item = (&'b Address, &'b ValidatorRecord)>,
+    ) -> Result<(), StorageError> {
+        let items: Vec<_> = items.into_iter().collect();
+        let keys: Vec<_> = items
+            .iter()
+            .map(|(addr, record)| (key_id(&record.public_key), *addr))
+            .collect();
+
+        self.insert_all(self.schema.validator, items)?;
+        self.insert_all(
+            self.schema.validator_key,
+            keys.iter().map(|(id, addr)| (id, *addr)),
+        )
+    }
+
+    pub fn insert_multisigs<'b>(
+        &self,
+        items: impl IntoIterator<Item = (&'b Address, &'b MultisigAccount)>,
+    ) -> Result<(), StorageError> {
+        self.insert_all(self.schema.multisig, items)
+    }
+
+    /// Every entry of the validator registry, by scanning the table.
+    pub fn validators(&self) -> Result<Vec<ValidatorRecord>, StorageError> {
+        let table = self.txn.open_table(self.schema.validator)?;
+        let mut validators = vec![];
+
+        for entry in table.iter()? {
+            validators.push(entry?.1.value());
+        }
+
+        Ok(validators)
+    }
+
+    fn insert_all<'b, K: Key + 'static, V: Value + 'static>(
+        &self,
+        definition: TableDefinition<'static, K, V>,
+        items: impl IntoIterator<Item = (&'b K::SelfType<'b>, &'b V::SelfType<'b>)>,
+    ) -> Result<(), StorageError> {
+        let mut table = self.txn.open_table(definition)?;
+        let mut undo = self.undo.borrow_mut();
+
+        for (key, value) in items {
+            let old = table
+                .insert(key, value)?
+                .map(|old| V::as_bytes(&old.value()).as_ref().to_vec());
+
+            undo.push(UndoEntry {
+                table: definition.name().to_string(),
+                key: K::as_bytes(key).as_ref().to_vec(),
+                value: old,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn restore(&self, entry: &UndoEntry) -> Result<(), StorageError> {
+        let schema = self.schema;
+
+        match entry.table.as_str() {
+            name if name == schema.block.name() => self.restore_in(schema.block, entry),
+            name if name == schema.bloom.name() => self.restore_in(schema.bloom, entry),
+            name if name == schema.commit.name() => self.restore_in(schema.commit, entry),
+            name if name == schema.epoch.name() => self.restore_in(schema.epoch, entry),
+            name if name == schema.balance.name() => self.restore_in(schema.balance, entry),
+            name if name == schema.nonce.name() => self.restore_in(schema.nonce, entry),
+            name if name == schema.asset.name() => self.restore_in(schema.asset, entry),
+            name if name == schema.asset_balance.name() => {
+                self.restore_in(schema.asset_balance, entry)
+            }
+            name if name == schema.htlc.name() => self.restore_in(schema.htlc, entry),
+            name if name == schema.htlc_by_address.name() => {
+                self.restore_in(schema.htlc_by_address, entry)
+            }
+            name if name == schema.vesting.name() => self.restore_in(schema.vesting, entry),
+            name if name == schema.vesting_offer.name() => {
+                self.restore_in(schema.vesting_offer, entry)
+            }
+            name if name == schema.validator.name() => self.restore_in(schema.validator, entry),
+            name if name == schema.validator_key.name() => {
+                self.restore_in(schema.validator_key, entry)
+            }
+            name if name == schema.multisig.name() => self.restore_in(schema.multisig, entry),
+            name => Err(StorageError::Other(format!("cannot undo writes to {name}"))),
+        }
+    }
+
+    fn restore_in<K: Key + 'static, V: Value + 'static>(
+        &self,
+        definition: TableDefinition<'static, K, V>,
+        entry: &UndoEntry,
+    ) -> Result<(), StorageError> {
+        let mut table = self.txn.open_table(definition)?;
+        let key = K::from_bytes(&entry.key);
+
+        match &entry.value {
+            Some(value) => table.insert(key, V::from_bytes(value))?,
+            None => table.remove(key)?,
+        };
+
+        Ok(())
+    }
+}
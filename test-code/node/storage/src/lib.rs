@@ -1,7 +1,12 @@
+pub mod batch;
 pub mod error;
 pub mod manager;
 pub mod schema;
+pub mod snapshot;
 pub mod tables;
+pub mod undo;
 
+pub use batch::WriteBatch;
 pub use manager::StorageManager;
 pub use schema::TableId;
+pub use snapshot::Snapshot;
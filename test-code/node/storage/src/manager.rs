@@ -1,10 +1,12 @@
 use std::path::Path;
 
-use redb::Database;
+use redb::{Database, ReadableDatabase, backends::InMemoryBackend};
 
 use crate::{
+    batch::WriteBatch,
     error::StorageError,
     schema::{DbSchema, TableId},
+    snapshot::Snapshot,
     tables::TableAccessor,
 };
 
@@ -23,7 +25,15 @@ impl StorageManager {
     }
 
     pub fn create_or_open<P: AsRef<Path>>(path: P) -> Result<Self, StorageError> {
-        let db = Database::create(path)?;
+        Self::with_database(Database::create(path)?)
+    }
+
+    /// Creates a database that lives in memory only, mostly useful for tests.
+    pub fn in_memory() -> Result<Self, StorageError> {
+        Self::with_database(Database::builder().create_with_backend(InMemoryBackend::new())?)
+    }
+
+    fn with_database(db: Database) -> Result<Self, StorageError> {
         let schema = Box::new(DbSchema::new());
 
         let manager = Self { schema, db };
@@ -33,12 +43,37 @@ impl StorageManager {
         Ok(manager)
     }
 
+    /// Opens a read-only, point-in-time view of the tables.
+    pub fn snapshot(&self) -> Result<Snapshot<'_>, StorageError> {
+        Ok(Snapshot::new(&self.schema, self.db.begin_read()?))
+    }
+
+    /// Opens a batch of writes that are committed together, see [`WriteBatch`].
+    pub fn batch(&self) -> Result<WriteBatch<'_>, StorageError> {
+        Ok(WriteBatch::new(&self.schema, self.db.begin_write()?))
+    }
+
     pub fn create_tables(&self) -> Result<(), StorageError> {
         let txn = self.db.begin_write()?;
 
         txn.open_table(self.schema.block)?;
         txn.open_table(self.schema.balance)?;
         txn.open_table(self.schema.nonce)?;
+        txn.open_table(self.schema.bloom)?;
+        txn.open_table(self.schema.asset)?;
+        txn.open_table(self.schema.asset_balance)?;
+        txn.open_table(self.schema.minter)?;
+        txn.open_table(self.schema.htlc)?;
+        txn.open_table(self.schema.htlc_by_address)?;
+        txn.open_table(self.schema.vesting)?;
+        txn.open_table(self.schema.commit)?;
+        txn.open_table(self.schema.validator)?;
+        txn.open_table(self.schema.validator_key)?;
+        txn.open_table(self.schema.epoch)?;
+        txn.open_table(self.schema.multisig)?;
+        txn.open_table(self.schema.vesting_offer)?;
+        txn.open_table(self.schema.chain)?;
+        txn.open_table(self.schema.undo)?;
 
         txn.commit()?;
 
@@ -64,6 +99,21 @@ impl StorageManager {
         txn.delete_table(self.schema.block)?;
         txn.delete_table(self.schema.balance)?;
         txn.delete_table(self.schema.nonce)?;
+        txn.delete_table(self.schema.bloom)?;
+        txn.delete_table(self.schema.asset)?;
+        txn.delete_table(self.schema.asset_balance)?;
+        txn.delete_table(self.schema.minter)?;
+        txn.delete_table(self.schema.htlc)?;
+        txn.delete_table(self.schema.htlc_by_address)?;
+        txn.delete_table(self.schema.vesting)?;
+        txn.delete_table(self.schema.commit)?;
+        txn.delete_table(self.schema.validator)?;
+        txn.delete_table(self.schema.validator_key)?;
+        txn.delete_table(self.schema.epoch)?;
+        txn.delete_table(self.schema.multisig)?;
+        txn.delete_table(self.schema.vesting_offer)?;
+        txn.delete_table(self.schema.chain)?;
+        txn.delete_table(self.schema.undo)?;
 
         txn.commit()?;
 
@@ -1,18 +1,70 @@
 use redb::TableDefinition;
-use rm_reth_types::{Address, block::block::Block, int::Uint256};
+use rm_reth_types::{
+    Address,
+    asset::{AssetId, AssetMetadata},
+    block::block::Block,
+    bloom::Bloom,
+    finality::CommitCertificate,
+    hash::Hash,
+    htlc::Htlc,
+    int::Uint256,
+    multisig::MultisigAccount,
+    staking::{EpochValidators, ValidatorRecord},
+    vesting::{VestingOffer, VestingSchedule},
+};
 
-use crate::tables::TableSpec;
+use crate::{tables::TableSpec, undo::BlockUndo};
 
 pub enum TableId {
     Block,
     Balance,
     Nonce,
+    Bloom,
+    Asset,
+    AssetBalance,
+    Minter,
+    Htlc,
+    HtlcByAddress,
+    Vesting,
+    Commit,
+    Validator,
+    ValidatorKey,
+    Epoch,
+    Multisig,
+    VestingOffer,
+    Chain,
+    Undo,
 }
 
+/// Key of the finalized height in the [`DbSchema::chain`] table.
+pub const FINALIZED_KEY: &str = "finalized";
+/// Key of the lowest non genesis block id not pruned in the [`DbSchema::chain`]
+/// table.
+pub const PRUNED_KEY: &str = "pruned";
+
 pub struct DbSchema {
     pub block: TableDefinition<'static, u64, Block>,
     pub balance: TableDefinition<'static, Address, Uint256>,
     pub nonce: TableDefinition<'static, Address, u64>,
+    pub bloom: TableDefinition<'static, u64, Bloom>,
+    pub asset: TableDefinition<'static, AssetId, AssetMetadata>,
+    pub asset_balance: TableDefinition<'static, (Address, AssetId), Uint256>,
+    pub minter: TableDefinition<'static, Address, bool>,
+    pub htlc: TableDefinition<'static, Hash, Htlc>,
+    /// Ids of the locks each address is the sender or the recipient of.
+    pub htlc_by_address: TableDefinition<'static, (Address, Hash), ()>,
+    pub vesting: TableDefinition<'static, Address, VestingSchedule>,
+    pub commit: TableDefinition<'static, u64, CommitCertificate>,
+    pub validator: TableDefinition<'static, Address, ValidatorRecord>,
+    /// Validator registered with a staking key, by [`rm_reth_types::staking::key_id`].
+    pub validator_key: TableDefinition<'static, Hash, Address>,
+    pub epoch: TableDefinition<'static, u64, EpochValidators>,
+    pub multisig: TableDefinition<'static, Address, MultisigAccount>,
+    pub vesting_offer: TableDefinition<'static, Hash, VestingOffer>,
+    /// Heights describing the stored chain, by name.
+    pub chain: TableDefinition<'static, &'static str, u64>,
+    /// What every block above the finalized height overwrote, by block id.
+    pub undo: TableDefinition<'static, u64, BlockUndo>,
 }
 
 impl DbSchema {
@@ -21,6 +73,21 @@ impl DbSchema {
             block: TableDefinition::new("Block"),
             balance: TableDefinition::new("Balance"),
             nonce: TableDefinition::new("Nonce"),
+            bloom: TableDefinition::new("Bloom"),
+            asset: TableDefinition::new("Asset"),
+            asset_balance: TableDefinition::new("AssetBalance"),
+            minter: TableDefinition::new("Minter"),
+            htlc: TableDefinition::new("Htlc"),
+            htlc_by_address: TableDefinition::new("HtlcByAddress"),
+            vesting: TableDefinition::new("Vesting"),
+            commit: TableDefinition::new("Commit"),
+            validator: TableDefinition::new("Validator"),
+            validator_key: TableDefinition::new("ValidatorKey"),
+            epoch: TableDefinition::new("Epoch"),
+            multisig: TableDefinition::new("Multisig"),
+            vesting_offer: TableDefinition::new("VestingOffer"),
+            chain: TableDefinition::new("Chain"),
+            undo: TableDefinition::new("Undo"),
         }
     }
 
@@ -29,6 +96,21 @@ impl DbSchema {
             TableId::Block => TableSpec::Block(self.block),
             TableId::Balance => TableSpec::Balance(self.balance),
             TableId::Nonce => TableSpec::Nonce(self.nonce),
+            TableId::Bloom => TableSpec::Bloom(self.bloom),
+            TableId::Asset => TableSpec::Asset(self.asset),
+            TableId::AssetBalance => TableSpec::AssetBalance(self.asset_balance),
+            TableId::Minter => TableSpec::Minter(self.minter),
+            TableId::Htlc => TableSpec::Htlc(self.htlc),
+            TableId::HtlcByAddress => TableSpec::HtlcByAddress(self.htlc_by_address),
+            TableId::Vesting => TableSpec::Vesting(self.vesting),
+            TableId::Commit => TableSpec::Commit(self.commit),
+            TableId::Validator => TableSpec::Validator(self.validator),
+            TableId::ValidatorKey => TableSpec::ValidatorKey(self.validator_key),
+            TableId::Epoch => TableSpec::Epoch(self.epoch),
+            TableId::Multisig => TableSpec::Multisig(self.multisig),
+            TableId::VestingOffer => TableSpec::VestingOffer(self.vesting_offer),
+            TableId::Chain => TableSpec::Chain(self.chain),
+            TableId::Undo => TableSpec::Undo(self.undo),
         }
     }
 }
@@ -0,0 +1,171 @@
+use redb::{ReadTransaction, ReadableTable};
+use rm_reth_types::{
+    Address,
+    asset::{AssetId, AssetMetadata},
+    block::block::Block,
+    bloom::Bloom,
+    hash::Hash,
+    htlc::Htlc,
+    int::Uint256,
+    multisig::{MultisigAccount, PublicKey},
+    staking::{EpochValidators, ValidatorRecord, key_id},
+    vesting::{VestingOffer, VestingSchedule},
+};
+
+use crate::{
+    error::StorageError,
+    schema::{DbSchema, FINALIZED_KEY, PRUNED_KEY},
+};
+
+/// Read-only view of the database at the moment it was opened.
+///
+/// Every read goes through the same read transaction, so a snapshot never observes
+/// writes committed after [`crate::StorageManager::snapshot`] was called.
+pub struct Snapshot<'a> {
+    schema: &'a DbSchema,
+    txn: ReadTransaction,
+}
+
+impl<'a> Snapshot<'a> {
+    pub(crate) fn new(schema: &'a DbSchema, txn: ReadTransaction) -> Self {
+        Self { schema, txn }
+    }
+
+    pub fn balance(&self, addr: &Address) -> Result<Uint256, StorageError> {
+        let table = self.txn.open_table(self.schema.balance)?;
+        Ok(table.get(addr)?.map(|v| v.value()).unwrap_or_default())
+    }
+
+    pub fn nonce(&self, addr: &Address) -> Result<u64, StorageError> {
+        let table = self.txn.open_table(self.schema.nonce)?;
+        Ok(table.get(addr)?.map(|v| v.value()).unwrap_or_default())
+    }
+
+    pub fn block(&self, id: u64) -> Result<Option<Block>, StorageError> {
+        let table = self.txn.open_table(self.schema.block)?;
+        Ok(table.get(&id)?.map(|v| v.value()))
+    }
+
+    /// Stored block with the highest id.
+    pub fn latest_block(&self) -> Result<Option<Block>, StorageError> {
+        let table = self.txn.open_table(self.schema.block)?;
+        Ok(table.last()?.map(|(_, v)| v.value()))
+    }
+
+    /// Whether block `id` is stored, without decoding it.
+    pub fn has_block(&self, id: u64) -> Result<bool, StorageError> {
+        let table = self.txn.open_table(self.schema.block)?;
+        Ok(table.get(&id)?.is_some())
+    }
+
+    pub fn bloom(&self, id: u64) -> Result<Option<Bloom>, StorageError> {
+        let table = self.txn.open_table(self.schema.bloom)?;
+        Ok(table.get(&id)?.map(|v| v.value()))
+    }
+
+    pub fn asset(&self, id: &AssetId) -> Result<Option<AssetMetadata>, StorageError> {
+        let table = self.txn.open_table(self.schema.asset)?;
+        Ok(table.get(id)?.map(|v| v.value()))
+    }
+
+    pub fn asset_balance(&self, addr: &Address, id: &AssetId) -> Result<Uint256, StorageError> {
+        let table = self.txn.open_table(self.schema.asset_balance)?;
+        Ok(table
+            .get(&(*addr, *id))?
+            .map(|v| v.value())
+            .unwrap_or_default())
+    }
+
+    pub fn is_minter(&self, addr: &Address) -> Result<bool, StorageError> {
+        let table = self.txn.open_table(self.schema.minter)?;
+        Ok(table.get(addr)?.is_some_and(|v| v.value()))
+    }
+
+    pub fn htlc(&self, id: &Hash) -> Result<Option<Htlc>, StorageError> {
+        let table = self.txn.open_table(self.schema.htlc)?;
+        Ok(table.get(id)?.map(|v| v.value()))
+    }
+
+    pub fn vesting(&self, addr: &Address) -> Result<Option<VestingSchedule>, StorageError> {
+        let table = self.txn.open_table(self.schema.vesting)?;
+        Ok(table.get(addr)?.map(|v| v.value()))
+    }
+
+    pub fn vesting_offer(&self, id: &Hash) -> Result<Option<VestingOffer>, StorageError> {
+        let table = self.txn.open_table(self.schema.vesting_offer)?;
+        Ok(table.get(id)?.map(|v| v.value()))
+    }
+
+    pub fn multisig(&self, addr: &Address) -> Result<Option<MultisigAccount>, StorageError> {
+        let table = self.txn.open_table(self.schema.multisig)?;
+        Ok(table.get(addr)?.map(|v| v.value()))
+    }
+
+    pub fn validator(&self, addr: &Address) -> Result<Option<ValidatorRecord>, StorageError> {
+        let table = self.txn.open_table(self.schema.validator)?;
+        Ok(table.get(addr)?.map(|v| v.value()))
+    }
+
+    /// Validator registered with `public_key`, if any.
+    pub fn validator_by_key(
+        &self,
+        public_key: &PublicKey,
+    ) -> Result<Option<Address>, StorageError> {
+        let table = self.txn.open_table(self.schema.validator_key)?;
+        Ok(table.get(key_id(public_key))?.map(|v| v.value()))
+    }
+
+    /// Every entry of the validator registry, by scanning the table.
+    pub fn validators(&self) -> Result<Vec<ValidatorRecord>, StorageError> {
+        let table = self.txn.open_table(self.schema.validator)?;
+        let mut validators = vec![];
+
+        for entry in table.iter()? {
+            validators.push(entry?.1.value());
+        }
+
+        Ok(validators)
+    }
+
+    /// Finalized height recorded by [`crate::WriteBatch::set_finalized_height`].
+    pub fn finalized_height(&self) -> Result<u64, StorageError> {
+        let table = self.txn.open_table(self.schema.chain)?;
+        Ok(table
+            .get(FINALIZED_KEY)?
+            .map(|v| v.value())
+            .unwrap_or_default())
+    }
+
+    /// Pruning horizon recorded by [`crate::WriteBatch::set_pruned_height`].
+    pub fn pruned_height(&self) -> Result<u64, StorageError> {
+        let table = self.txn.open_table(self.schema.chain)?;
+        Ok(table
+            .get(PRUNED_KEY)?
+            .map(|v| v.value())
+            .unwrap_or_default())
+    }
+
+    pub fn epoch(&self, epoch: u64) -> Result<Option<EpochValidators>, StorageError> {
+        let table = self.txn.open_table(self.schema.epoch)?;
+        Ok(table.get(epoch)?.map(|v| v.value()))
+    }
+
+    /// Open locks `addr` is the sender or the recipient of, sorted by id.
+    pub fn open_htlcs(&self, addr: &Address) -> Result<Vec<Htlc>, StorageError> {
+        let index = self.txn.open_table(self.schema.htlc_by_address)?;
+        let table = self.txn.open_table(self.schema.htlc)?;
+        let mut htlcs = vec![];
+
+        for entry in index.range((*addr, Hash::empty())..=(*addr, [0xff; 32].into()))? {
+            let (_, id) = entry?.0.value();
+
+            if let Some(htlc) = table.get(id)?.map(|v| v.value())
+                && htlc.is_open()
+            {
+                htlcs.push(htlc);
+            }
+        }
+
+        Ok(htlcs)
+    }
+}
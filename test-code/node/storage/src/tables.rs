@@ -1,12 +1,39 @@
 use redb::{Database, Key, ReadableDatabase, TableDefinition, Value};
-use rm_reth_types::{Address, block::block::Block, int::Uint256};
-
-use crate::error::StorageError;
+use rm_reth_types::{
+    Address,
+    asset::{AssetId, AssetMetadata},
+    block::block::Block,
+    bloom::Bloom,
+    finality::CommitCertificate,
+    hash::Hash,
+    htlc::Htlc,
+    int::Uint256,
+    multisig::MultisigAccount,
+    staking::{EpochValidators, ValidatorRecord},
+    vesting::{VestingOffer, VestingSchedule},
+};
+
+use crate::{error::StorageError, undo::BlockUndo};
 
 pub enum TableSpec {
     Block(TableDefinition<'static, u64, Block>),
     Balance(TableDefinition<'static, Address, Uint256>),
     Nonce(TableDefinition<'static, Address, u64>),
+    Bloom(TableDefinition<'static, u64, Bloom>),
+    Asset(TableDefinition<'static, AssetId, AssetMetadata>),
+    AssetBalance(TableDefinition<'static, (Address, AssetId), Uint256>),
+    Minter(TableDefinition<'static, Address, bool>),
+    Htlc(TableDefinition<'static, Hash, Htlc>),
+    HtlcByAddress(TableDefinition<'static, (Address, Hash), ()>),
+    Vesting(TableDefinition<'static, Address, VestingSchedule>),
+    Commit(TableDefinition<'static, u64, CommitCertificate>),
+    Validator(TableDefinition<'static, Address, ValidatorRecord>),
+    ValidatorKey(TableDefinition<'static, Hash, Address>),
+    Epoch(TableDefinition<'static, u64, EpochValidators>),
+    Multisig(TableDefinition<'static, Address, MultisigAccount>),
+    VestingOffer(TableDefinition<'static, Hash, VestingOffer>),
+    Chain(TableDefinition<'static, &'static str, u64>),
+    Undo(TableDefinition<'static, u64, BlockUndo>),
 }
 
 impl TableSpec {
@@ -16,6 +43,31 @@ impl TableSpec {
             TableSpec::Block(table) => TableAccessor::Block(TableAccessContext { db, table }),
             TableSpec::Balance(table) => TableAccessor::Balance(TableAccessContext { db, table }),
             TableSpec::Nonce(table) => TableAccessor::Nonce(TableAccessContext { db, table }),
+            TableSpec::Bloom(table) => TableAccessor::Bloom(TableAccessContext { db, table }),
+            TableSpec::Asset(table) => TableAccessor::Asset(TableAccessContext { db, table }),
+            TableSpec::AssetBalance(table) => {
+                TableAccessor::AssetBalance(TableAccessContext { db, table })
+            }
+            TableSpec::Minter(table) => TableAccessor::Minter(TableAccessContext { db, table }),
+            TableSpec::Htlc(table) => TableAccessor::Htlc(TableAccessContext { db, table }),
+            TableSpec::HtlcByAddress(table) => {
+                TableAccessor::HtlcByAddress(TableAccessContext { db, table })
+            }
+            TableSpec::Vesting(table) => TableAccessor::Vesting(TableAccessContext { db, table }),
+            TableSpec::Commit(table) => TableAccessor::Commit(TableAccessContext { db, table }),
+            TableSpec::Validator(table) => {
+                TableAccessor::Validator(TableAccessContext { db, table })
+            }
+            TableSpec::ValidatorKey(table) => {
+                TableAccessor::ValidatorKey(TableAccessContext { db, table })
+            }
+            TableSpec::Epoch(table) => TableAccessor::Epoch(TableAccessContext { db, table }),
+            TableSpec::Multisig(table) => TableAccessor::Multisig(TableAccessContext { db, table }),
+            TableSpec::VestingOffer(table) => {
+                TableAccessor::VestingOffer(TableAccessContext { db, table })
+            }
+            TableSpec::Chain(table) => TableAccessor::Chain(TableAccessContext { db, table }),
+            TableSpec::Undo(table) => TableAccessor::Undo(TableAccessContext { db, table }),
         }
     }
 }
@@ -24,6 +76,21 @@ pub enum TableAccessor<'db> {
     Block(TableAccessContext<'db, u64, Block>),
     Balance(TableAccessContext<'db, Address, Uint256>),
     Nonce(TableAccessContext<'db, Address, u64>),
+    Bloom(TableAccessContext<'db, u64, Bloom>),
+    Asset(TableAccessContext<'db, AssetId, AssetMetadata>),
+    AssetBalance(TableAccessContext<'db, (Address, AssetId), Uint256>),
+    Minter(TableAccessContext<'db, Address, bool>),
+    Htlc(TableAccessContext<'db, Hash, Htlc>),
+    HtlcByAddress(TableAccessContext<'db, (Address, Hash), ()>),
+    Vesting(TableAccessContext<'db, Address, VestingSchedule>),
+    Commit(TableAccessContext<'db, u64, CommitCertificate>),
+    Validator(TableAccessContext<'db, Address, ValidatorRecord>),
+    ValidatorKey(TableAccessContext<'db, Hash, Address>),
+    Epoch(TableAccessContext<'db, u64, EpochValidators>),
+    Multisig(TableAccessContext<'db, Address, MultisigAccount>),
+    VestingOffer(TableAccessContext<'db, Hash, VestingOffer>),
+    Chain(TableAccessContext<'db, &'static str, u64>),
+    Undo(TableAccessContext<'db, u64, BlockUndo>),
 }
 
 impl<'db> TableAccessor<'db> {
@@ -74,6 +141,248 @@ impl<'db> TableAccessor<'db> {
             _ => panic!("(UB) Accessed Nonce table incorrectly"),
         }
     }
+
+    #[inline]
+    pub fn as_bloom(&self) -> Option<&TableAccessContext<'db, u64, Bloom>> {
+        match self {
+            TableAccessor::Bloom(ctx) => Some(ctx),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn to_bloom(self) -> TableAccessContext<'db, u64, Bloom> {
+        match self {
+            TableAccessor::Bloom(ctx) => ctx,
+            _ => panic!("(UB) Accessed Bloom table incorrectly"),
+        }
+    }
+
+    #[inline]
+    pub fn as_asset(&self) -> Option<&TableAccessContext<'db, AssetId, AssetMetadata>> {
+        match self {
+            TableAccessor::Asset(ctx) => Some(ctx),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn to_asset(self) -> TableAccessContext<'db, AssetId, AssetMetadata> {
+        match self {
+            TableAccessor::Asset(ctx) => ctx,
+            _ => panic!("(UB) Accessed Asset table incorrectly"),
+        }
+    }
+
+    #[inline]
+    pub fn as_asset_balance(
+        &self,
+    ) -> Option<&TableAccessContext<'db, (Address, AssetId), Uint256>> {
+        match self {
+            TableAccessor::AssetBalance(ctx) => Some(ctx),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn to_asset_balance(self) -> TableAccessContext<'db, (Address, AssetId), Uint256> {
+        match self {
+            TableAccessor::AssetBalance(ctx) => ctx,
+            _ => panic!("(UB) Accessed AssetBalance table incorrectly"),
+        }
+    }
+
+    #[inline]
+    pub fn as_minter(&self) -> Option<&TableAccessContext<'db, Address, bool>> {
+        match self {
+            TableAccessor::Minter(ctx) => Some(ctx),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn to_minter(self) -> TableAccessContext<'db, Address, bool> {
+        match self {
+            TableAccessor::Minter(ctx) => ctx,
+            _ => panic!("(UB) Accessed Minter table incorrectly"),
+        }
+    }
+
+    #[inline]
+    pub fn as_htlc(&self) -> Option<&TableAccessContext<'db, Hash, Htlc>> {
+        match self {
+            TableAccessor::Htlc(ctx) => Some(ctx),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn to_htlc(self) -> TableAccessContext<'db, Hash, Htlc> {
+        match self {
+            TableAccessor::Htlc(ctx) => ctx,
+            _ => panic!("(UB) Accessed Htlc table incorrectly"),
+        }
+    }
+
+    #[inline]
+    pub fn as_htlc_by_address(&self) -> Option<&TableAccessContext<'db, (Address, Hash), ()>> {
+        match self {
+            TableAccessor::HtlcByAddress(ctx) => Some(ctx),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn to_htlc_by_address(self) -> TableAccessContext<'db, (Address, Hash), ()> {
+        match self {
+            TableAccessor::HtlcByAddress(ctx) => ctx,
+            _ => panic!("(UB) Accessed HtlcByAddress table incorrectly"),
+        }
+    }
+
+    #[inline]
+    pub fn as_vesting(&self) -> Option<&TableAccessContext<'db, Address, VestingSchedule>> {
+        match self {
+            TableAccessor::Vesting(ctx) => Some(ctx),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn to_vesting(self) -> TableAccessContext<'db, Address, VestingSchedule> {
+        match self {
+            TableAccessor::Vesting(ctx) => ctx,
+            _ => panic!("(UB) Accessed Vesting table incorrectly"),
+        }
+    }
+
+    #[inline]
+    pub fn as_commit(&self) -> Option<&TableAccessContext<'db, u64, CommitCertificate>> {
+        match self {
+            TableAccessor::Commit(ctx) => Some(ctx),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn to_commit(self) -> TableAccessContext<'db, u64, CommitCertificate> {
+        match self {
+            TableAccessor::Commit(ctx) => ctx,
+            _ => panic!("(UB) Accessed Commit table incorrectly"),
+        }
+    }
+
+    #[inline]
+    pub fn as_validator(&self) -> Option<&TableAccessContext<'db, Address, ValidatorRecord>> {
+        match self {
+            TableAccessor::Validator(ctx) => Some(ctx),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn to_validator(self) -> TableAccessContext<'db, Address, ValidatorRecord> {
+        match self {
+            TableAccessor::Validator(ctx) => ctx,
+            _ => panic!("(UB) Accessed Validator table incorrectly"),
+        }
+    }
+
+    #[inline]
+    pub fn as_validator_key(&self) -> Option<&TableAccessContext<'db, Hash, Address>> {
+        match self {
+            TableAccessor::ValidatorKey(ctx) => Some(ctx),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn to_validator_key(self) -> TableAccessContext<'db, Hash, Address> {
+        match self {
+            TableAccessor::ValidatorKey(ctx) => ctx,
+            _ => panic!("(UB) Accessed ValidatorKey table incorrectly"),
+        }
+    }
+
+    #[inline]
+    pub fn as_epoch(&self) -> Option<&TableAccessContext<'db, u64, EpochValidators>> {
+        match self {
+            TableAccessor::Epoch(ctx) => Some(ctx),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn to_epoch(self) -> TableAccessContext<'db, u64, EpochValidators> {
+        match self {
+            TableAccessor::Epoch(ctx) => ctx,
+            _ => panic!("(UB) Accessed Epoch table incorrectly"),
+        }
+    }
+
+    #[inline]
+    pub fn as_multisig(&self) -> Option<&TableAccessContext<'db, Address, MultisigAccount>> {
+        match self {
+            TableAccessor::Multisig(ctx) => Some(ctx),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn to_multisig(self) -> TableAccessContext<'db, Address, MultisigAccount> {
+        match self {
+            TableAccessor::Multisig(ctx) => ctx,
+            _ => panic!("(UB) Accessed Multisig table incorrectly"),
+        }
+    }
+
+    #[inline]
+    pub fn as_vesting_offer(&self) -> Option<&TableAccessContext<'db, Hash, VestingOffer>> {
+        match self {
+            TableAccessor::VestingOffer(ctx) => Some(ctx),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn to_vesting_offer(self) -> TableAccessContext<'db, Hash, VestingOffer> {
+        match self {
+            TableAccessor::VestingOffer(ctx) => ctx,
+            _ => panic!("(UB) Accessed VestingOffer table incorrectly"),
+        }
+    }
+
+    #[inline]
+    pub fn as_chain(&self) -> Option<&TableAccessContext<'db, &'static str, u64>> {
+        match self {
+            TableAccessor::Chain(ctx) => Some(ctx),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn to_chain(self) -> TableAccessContext<'db, &'static str, u64> {
+        match self {
+            TableAccessor::Chain(ctx) => ctx,
+            _ => panic!("(UB) Accessed Chain table incorrectly"),
+        }
+    }
+
+    #[inline]
+    pub fn as_undo(&self) -> Option<&TableAccessContext<'db, u64, BlockUndo>> {
+        match self {
+            TableAccessor::Undo(ctx) => Some(ctx),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn to_undo(self) -> TableAccessContext<'db, u64, BlockUndo> {
+        match self {
+            TableAccessor::Undo(ctx) => ctx,
+            _ => panic!("(UB) Accessed Undo table incorrectly"),
+        }
+    }
 }
 
 pub struct TableAccessContext<'db, K: Key + 'static, V: Value + 'static> {
@@ -0,0 +1,52 @@
+use parity_scale_codec::{Decode, Encode};
+use redb::TypeName;
+
+/// Entry of a table as it was before a write, in the encoding of the table.
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+pub struct UndoEntry {
+    pub table: String,
+    pub key: Vec<u8>,
+    /// `None` if the key was not in the table.
+    pub value: Option<Vec<u8>>,
+}
+
+/// What the writes of one block overwrote, oldest first, so that the block can
+/// be taken back out of the chain by [`crate::WriteBatch::revert_block`].
+#[derive(Debug, Clone, Default, PartialEq, Encode, Decode)]
+pub struct BlockUndo(pub Vec<UndoEntry>);
+
+impl redb::Value for BlockUndo {
+    type SelfType<'a>
+        = BlockUndo
+    where
+        Self: 'a;
+
+    type AsBytes<'a>
+        = Vec<u8>
+    where
+        Self: 'a;
+
+    fn fixed_width() -> Option<usize> {
+        None
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a Self::SelfType<'b>) -> Self::AsBytes<'a>
+    where
+        Self: 'b,
+    {
+        value.encode()
+    }
+
+    fn from_bytes<'a>(data: &'a [u8]) -> Self::SelfType<'a>
+    where
+        Self: 'a,
+    {
+        let mut slice = data;
+
+        BlockUndo::decode(&mut slice).expect("block undo decode failed")
+    }
+
+    fn type_name() -> TypeName {
+        TypeName::new("BlockUndo")
+    }
+}
@@ -0,0 +1,212 @@
+use std::fmt;
+
+use parity_scale_codec::{Decode, Encode};
+use redb::TypeName;
+
+#[cfg(feature = "json")]
+use serde::{Deserialize, Serialize};
+
+use crate::{Address, hash::Hash, int::Uint256};
+
+pub const MAX_SYMBOL_LEN: usize = 12;
+
+/// Identifier of a user-issued fungible asset.
+///
+/// Derived from the issuer and the symbol, so an issuer can use each symbol once.
+#[repr(transparent)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Encode, Decode, Default)]
+pub struct AssetId(pub Hash);
+
+impl AssetId {
+    pub fn derive(issuer: &Address, symbol: &str) -> Self {
+        let mut buf = issuer.to_vec();
+        buf.extend_from_slice(symbol.as_bytes());
+
+        Self(Hash::hash(&buf))
+    }
+
+    #[inline]
+    pub fn as_slice(&self) -> &[u8] {
+        self.0.as_slice()
+    }
+}
+
+// same order as the `redb::Key` impl
+impl PartialOrd for AssetId {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for AssetId {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.as_slice().cmp(other.as_slice())
+    }
+}
+
+impl fmt::Display for AssetId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl fmt::Debug for AssetId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("AssetId(")?;
+        fmt::Display::fmt(&self.0, f)?;
+        f.write_str(")")
+    }
+}
+
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[derive(Debug, Encode, Decode, PartialEq, Eq, Clone)]
+pub struct AssetMetadata {
+    pub symbol: String,
+    pub decimals: u8,
+    pub issuer: Address,
+    pub total_supply: Uint256,
+}
+
+impl AssetMetadata {
+    #[inline]
+    pub fn id(&self) -> AssetId {
+        AssetId::derive(&self.issuer, &self.symbol)
+    }
+}
+
+/// Balance of one asset held by an address.
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[derive(Debug, Encode, Decode, PartialEq, Eq, Clone)]
+pub struct AssetBalance {
+    pub addr: Address,
+    pub asset: AssetId,
+    pub amount: Uint256,
+}
+
+/// Asset operation carried by a transaction, signed by `Transaction::from`.
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[derive(Debug, Encode, Decode, PartialEq, Eq, Clone)]
+pub enum AssetOp {
+    /// Creates a new asset and credits `initial_supply` to the issuer.
+    #[codec(index = 0)]
+    Issue {
+        symbol: String,
+        decimals: u8,
+        initial_supply: Uint256,
+    },
+    /// Creates new units, only allowed for the issuer.
+    #[codec(index = 1)]
+    Mint {
+        asset: AssetId,
+        to: Address,
+        amount: Uint256,
+    },
+    /// Destroys units held by the sender.
+    #[codec(index = 2)]
+    Burn { asset: AssetId, amount: Uint256 },
+    #[codec(index = 3)]
+    Transfer {
+        asset: AssetId,
+        to: Address,
+        amount: Uint256,
+    },
+}
+
+impl AssetOp {
+    /// Asset the operation applies to.
+    pub fn asset_id(&self, sender: &Address) -> AssetId {
+        match self {
+            AssetOp::Issue { symbol, .. } => AssetId::derive(sender, symbol),
+            AssetOp::Mint { asset, .. }
+            | AssetOp::Burn { asset, .. }
+            | AssetOp::Transfer { asset, .. } => *asset,
+        }
+    }
+
+    /// Addresses whose balance of [`AssetOp::asset_id`] may change.
+    pub fn holders(&self, sender: &Address) -> Vec<Address> {
+        match self {
+            AssetOp::Issue { .. } | AssetOp::Burn { .. } => vec![*sender],
+            AssetOp::Mint { to, .. } => vec![*to],
+            AssetOp::Transfer { to, .. } => vec![*sender, *to],
+        }
+    }
+}
+
+impl redb::Value for AssetId {
+    type SelfType<'a>
+        = AssetId
+    where
+        Self: 'a;
+
+    type AsBytes<'a>
+        = &'a [u8]
+    where
+        Self: 'a;
+
+    fn fixed_width() -> Option<usize> {
+        Some(32)
+    }
+
+    fn from_bytes<'a>(data: &'a [u8]) -> Self::SelfType<'a>
+    where
+        Self: 'a,
+    {
+        let bytes: [u8; 32] = data.try_into().unwrap();
+        AssetId(bytes.into())
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a Self::SelfType<'b>) -> Self::AsBytes<'a>
+    where
+        Self: 'b,
+    {
+        value.as_slice()
+    }
+
+    fn type_name() -> TypeName {
+        TypeName::new("AssetId")
+    }
+}
+
+impl redb::Key for AssetId {
+    fn compare(data1: &[u8], data2: &[u8]) -> std::cmp::Ordering {
+        data1.cmp(data2)
+    }
+}
+
+impl redb::Value for AssetMetadata {
+    type SelfType<'a>
+        = AssetMetadata
+    where
+        Self: 'a;
+
+    type AsBytes<'a>
+        = Vec<u8>
+    where
+        Self: 'a;
+
+    fn fixed_width() -> Option<usize> {
+        None
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a Self::SelfType<'b>) -> Self::AsBytes<'a>
+    where
+        Self: 'b,
+    {
+        value.encode()
+    }
+
+    fn from_bytes<'a>(data: &'a [u8]) -> Self::SelfType<'a>
+    where
+        Self: 'a,
+    {
+        let mut slice = data;
+
+        AssetMetadata::decode(&mut slice).expect("asset metadata decode failed")
+    }
+
+    fn type_name() -> TypeName {
+        TypeName::new("AssetMetadata")
+    }
+}
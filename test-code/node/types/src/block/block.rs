@@ -1,8 +1,14 @@
 use crate::Address;
+use crate::asset::{AssetBalance, AssetMetadata};
 use crate::block::error::BlockError;
+use crate::bloom::Bloom;
 use crate::bytes::FixedBytes;
+use crate::htlc::Htlc;
 use crate::int::Uint256;
-use crate::tx::transaction::Transaction;
+use crate::multisig::MultisigRecord;
+use crate::staking::ValidatorRecord;
+use crate::tx::{receipt::Receipt, transaction::Transaction};
+use crate::vesting::{VestingOffer, VestingSchedule};
 use crate::{hash::Hash, token::Balance};
 
 use parity_scale_codec::{Decode, Encode};
@@ -14,6 +20,8 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Clone, Encode, Decode)]
 pub struct Block {
     pub block_hash: Hash,
+    // consensus proof over `block_hash`, so not covered by it
+    pub seal: Vec<u8>,
     _inner: BlockInner,
 }
 
@@ -22,6 +30,7 @@ impl Block {
     pub fn new() -> Self {
         Self {
             block_hash: Hash::empty(),
+            seal: vec![],
             _inner: BlockInner::new(Header::empty(), BlockData::new()),
         }
     }
@@ -41,6 +50,48 @@ impl Block {
         self
     }
 
+    /// Stores the receipts and sets the header bloom accordingly.
+    pub fn with_receipts(mut self, receipts: Vec<Receipt>) -> Self {
+        self.header_mut().logs_bloom = Bloom::from_receipts(&receipts);
+        self.data_mut().receipts = receipts;
+        self
+    }
+
+    pub fn with_assets(
+        mut self,
+        assets: Vec<AssetMetadata>,
+        asset_balances: Vec<AssetBalance>,
+    ) -> Self {
+        self.data_mut().assets = assets;
+        self.data_mut().asset_balances = asset_balances;
+        self
+    }
+
+    pub fn with_htlcs(mut self, htlcs: Vec<Htlc>) -> Self {
+        self.data_mut().htlcs = htlcs;
+        self
+    }
+
+    pub fn with_vesting(
+        mut self,
+        vesting: Vec<VestingSchedule>,
+        vesting_offers: Vec<VestingOffer>,
+    ) -> Self {
+        self.data_mut().vesting = vesting;
+        self.data_mut().vesting_offers = vesting_offers;
+        self
+    }
+
+    pub fn with_validators(mut self, validators: Vec<ValidatorRecord>) -> Self {
+        self.data_mut().validators = validators;
+        self
+    }
+
+    pub fn with_multisigs(mut self, multisigs: Vec<MultisigRecord>) -> Self {
+        self.data_mut().multisigs = multisigs;
+        self
+    }
+
     pub fn with_vm_processed<I>(mut self, items: I) -> Self
     where
         I: IntoIterator<Item = (Address, Uint256)>,
@@ -50,21 +101,15 @@ impl Block {
     }
 
     pub fn genesis() -> Self {
-        let header = Header {
-            block_id: 0,
-            prev_block: Hash::empty(),
-            extra_data: FixedBytes::default(),
-        };
+        let header = Header::empty();
 
-        let data = BlockData {
-            tx_pool: vec![],
-            tokens: vec![],
-        };
+        let data = BlockData::new();
 
         let block = BlockInner { header, data };
 
         Self {
             block_hash: block.get_hash(),
+            seal: vec![],
             _inner: block,
         }
     }
@@ -147,6 +192,9 @@ impl BlockInner {
 pub struct Header {
     pub block_id: u64,
     pub prev_block: Hash,
+    pub logs_bloom: Bloom,
+    // set by the consensus engine, see `ConsensusEngine::prepare_header`
+    pub difficulty: u32,
     pub extra_data: FixedBytes<32>,
 }
 
@@ -155,6 +203,8 @@ impl Header {
         Self {
             block_id: 0,
             prev_block: Hash::empty(),
+            logs_bloom: Bloom::default(),
+            difficulty: 0,
             extra_data: FixedBytes::default(),
         }
     }
@@ -166,6 +216,22 @@ pub struct BlockData {
     pub tx_pool: Vec<Transaction>,
     // token holding amount (only contain changed address)
     pub tokens: Vec<Balance>, // TODO: save slot changed
+    // one receipt per entry of `tx_pool`
+    pub receipts: Vec<Receipt>,
+    // metadata of the assets touched by `tx_pool`
+    pub assets: Vec<AssetMetadata>,
+    // asset holding amount (only contain touched address and asset)
+    pub asset_balances: Vec<AssetBalance>,
+    // hash time-locked contracts touched by `tx_pool`
+    pub htlcs: Vec<Htlc>,
+    // vesting schedules of the senders and beneficiaries of `tx_pool`
+    pub vesting: Vec<VestingSchedule>,
+    // vesting offers made, accepted or cancelled by `tx_pool`
+    pub vesting_offers: Vec<VestingOffer>,
+    // registry entries of the stakers of `tx_pool`
+    pub validators: Vec<ValidatorRecord>,
+    // registered multisig accounts `tx_pool` sends from or creates
+    pub multisigs: Vec<MultisigRecord>,
 }
 
 impl BlockData {
@@ -173,6 +239,14 @@ impl BlockData {
         Self {
             tx_pool: vec![],
             tokens: vec![],
+            receipts: vec![],
+            assets: vec![],
+            asset_balances: vec![],
+            htlcs: vec![],
+            vesting: vec![],
+            vesting_offers: vec![],
+            validators: vec![],
+            multisigs: vec![],
         }
     }
 
@@ -188,6 +262,8 @@ impl BlockData {
             .into_iter()
             .map(|(addr, amount)| Balance { addr, amount })
             .collect();
+        // sources are usually hash maps, the block hash must not depend on their order
+        self.tokens.sort_by_key(|balance| balance.addr);
     }
 
     // pub fn finish(&mut self) -> Result<(), BlockError> {
@@ -0,0 +1,157 @@
+use std::fmt;
+
+use parity_scale_codec::{Decode, Encode};
+use redb::TypeName;
+
+#[cfg(feature = "json")]
+use serde::{Deserialize, Serialize};
+
+use crate::{bytes::FixedBytes, log::Log, tx::receipt::Receipt};
+
+pub const BLOOM_BYTES: usize = 256;
+
+const BLOOM_BITS: usize = BLOOM_BYTES * 8;
+
+/// 2048-bit bloom filter over log emitters and topics.
+///
+/// Every input sets three bits taken from its blake3 hash, so a cleared bit proves
+/// the input was never added while a set bit only means it may have been.
+#[repr(transparent)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Encode, Decode, Default)]
+pub struct Bloom(FixedBytes<BLOOM_BYTES>);
+
+impl Bloom {
+    pub fn from_receipts<'a>(receipts: impl IntoIterator<Item = &'a Receipt>) -> Self {
+        let mut bloom = Self::default();
+
+        for log in receipts.into_iter().flat_map(|receipt| &receipt.logs) {
+            bloom.accrue_log(log);
+        }
+
+        bloom
+    }
+
+    pub fn accrue(&mut self, input: &[u8]) {
+        for bit in bloom_bits(input) {
+            self.0.0[bit / 8] |= 1 << (bit % 8);
+        }
+    }
+
+    pub fn accrue_log(&mut self, log: &Log) {
+        self.accrue(log.address.as_slice());
+
+        for topic in &log.topics {
+            self.accrue(topic.as_slice());
+        }
+    }
+
+    pub fn contains_input(&self, input: &[u8]) -> bool {
+        bloom_bits(input)
+            .into_iter()
+            .all(|bit| self.0.0[bit / 8] & (1 << (bit % 8)) != 0)
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0.0.iter().all(|byte| *byte == 0)
+    }
+
+    #[inline]
+    pub fn as_slice(&self) -> &[u8] {
+        self.0.as_slice()
+    }
+}
+
+fn bloom_bits(input: &[u8]) -> [usize; 3] {
+    let hash = blake3::hash(input);
+    let bytes = hash.as_bytes();
+
+    [0, 2, 4].map(|i| u16::from_be_bytes([bytes[i], bytes[i + 1]]) as usize % BLOOM_BITS)
+}
+
+impl fmt::Debug for Bloom {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Bloom(0x{:x})", self.0)
+    }
+}
+
+impl redb::Value for Bloom {
+    type SelfType<'a>
+        = Bloom
+    where
+        Self: 'a;
+
+    type AsBytes<'a>
+        = &'a [u8]
+    where
+        Self: 'a;
+
+    fn fixed_width() -> Option<usize> {
+        Some(BLOOM_BYTES)
+    }
+
+    fn from_bytes<'a>(data: &'a [u8]) -> Self::SelfType<'a>
+    where
+        Self: 'a,
+    {
+        Bloom(data.try_into().unwrap())
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a Self::SelfType<'b>) -> Self::AsBytes<'a>
+    where
+        Self: 'b,
+    {
+        value.as_slice()
+    }
+
+    fn type_name() -> TypeName {
+        TypeName::new("Bloom")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Address, hash::Hash};
+
+    fn log(id: u8, topics: &[&[u8]]) -> Log {
+        Log {
+            address: Address::from([id; 20]),
+            topics: topics.iter().map(|t| Hash::hash(t)).collect(),
+            data: vec![],
+        }
+    }
+
+    #[test]
+    fn empty_bloom_contains_nothing() {
+        let bloom = Bloom::default();
+
+        assert!(bloom.is_empty());
+        assert!(!bloom.contains_input(Address::from([1; 20]).as_slice()));
+    }
+
+    #[test]
+    fn accrued_log_is_contained() {
+        let log = log(1, &[b"Transfer", b"other"]);
+
+        let mut bloom = Bloom::default();
+        bloom.accrue_log(&log);
+
+        assert!(!bloom.is_empty());
+        assert!(bloom.contains_input(log.address.as_slice()));
+        assert!(bloom.contains_input(Hash::hash(b"Transfer").as_slice()));
+        assert!(bloom.contains_input(Hash::hash(b"other").as_slice()));
+        assert!(!bloom.contains_input(Address::from([2; 20]).as_slice()));
+    }
+
+    #[test]
+    fn bloom_codec_roundtrip() {
+        let mut bloom = Bloom::default();
+        bloom.accrue_log(&log(3, &[b"topic"]));
+
+        let decoded = Bloom::decode(&mut &bloom.encode()[..]).unwrap();
+
+        assert_eq!(decoded, bloom);
+    }
+}
@@ -0,0 +1,374 @@
+use k256::ecdsa::{Signature, SigningKey, signature::Signer};
+use parity_scale_codec::{Decode, Encode};
+use redb::TypeName;
+use thiserror::Error;
+
+#[cfg(feature = "json")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bytes::FixedBytes,
+    hash::Hash,
+    multisig::{KeySignature, PublicKey},
+};
+
+const VOTE_DOMAIN: &[u8] = b"rm-reth/bft-vote";
+
+/// Maximum number of validators, votes address them by a `u8` index.
+pub const MAX_VALIDATORS: usize = 256;
+
+#[derive(Debug, Error, Clone, PartialEq)]
+pub enum FinalityError {
+    #[error("invalid validator set of {0} keys")]
+    InvalidValidatorSet(usize),
+
+    #[error("no validator at index {0}")]
+    UnknownValidator(u8),
+
+    #[error("duplicate vote of validator {0}")]
+    DuplicateVote(u8),
+
+    #[error("invalid vote signature of validator {0}")]
+    InvalidSignature(u8),
+
+    #[error("no quorum (required: {required}, got: {got})")]
+    NoQuorum { required: usize, got: usize },
+
+    #[error("certificate is for block {got}, not {expected}")]
+    BlockMismatch { expected: Hash, got: Hash },
+}
+
+/// Static set of validators agreeing on blocks, each with the same voting power.
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[derive(Debug, Encode, Decode, PartialEq, Eq, Clone)]
+pub struct ValidatorSet {
+    keys: Vec<PublicKey>,
+}
+
+impl ValidatorSet {
+    pub fn new(keys: Vec<PublicKey>) -> Result<Self, FinalityError> {
+        let mut sorted = keys.clone();
+        sorted.sort();
+        sorted.dedup();
+
+        if keys.is_empty() || keys.len() > MAX_VALIDATORS || sorted.len() != keys.len() {
+            return Err(FinalityError::InvalidValidatorSet(keys.len()));
+        }
+
+        Ok(Self { keys })
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    #[inline]
+    pub fn keys(&self) -> &[PublicKey] {
+        &self.keys
+    }
+
+    #[inline]
+    pub fn get(&self, index: u8) -> Option<&PublicKey> {
+        self.keys.get(index as usize)
+    }
+
+    #[inline]
+    pub fn index_of(&self, key: &PublicKey) -> Option<u8> {
+        self.keys.iter().position(|k| k == key).map(|i| i as u8)
+    }
+
+    /// Votes needed to decide, more than two thirds of the set.
+    #[inline]
+    pub fn quorum(&self) -> usize {
+        self.keys.len() * 2 / 3 + 1
+    }
+
+    /// Votes that include at least one honest validator when at most a third is
+    /// faulty.
+    #[inline]
+    pub fn honest_threshold(&self) -> usize {
+        self.keys.len() - self.quorum() + 1
+    }
+
+    /// Validator proposing at `round` of `height`, rotating through the set.
+    pub fn proposer(&self, height: u64, round: u32) -> u8 {
+        ((height + round as u64) % self.keys.len() as u64) as u8
+    }
+}
+
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[derive(Debug, Encode, Decode, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum VoteKind {
+    Prevote,
+    Precommit,
+}
+
+/// Signed prevote or precommit of a validator for a block, or for no block.
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[derive(Debug, Encode, Decode, PartialEq, Eq, Clone)]
+pub struct Vote {
+    pub kind: VoteKind,
+    pub height: u64,
+    pub round: u32,
+    pub block_hash: Option<Hash>,
+    pub validator: u8,
+    pub signature: FixedBytes<64>,
+}
+
+impl Vote {
+    /// Message validators sign, binding the vote to its height and round.
+    pub fn signing_hash(
+        kind: VoteKind,
+        height: u64,
+        round: u32,
+        block_hash: &Option<Hash>,
+    ) -> Hash {
+        let mut buf = VOTE_DOMAIN.to_vec();
+        (kind, height, round, block_hash).encode_to(&mut buf);
+
+        Hash::hash(&buf)
+    }
+
+    pub fn sign(
+        key: &SigningKey,
+        validator: u8,
+        kind: VoteKind,
+        height: u64,
+        round: u32,
+        block_hash: Option<Hash>,
+    ) -> Self {
+        let message = Self::signing_hash(kind, height, round, &block_hash);
+        let signature: Signature = key.sign(message.as_slice());
+
+        Self {
+            kind,
+            height,
+            round,
+            block_hash,
+            validator,
+            signature: FixedBytes(signature.to_bytes().into()),
+        }
+    }
+
+    pub fn verify(&self, validators: &ValidatorSet) -> Result<(), FinalityError> {
+        let key = validators
+            .get(self.validator)
+            .ok_or(FinalityError::UnknownValidator(self.validator))?;
+
+        let message = Self::signing_hash(self.kind, self.height, self.round, &self.block_hash);
+
+        if key.verify(&message, &self.signature) {
+            Ok(())
+        } else {
+            Err(FinalityError::InvalidSignature(self.validator))
+        }
+    }
+}
+
+/// Precommits of a quorum of validators for `block_hash`, proving the block at
+/// `height` is final.
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[derive(Debug, Encode, Decode, PartialEq, Eq, Clone)]
+pub struct CommitCertificate {
+    pub height: u64,
+    pub round: u32,
+    pub block_hash: Hash,
+    pub signatures: Vec<KeySignature>,
+}
+
+impl CommitCertificate {
+    /// Collects the precommits for `block_hash` out of `votes`, ignoring the rest.
+    pub fn from_votes<'a>(
+        height: u64,
+        round: u32,
+        block_hash: Hash,
+        votes: impl IntoIterator<Item = &'a Vote>,
+    ) -> Self {
+        let mut signatures: Vec<_> = votes
+            .into_iter()
+            .filter(|vote| {
+                vote.kind == VoteKind::Precommit
+                    && vote.height == height
+                    && vote.round == round
+                    && vote.block_hash == Some(block_hash)
+            })
+            .map(|vote| KeySignature {
+                index: vote.validator,
+                signature: vote.signature,
+            })
+            .collect();
+
+        signatures.sort_by_key(|sig| sig.index);
+        signatures.dedup_by_key(|sig| sig.index);
+
+        Self {
+            height,
+            round,
+            block_hash,
+            signatures,
+        }
+    }
+
+    /// Checks that a quorum of distinct validators precommitted `block_hash`.
+    pub fn verify(
+        &self,
+        validators: &ValidatorSet,
+        block_hash: &Hash,
+    ) -> Result<(), FinalityError> {
+        if self.block_hash != *block_hash {
+            return Err(FinalityError::BlockMismatch {
+                expected: *block_hash,
+                got: self.block_hash,
+            });
+        }
+
+        let message = Vote::signing_hash(
+            VoteKind::Precommit,
+            self.height,
+            self.round,
+            &Some(self.block_hash),
+        );
+
+        let mut seen = vec![false; validators.len()];
+
+        for KeySignature { index, signature } in &self.signatures {
+            let key = validators
+                .get(*index)
+                .ok_or(FinalityError::UnknownValidator(*index))?;
+
+            if std::mem::replace(&mut seen[*index as usize], true) {
+                return Err(FinalityError::DuplicateVote(*index));
+            }
+
+            if !key.verify(&message, signature) {
+                return Err(FinalityError::InvalidSignature(*index));
+            }
+        }
+
+        if self.signatures.len() < validators.quorum() {
+            return Err(FinalityError::NoQuorum {
+                required: validators.quorum(),
+                got: self.signatures.len(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl redb::Value for CommitCertificate {
+    type SelfType<'a>
+        = CommitCertificate
+    where
+        Self: 'a;
+
+    type AsBytes<'a>
+        = Vec<u8>
+    where
+        Self: 'a;
+
+    fn fixed_width() -> Option<usize> {
+        None
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a Self::SelfType<'b>) -> Self::AsBytes<'a>
+    where
+        Self: 'b,
+    {
+        value.encode()
+    }
+
+    fn from_bytes<'a>(data: &'a [u8]) -> Self::SelfType<'a>
+    where
+        Self: 'a,
+    {
+        let mut slice = data;
+
+        CommitCertificate::decode(&mut slice).expect("commit certificate decode failed")
+    }
+
+    fn type_name() -> TypeName {
+        TypeName::new("CommitCertificate")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(id: u8) -> SigningKey {
+        SigningKey::from_bytes(&[id; 32].into()).unwrap()
+    }
+
+    fn validators() -> ValidatorSet {
+        ValidatorSet::new(
+            (1..=4)
+                .map(|id| PublicKey::from_signing_key(&key(id)))
+                .collect(),
+        )
+        .unwrap()
+    }
+
+    fn precommit(id: u8, block_hash: Option<Hash>) -> Vote {
+        Vote::sign(&key(id), id - 1, VoteKind::Precommit, 7, 1, block_hash)
+    }
+
+    #[test]
+    fn quorum_is_more_than_two_thirds() {
+        let set = validators();
+
+        assert_eq!(set.quorum(), 3);
+        assert_eq!(set.honest_threshold(), 2);
+        assert_eq!(set.proposer(7, 1), 0);
+        assert!(ValidatorSet::new(vec![]).is_err());
+        assert!(ValidatorSet::new(vec![set.keys()[0]; 2]).is_err());
+    }
+
+    #[test]
+    fn certificate_needs_quorum_of_precommits() {
+        let set = validators();
+        let hash = Hash::hash(b"block");
+
+        let mut votes = vec![
+            precommit(1, Some(hash)),
+            precommit(2, Some(hash)),
+            precommit(3, None),
+        ];
+        assert_eq!(
+            CommitCertificate::from_votes(7, 1, hash, &votes).verify(&set, &hash),
+            Err(FinalityError::NoQuorum {
+                required: 3,
+                got: 2
+            })
+        );
+
+        votes.push(precommit(4, Some(hash)));
+        let cert = CommitCertificate::from_votes(7, 1, hash, &votes);
+        assert_eq!(cert.verify(&set, &hash), Ok(()));
+        assert!(matches!(
+            cert.verify(&set, &Hash::hash(b"other")),
+            Err(FinalityError::BlockMismatch { .. })
+        ));
+
+        // a certificate for another round does not verify
+        let mut moved = cert.clone();
+        moved.round = 2;
+        assert_eq!(
+            moved.verify(&set, &hash),
+            Err(FinalityError::InvalidSignature(0))
+        );
+
+        let mut duplicated = cert;
+        duplicated.signatures[1] = duplicated.signatures[0].clone();
+        assert_eq!(
+            duplicated.verify(&set, &hash),
+            Err(FinalityError::DuplicateVote(0))
+        );
+    }
+}
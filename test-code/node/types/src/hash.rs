@@ -80,3 +80,44 @@ impl Debug for Hash {
         f.write_str(unsafe { std::str::from_utf8_unchecked(&buf) })
     }
 }
+
+impl redb::Value for Hash {
+    type SelfType<'a>
+        = Hash
+    where
+        Self: 'a;
+
+    type AsBytes<'a>
+        = &'a [u8]
+    where
+        Self: 'a;
+
+    fn fixed_width() -> Option<usize> {
+        Some(32)
+    }
+
+    fn from_bytes<'a>(data: &'a [u8]) -> Self::SelfType<'a>
+    where
+        Self: 'a,
+    {
+        let bytes: [u8; 32] = data.try_into().unwrap();
+        bytes.into()
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a Self::SelfType<'b>) -> Self::AsBytes<'a>
+    where
+        Self: 'b,
+    {
+        value.as_slice()
+    }
+
+    fn type_name() -> redb::TypeName {
+        redb::TypeName::new("Hash")
+    }
+}
+
+impl redb::Key for Hash {
+    fn compare(data1: &[u8], data2: &[u8]) -> std::cmp::Ordering {
+        data1.cmp(data2)
+    }
+}
@@ -0,0 +1,98 @@
+use parity_scale_codec::{Decode, Encode};
+use redb::TypeName;
+
+#[cfg(feature = "json")]
+use serde::{Deserialize, Serialize};
+
+use crate::{Address, hash::Hash, int::Uint256};
+
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[derive(Debug, Encode, Decode, PartialEq, Eq, Clone)]
+pub enum HtlcStatus {
+    #[codec(index = 0)]
+    Open,
+    /// Paid to the recipient. The preimage is kept so the counterparty of a swap
+    /// can read it.
+    #[codec(index = 1)]
+    Claimed { preimage: Vec<u8> },
+    /// Returned to the sender after expiry.
+    #[codec(index = 2)]
+    Refunded,
+}
+
+/// Hash time-locked funds, identified by [`Htlc::lock_id`] of the locking
+/// transaction.
+///
+/// `amount` is paid to `recipient` by whoever reveals a preimage whose blake3 hash
+/// is `hashlock` before block `timelock`, and can be refunded to `sender` from
+/// that block on.
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[derive(Debug, Encode, Decode, PartialEq, Eq, Clone)]
+pub struct Htlc {
+    pub id: Hash,
+    pub sender: Address,
+    pub recipient: Address,
+    pub amount: Uint256,
+    pub hashlock: Hash,
+    pub timelock: u64,
+    pub status: HtlcStatus,
+}
+
+impl Htlc {
+    /// Id of the lock created by the transaction `tx_hash` at position `index` of
+    /// block `height`, so identical locking transactions still get their own lock.
+    pub fn lock_id(tx_hash: &Hash, height: u64, index: u32) -> Hash {
+        Hash::hash(&(tx_hash, height, index).encode())
+    }
+
+    #[inline]
+    pub fn is_open(&self) -> bool {
+        self.status == HtlcStatus::Open
+    }
+
+    #[inline]
+    pub fn is_expired(&self, height: u64) -> bool {
+        height >= self.timelock
+    }
+
+    #[inline]
+    pub fn unlocks(&self, preimage: &[u8]) -> bool {
+        Hash::hash(preimage) == self.hashlock
+    }
+}
+
+impl redb::Value for Htlc {
+    type SelfType<'a>
+        = Htlc
+    where
+        Self: 'a;
+
+    type AsBytes<'a>
+        = Vec<u8>
+    where
+        Self: 'a;
+
+    fn fixed_width() -> Option<usize> {
+        None
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a Self::SelfType<'b>) -> Self::AsBytes<'a>
+    where
+        Self: 'b,
+    {
+        value.encode()
+    }
+
+    fn from_bytes<'a>(data: &'a [u8]) -> Self::SelfType<'a>
+    where
+        Self: 'a,
+    {
+        let mut slice = data;
+
+        Htlc::decode(&mut slice).expect("htlc decode failed")
+    }
+
+    fn type_name() -> TypeName {
+        TypeName::new("Htlc")
+    }
+}
@@ -1,16 +1,24 @@
 pub mod address;
 pub mod api;
+pub mod asset;
 pub mod block;
+pub mod bloom;
 pub mod bytes;
 pub mod dashmap;
 pub mod error;
+pub mod finality;
 pub mod hash;
+pub mod htlc;
 pub mod init;
 pub mod int;
+pub mod log;
+pub mod multisig;
 pub mod peers;
 pub mod socket;
+pub mod staking;
 pub mod token;
 pub mod tx;
+pub mod vesting;
 
 pub use crate::address::Address;
 
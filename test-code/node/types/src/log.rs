@@ -0,0 +1,72 @@
+use parity_scale_codec::{Decode, Encode};
+
+#[cfg(feature = "json")]
+use serde::{Deserialize, Serialize};
+
+use crate::{Address, bloom::Bloom, hash::Hash};
+
+/// Structured event emitted while executing a transaction.
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[derive(Debug, Encode, Decode, PartialEq, Eq, Clone)]
+pub struct Log {
+    /// Address that emitted the event.
+    pub address: Address,
+    pub topics: Vec<Hash>,
+    pub data: Vec<u8>,
+}
+
+impl Log {
+    /// Left-pads `addr` to a 32-byte topic.
+    pub fn address_topic(addr: &Address) -> Hash {
+        let mut topic = [0u8; 32];
+        topic[12..].copy_from_slice(addr.as_slice());
+        topic.into()
+    }
+}
+
+/// Log together with its position in the chain.
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[derive(Debug, Encode, Decode, PartialEq, Eq, Clone)]
+pub struct LogEntry {
+    pub block_id: u64,
+    pub tx_hash: Hash,
+    /// Index of the log inside its block.
+    pub log_index: u32,
+    pub log: Log,
+}
+
+/// Selects logs in the inclusive block range `from..=to`.
+///
+/// `topics` are positional: `None` matches any topic at that position.
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[derive(Debug, Encode, Decode, PartialEq, Eq, Clone, Default)]
+pub struct LogFilter {
+    pub from: u64,
+    pub to: u64,
+    pub address: Option<Address>,
+    pub topics: Vec<Option<Hash>>,
+}
+
+impl LogFilter {
+    pub fn matches(&self, log: &Log) -> bool {
+        if self.address.is_some_and(|addr| addr != log.address) {
+            return false;
+        }
+
+        self.topics
+            .iter()
+            .enumerate()
+            .all(|(i, topic)| topic.is_none_or(|topic| log.topics.get(i) == Some(&topic)))
+    }
+
+    /// Returns `false` only if no log of a block with `bloom` can match.
+    pub fn may_match(&self, bloom: &Bloom) -> bool {
+        self.address
+            .is_none_or(|addr| bloom.contains_input(addr.as_slice()))
+            && self
+                .topics
+                .iter()
+                .flatten()
+                .all(|topic| bloom.contains_input(topic.as_slice()))
+    }
+}
@@ -0,0 +1,403 @@
+use k256::ecdsa::{
+    Signature, SigningKey, VerifyingKey,
+    signature::{Signer, Verifier},
+};
+use parity_scale_codec::{Decode, Encode};
+use redb::TypeName;
+use thiserror::Error;
+
+#[cfg(feature = "json")]
+use serde::{Deserialize, Serialize};
+
+use crate::{Address, bytes::FixedBytes, hash::Hash};
+
+/// Prefix of `Transaction::data` carrying a [`MultisigWitness`] before the payload.
+pub const MULTISIG_TX_MAGIC: &[u8; 4] = b"RMMS";
+
+/// Maximum number of keys of a multisig account.
+pub const MAX_MULTISIG_KEYS: usize = 16;
+
+const MULTISIG_ADDRESS_DOMAIN: &[u8] = b"rm-reth/multisig";
+
+#[derive(Debug, Error, Clone, PartialEq)]
+pub enum MultisigError {
+    #[error("invalid threshold (threshold: {threshold}, keys: {keys})")]
+    InvalidThreshold { threshold: u8, keys: usize },
+
+    #[error("invalid public key at index {0}")]
+    InvalidPublicKey(usize),
+
+    #[error("no key at index {0}")]
+    UnknownKey(u8),
+
+    #[error("duplicate signature for key {0}")]
+    DuplicateSignature(u8),
+
+    #[error("invalid signature for key {0}")]
+    InvalidSignature(u8),
+
+    #[error("not enough signatures (required: {required}, got: {got})")]
+    NotEnoughSignatures { required: u8, got: usize },
+
+    #[error("key is not part of the multisig account")]
+    NotASigner,
+
+    #[error("witness account {expected} does not match sender {sender}")]
+    SenderMismatch { expected: Address, sender: Address },
+
+    #[error("{0} is a multisig account and needs a witness")]
+    MissingWitness(Address),
+
+    #[error("invalid witness nonce (expected: {expected}, got: {got})")]
+    InvalidNonce { expected: u64, got: u64 },
+}
+
+/// Compressed SEC1 encoding of a secp256k1 public key.
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[derive(Debug, Encode, Decode, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub struct PublicKey(pub FixedBytes<33>);
+
+impl PublicKey {
+    pub fn from_signing_key(key: &SigningKey) -> Self {
+        let point = key.verifying_key().to_encoded_point(true);
+        let bytes: [u8; 33] = point.as_bytes().try_into().expect("compressed point");
+
+        Self(bytes.into())
+    }
+
+    #[inline]
+    pub fn verifying_key(&self) -> Option<VerifyingKey> {
+        VerifyingKey::from_sec1_bytes(self.0.as_slice()).ok()
+    }
+
+    /// Checks a 64 byte signature of this key over `message`.
+    pub fn verify(&self, message: &Hash, signature: &FixedBytes<64>) -> bool {
+        match (
+            self.verifying_key(),
+            Signature::from_slice(signature.as_slice()),
+        ) {
+            (Some(key), Ok(signature)) => key.verify(message.as_slice(), &signature).is_ok(),
+            _ => false,
+        }
+    }
+}
+
+/// M-of-N account: `threshold` of `keys` must sign every transaction it sends.
+///
+/// Keys are kept sorted, so the same set always yields the same address.
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[derive(Debug, Encode, Decode, PartialEq, Eq, Clone)]
+pub struct MultisigAccount {
+    pub threshold: u8,
+    pub keys: Vec<PublicKey>,
+}
+
+impl MultisigAccount {
+    pub fn new(threshold: u8, mut keys: Vec<PublicKey>) -> Result<Self, MultisigError> {
+        keys.sort();
+        keys.dedup();
+
+        let account = Self { threshold, keys };
+        account.validate()?;
+
+        Ok(account)
+    }
+
+    /// Checks the invariants [`MultisigAccount::new`] establishes, for decoded accounts.
+    pub fn validate(&self) -> Result<(), MultisigError> {
+        let keys = self.keys.len();
+
+        if self.threshold == 0 || self.threshold as usize > keys || keys > MAX_MULTISIG_KEYS {
+            return Err(MultisigError::InvalidThreshold {
+                threshold: self.threshold,
+                keys,
+            });
+        }
+
+        for (index, pair) in self.keys.windows(2).enumerate() {
+            if pair[0] >= pair[1] {
+                return Err(MultisigError::InvalidPublicKey(index + 1));
+            }
+        }
+
+        if let Some(index) = self
+            .keys
+            .iter()
+            .position(|key| key.verifying_key().is_none())
+        {
+            return Err(MultisigError::InvalidPublicKey(index));
+        }
+
+        Ok(())
+    }
+
+    /// Address the account sends from, derived from the threshold and the keys.
+    pub fn address(&self) -> Address {
+        let mut buf = MULTISIG_ADDRESS_DOMAIN.to_vec();
+        self.encode_to(&mut buf);
+
+        let hash = Hash::hash(&buf);
+        let bytes: [u8; 20] = hash.as_slice()[12..].try_into().unwrap();
+
+        bytes.into()
+    }
+
+    #[inline]
+    pub fn index_of(&self, key: &PublicKey) -> Option<u8> {
+        self.keys.iter().position(|k| k == key).map(|i| i as u8)
+    }
+}
+
+/// Signature of the key at `index` of a [`MultisigAccount`].
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[derive(Debug, Encode, Decode, PartialEq, Eq, Clone)]
+pub struct KeySignature {
+    pub index: u8,
+    pub signature: FixedBytes<64>,
+}
+
+/// Registered multisig account and the nonce its next transaction has to use.
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[derive(Debug, Encode, Decode, PartialEq, Eq, Clone)]
+pub struct MultisigRecord {
+    pub account: MultisigAccount,
+    pub nonce: u64,
+}
+
+impl MultisigRecord {
+    #[inline]
+    pub fn address(&self) -> Address {
+        self.account.address()
+    }
+}
+
+/// Account, nonce and collected signatures of a multisig transaction.
+///
+/// The signatures are over `Transaction::signing_hash(nonce)`, so a witness is only
+/// valid for the transaction the account sends at that nonce.
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[derive(Debug, Encode, Decode, PartialEq, Eq, Clone)]
+pub struct MultisigWitness {
+    pub account: MultisigAccount,
+    pub nonce: u64,
+    pub signatures: Vec<KeySignature>,
+}
+
+impl MultisigWitness {
+    pub fn new(account: MultisigAccount, nonce: u64) -> Self {
+        Self {
+            account,
+            nonce,
+            signatures: vec![],
+        }
+    }
+
+    /// Adds, or replaces, the signature of `key` over `message`.
+    pub fn cosign(&mut self, key: &SigningKey, message: &Hash) -> Result<(), MultisigError> {
+        let index = self
+            .account
+            .index_of(&PublicKey::from_signing_key(key))
+            .ok_or(MultisigError::NotASigner)?;
+
+        let signature: Signature = key.sign(message.as_slice());
+        let signature = KeySignature {
+            index,
+            signature: FixedBytes(signature.to_bytes().into()),
+        };
+
+        self.signatures.retain(|sig| sig.index != index);
+        self.signatures.push(signature);
+        self.signatures.sort_by_key(|sig| sig.index);
+
+        Ok(())
+    }
+
+    /// Returns whether enough signatures were collected, without verifying them.
+    #[inline]
+    pub fn is_complete(&self) -> bool {
+        self.signatures.len() >= self.account.threshold as usize
+    }
+
+    /// Verifies that at least `threshold` distinct keys signed `message`.
+    ///
+    /// Every attached signature has to be valid, not only the first `threshold`.
+    pub fn verify(&self, message: &Hash) -> Result<(), MultisigError> {
+        self.account.validate()?;
+
+        let mut seen = [false; MAX_MULTISIG_KEYS];
+
+        for KeySignature { index, signature } in &self.signatures {
+            let key = self
+                .account
+                .keys
+                .get(*index as usize)
+                .ok_or(MultisigError::UnknownKey(*index))?;
+
+            if std::mem::replace(&mut seen[*index as usize], true) {
+                return Err(MultisigError::DuplicateSignature(*index));
+            }
+
+            if !key.verify(message, signature) {
+                return Err(MultisigError::InvalidSignature(*index));
+            }
+        }
+
+        if !self.is_complete() {
+            return Err(MultisigError::NotEnoughSignatures {
+                required: self.account.threshold,
+                got: self.signatures.len(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl redb::Value for MultisigAccount {
+    type SelfType<'a>
+        = MultisigAccount
+    where
+        Self: 'a;
+
+    type AsBytes<'a>
+        = Vec<u8>
+    where
+        Self: 'a;
+
+    fn fixed_width() -> Option<usize> {
+        None
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a Self::SelfType<'b>) -> Self::AsBytes<'a>
+    where
+        Self: 'b,
+    {
+        value.encode()
+    }
+
+    fn from_bytes<'a>(data: &'a [u8]) -> Self::SelfType<'a>
+    where
+        Self: 'a,
+    {
+        let mut slice = data;
+
+        MultisigAccount::decode(&mut slice).expect("multisig account decode failed")
+    }
+
+    fn type_name() -> TypeName {
+        TypeName::new("MultisigAccount")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(id: u8) -> SigningKey {
+        SigningKey::from_bytes(&[id; 32].into()).unwrap()
+    }
+
+    fn account(threshold: u8) -> MultisigAccount {
+        let keys = (1..=3)
+            .map(|id| PublicKey::from_signing_key(&key(id)))
+            .collect();
+        MultisigAccount::new(threshold, keys).unwrap()
+    }
+
+    #[test]
+    fn address_ignores_key_order() {
+        let mut keys: Vec<_> = (1..=3)
+            .map(|id| PublicKey::from_signing_key(&key(id)))
+            .collect();
+        keys.reverse();
+
+        let reversed = MultisigAccount::new(2, keys).unwrap();
+
+        assert_eq!(reversed.address(), account(2).address());
+        assert_ne!(account(2).address(), account(3).address());
+        assert!(matches!(
+            MultisigAccount::new(4, reversed.keys),
+            Err(MultisigError::InvalidThreshold { .. })
+        ));
+    }
+
+    #[test]
+    fn verifies_threshold_of_signatures() {
+        let message = Hash::hash(b"tx");
+        let mut witness = MultisigWitness::new(account(2), 0);
+
+        witness.cosign(&key(1), &message).unwrap();
+        assert_eq!(
+            witness.verify(&message),
+            Err(MultisigError::NotEnoughSignatures {
+                required: 2,
+                got: 1
+            })
+        );
+
+        // signing twice with the same key replaces the signature
+        witness.cosign(&key(1), &message).unwrap();
+        witness.cosign(&key(3), &message).unwrap();
+        assert_eq!(witness.signatures.len(), 2);
+        assert_eq!(witness.verify(&message), Ok(()));
+
+        assert!(matches!(
+            witness.verify(&Hash::hash(b"other")),
+            Err(MultisigError::InvalidSignature(_))
+        ));
+        assert_eq!(
+            witness.cosign(&key(4), &message),
+            Err(MultisigError::NotASigner)
+        );
+    }
+
+    #[test]
+    fn witness_wraps_transaction_payload() {
+        use crate::{
+            int::Uint256,
+            tx::{kind::TxKind, transaction::Transaction},
+        };
+
+        let account = account(2);
+        let tx = Transaction::with_kind(
+            account.address(),
+            [9; 20].into(),
+            Uint256::from(5),
+            TxKind::Burn,
+        );
+        let message = tx.signing_hash(3).unwrap();
+        assert_ne!(message, tx.hash());
+        assert_ne!(message, tx.signing_hash(4).unwrap());
+
+        let mut witness = MultisigWitness::new(account, 3);
+        witness.cosign(&key(2), &message).unwrap();
+
+        let signed = tx.clone().with_witness(witness.clone()).unwrap();
+        assert_eq!(signed.kind().unwrap(), TxKind::Burn);
+        assert_eq!(signed.witness().unwrap(), Some(witness.clone()));
+        assert_eq!(signed.signing_hash(3).unwrap(), message);
+        assert!(!signed.is_transfer());
+
+        witness.cosign(&key(1), &message).unwrap();
+        let signed = signed.with_witness(witness).unwrap();
+        assert_eq!(signed.witness().unwrap().unwrap().signatures.len(), 2);
+        assert_eq!(signed.split_witness().unwrap().1, tx.data.as_slice());
+    }
+
+    #[test]
+    fn rejects_duplicate_and_unknown_indices() {
+        let message = Hash::hash(b"tx");
+        let mut witness = MultisigWitness::new(account(2), 0);
+        witness.cosign(&key(1), &message).unwrap();
+
+        let mut duplicated = witness.clone();
+        duplicated.signatures.push(duplicated.signatures[0].clone());
+        assert!(matches!(
+            duplicated.verify(&message),
+            Err(MultisigError::DuplicateSignature(_))
+        ));
+
+        witness.signatures[0].index = 7;
+        assert_eq!(witness.verify(&message), Err(MultisigError::UnknownKey(7)));
+    }
+}
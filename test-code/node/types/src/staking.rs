@@ -0,0 +1,255 @@
+use k256::ecdsa::{Signature, SigningKey, signature::Signer};
+use parity_scale_codec::{Decode, Encode};
+use redb::TypeName;
+
+#[cfg(feature = "json")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Address,
+    bytes::FixedBytes,
+    finality::{FinalityError, ValidatorSet},
+    hash::Hash,
+    int::Uint256,
+    multisig::PublicKey,
+};
+
+const POSSESSION_DOMAIN: &[u8] = b"rm-reth/stake-key";
+
+/// Message a staking key signs to prove it is held by the validator `address`,
+/// so nobody can register the key of someone else.
+pub fn possession_message(address: &Address) -> Hash {
+    Hash::hash(&(POSSESSION_DOMAIN, address).encode())
+}
+
+/// Proof of possession of `key` for staking as `address`.
+pub fn prove_possession(key: &SigningKey, address: &Address) -> FixedBytes<64> {
+    let signature: Signature = key.sign(possession_message(address).as_slice());
+
+    FixedBytes(signature.to_bytes().into())
+}
+
+/// Key of the registry index from staking keys to the validators using them.
+pub fn key_id(public_key: &PublicKey) -> Hash {
+    Hash::hash(public_key.0.as_slice())
+}
+
+/// Unstaked tokens waiting for the unbonding period to pass.
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[derive(Debug, Encode, Decode, PartialEq, Eq, Clone)]
+pub struct Unbonding {
+    pub amount: Uint256,
+    /// First height the tokens can be withdrawn at.
+    pub release_height: u64,
+}
+
+/// Entry of the validator registry: the bonded stake of `address` and the key it
+/// signs consensus messages with.
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[derive(Debug, Encode, Decode, PartialEq, Eq, Clone)]
+pub struct ValidatorRecord {
+    pub address: Address,
+    pub public_key: PublicKey,
+    pub stake: Uint256,
+    pub unbonding: Vec<Unbonding>,
+}
+
+impl ValidatorRecord {
+    pub fn new(address: Address, public_key: PublicKey) -> Self {
+        Self {
+            address,
+            public_key,
+            stake: Uint256::zero(),
+            unbonding: vec![],
+        }
+    }
+
+    /// Unbonded amount that can be withdrawn at `height`.
+    pub fn withdrawable(&self, height: u64) -> Uint256 {
+        self.unbonding
+            .iter()
+            .filter(|entry| entry.release_height <= height)
+            .fold(Uint256::zero(), |total, entry| {
+                total.saturating_add(entry.amount.clone())
+            })
+    }
+}
+
+/// Member of the validator set of an epoch.
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[derive(Debug, Encode, Decode, PartialEq, Eq, Clone)]
+pub struct StakedValidator {
+    pub address: Address,
+    pub public_key: PublicKey,
+    pub stake: Uint256,
+}
+
+/// Validators of `epoch`, ordered by decreasing stake.
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[derive(Debug, Encode, Decode, PartialEq, Eq, Clone)]
+pub struct EpochValidators {
+    pub epoch: u64,
+    pub validators: Vec<StakedValidator>,
+}
+
+impl EpochValidators {
+    /// Picks the `max_validators` largest stakes of at least `min_stake`, ties going
+    /// to the lower address.
+    pub fn select<'a>(
+        epoch: u64,
+        records: impl IntoIterator<Item = &'a ValidatorRecord>,
+        max_validators: usize,
+        min_stake: &Uint256,
+    ) -> Self {
+        let mut validators: Vec<_> = records
+            .into_iter()
+            .filter(|record| !record.stake.is_zero() && record.stake >= *min_stake)
+            .map(|record| StakedValidator {
+                address: record.address,
+                public_key: record.public_key,
+                stake: record.stake.clone(),
+            })
+            .collect();
+
+        validators.sort_by(|a, b| b.stake.cmp(&a.stake).then(a.address.cmp(&b.address)));
+        validators.truncate(max_validators);
+
+        Self { epoch, validators }
+    }
+
+    /// Keys of the validators, as the consensus engines take them.
+    pub fn validator_set(&self) -> Result<ValidatorSet, FinalityError> {
+        ValidatorSet::new(
+            self.validators
+                .iter()
+                .map(|validator| validator.public_key)
+                .collect(),
+        )
+    }
+}
+
+impl redb::Value for ValidatorRecord {
+    type SelfType<'a>
+        = ValidatorRecord
+    where
+        Self: 'a;
+
+    type AsBytes<'a>
+        = Vec<u8>
+    where
+        Self: 'a;
+
+    fn fixed_width() -> Option<usize> {
+        None
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a Self::SelfType<'b>) -> Self::AsBytes<'a>
+    where
+        Self: 'b,
+    {
+        value.encode()
+    }
+
+    fn from_bytes<'a>(data: &'a [u8]) -> Self::SelfType<'a>
+    where
+        Self: 'a,
+    {
+        let mut slice = data;
+
+        ValidatorRecord::decode(&mut slice).expect("validator record decode failed")
+    }
+
+    fn type_name() -> TypeName {
+        TypeName::new("ValidatorRecord")
+    }
+}
+
+impl redb::Value for EpochValidators {
+    type SelfType<'a>
+        = EpochValidators
+    where
+        Self: 'a;
+
+    type AsBytes<'a>
+        = Vec<u8>
+    where
+        Self: 'a;
+
+    fn fixed_width() -> Option<usize> {
+        None
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a Self::SelfType<'b>) -> Self::AsBytes<'a>
+    where
+        Self: 'b,
+    {
+        value.encode()
+    }
+
+    fn from_bytes<'a>(data: &'a [u8]) -> Self::SelfType<'a>
+    where
+        Self: 'a,
+    {
+        let mut slice = data;
+
+        EpochValidators::decode(&mut slice).expect("epoch validators decode failed")
+    }
+
+    fn type_name() -> TypeName {
+        TypeName::new("EpochValidators")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(id: u8, stake: u64) -> ValidatorRecord {
+        let mut key = [2; 33];
+        key[1] = id;
+
+        ValidatorRecord {
+            stake: Uint256::from(stake),
+            ..ValidatorRecord::new([id; 20].into(), PublicKey(key.into()))
+        }
+    }
+
+    #[test]
+    fn selects_largest_stakes() {
+        let records = [
+            record(1, 50),
+            record(2, 200),
+            record(3, 5),
+            record(4, 50),
+            record(5, 0),
+        ];
+
+        let set = EpochValidators::select(3, &records, 3, &Uint256::from(10));
+        let picked: Vec<_> = set.validators.iter().map(|v| v.address).collect();
+
+        assert_eq!(set.epoch, 3);
+        assert_eq!(picked, vec![[2; 20].into(), [1; 20].into(), [4; 20].into()]);
+
+        let set = EpochValidators::select(3, &records, 10, &Uint256::zero());
+        assert_eq!(set.validators.len(), 4);
+    }
+
+    #[test]
+    fn only_released_unbonding_is_withdrawable() {
+        let mut record = record(1, 0);
+        record.unbonding = vec![
+            Unbonding {
+                amount: Uint256::from(10),
+                release_height: 5,
+            },
+            Unbonding {
+                amount: Uint256::from(20),
+                release_height: 8,
+            },
+        ];
+
+        assert_eq!(record.withdrawable(4), Uint256::zero());
+        assert_eq!(record.withdrawable(5), Uint256::from(10));
+        assert_eq!(record.withdrawable(8), Uint256::from(30));
+    }
+}
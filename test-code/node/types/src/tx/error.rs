@@ -20,6 +20,9 @@ pub enum TransactionError {
     #[error("TxPool reached limit")]
     TxPoolReachedLimit,
 
+    #[error("unsupported transaction kind version: ({0:?})")]
+    UnsupportedKindVersion(Option<u8>),
+
     #[error("unknown error: ({0})")]
     Unknown(String),
 }
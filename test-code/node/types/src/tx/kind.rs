@@ -0,0 +1,246 @@
+use parity_scale_codec::{Decode, DecodeAll, Encode};
+
+#[cfg(feature = "json")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Address,
+    asset::AssetOp,
+    bytes::FixedBytes,
+    hash::Hash,
+    int::Uint256,
+    multisig::{MultisigAccount, PublicKey},
+    tx::error::TransactionError,
+};
+
+/// Prefix of `Transaction::data` marking a typed transaction.
+pub const TX_KIND_MAGIC: &[u8; 4] = b"RMTX";
+
+/// Version of the [`TxKind`] encoding written after [`TX_KIND_MAGIC`].
+pub const TX_KIND_VERSION: u8 = 1;
+
+/// One payment of a [`TxKind::MultiTransfer`].
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[derive(Debug, Encode, Decode, PartialEq, Eq, Clone)]
+pub struct TransferLeg {
+    pub to: Address,
+    pub amount: Uint256,
+}
+
+/// What a transaction does, stored in `Transaction::data`.
+///
+/// The payload is `TX_KIND_MAGIC ++ version ++ SCALE(TxKind)`. Data without the
+/// magic decodes as a plain [`TxKind::Transfer`], so transactions encoded before
+/// kinds existed keep their meaning.
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[derive(Debug, Encode, Decode, PartialEq, Eq, Clone)]
+pub enum TxKind {
+    /// Moves `amount` from `from` to `to`.
+    #[codec(index = 0)]
+    Transfer,
+    /// Creates `amount` native tokens for `to`, only allowed for authorised minters.
+    #[codec(index = 1)]
+    Mint,
+    /// Destroys `amount` native tokens held by `from`.
+    #[codec(index = 2)]
+    Burn,
+    #[codec(index = 3)]
+    ContractDeploy { code: Vec<u8> },
+    /// Calls the contract at `to`, sending it `amount`.
+    #[codec(index = 4)]
+    ContractCall { input: Vec<u8> },
+    /// Pays every recipient from `from`, all or nothing.
+    #[codec(index = 5)]
+    MultiTransfer { transfers: Vec<TransferLeg> },
+    #[codec(index = 6)]
+    Asset(AssetOp),
+    /// Locks `amount` for `recipient` under `hashlock` until block `timelock`, as
+    /// the lock [`crate::htlc::Htlc::lock_id`] of the transaction.
+    #[codec(index = 7)]
+    HtlcLock {
+        recipient: Address,
+        hashlock: Hash,
+        timelock: u64,
+    },
+    /// Pays the lock `id` to its recipient.
+    #[codec(index = 8)]
+    HtlcClaim { id: Hash, preimage: Vec<u8> },
+    /// Returns the expired lock `id` to its sender.
+    #[codec(index = 9)]
+    HtlcRefund { id: Hash },
+    /// Offers `amount` to `beneficiary`, released linearly from block `start` over
+    /// `duration` blocks, with nothing released during the first `cliff` blocks.
+    /// The amount is held back until the beneficiary accepts the offer.
+    #[codec(index = 10)]
+    VestingGrant {
+        beneficiary: Address,
+        start: u64,
+        cliff: u64,
+        duration: u64,
+    },
+    /// Bonds `amount` to the validator `from`, registering it with `public_key` on
+    /// its first stake. `proof` is the signature of the key over
+    /// `staking::possession_message(from)`.
+    #[codec(index = 11)]
+    Stake {
+        public_key: PublicKey,
+        proof: FixedBytes<64>,
+    },
+    /// Starts unbonding `amount` of the stake of `from`.
+    #[codec(index = 12)]
+    Unstake,
+    /// Returns the stake of `from` whose unbonding period is over.
+    #[codec(index = 13)]
+    WithdrawStake,
+    /// Registers `account` at its address and moves `amount` into it.
+    #[codec(index = 14)]
+    MultisigCreate { account: MultisigAccount },
+    /// Accepts the vesting offer `id` made to `from`, paying it out under its
+    /// schedule.
+    #[codec(index = 15)]
+    VestingAccept { id: Hash },
+    /// Returns the vesting offer `id` made by `from` while it is still pending.
+    #[codec(index = 16)]
+    VestingCancel { id: Hash },
+}
+
+impl TxKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TxKind::Transfer => "transfer",
+            TxKind::Mint => "mint",
+            TxKind::Burn => "burn",
+            TxKind::ContractDeploy { .. } => "contract_deploy",
+            TxKind::ContractCall { .. } => "contract_call",
+            TxKind::MultiTransfer { .. } => "multi_transfer",
+            TxKind::Asset(_) => "asset",
+            TxKind::HtlcLock { .. } => "htlc_lock",
+            TxKind::HtlcClaim { .. } => "htlc_claim",
+            TxKind::HtlcRefund { .. } => "htlc_refund",
+            TxKind::VestingGrant { .. } => "vesting_grant",
+            TxKind::Stake { .. } => "stake",
+            TxKind::Unstake => "unstake",
+            TxKind::WithdrawStake => "withdraw_stake",
+            TxKind::MultisigCreate { .. } => "multisig_create",
+            TxKind::VestingAccept { .. } => "vesting_accept",
+            TxKind::VestingCancel { .. } => "vesting_cancel",
+        }
+    }
+
+    /// Encodes `self` as a `Transaction::data` payload.
+    pub fn to_payload(&self) -> Vec<u8> {
+        let mut data = TX_KIND_MAGIC.to_vec();
+        data.push(TX_KIND_VERSION);
+        self.encode_to(&mut data);
+
+        data
+    }
+
+    /// Decodes a `Transaction::data` payload.
+    pub fn from_payload(data: &[u8]) -> Result<Self, TransactionError> {
+        if let Some(rest) = data.strip_prefix(TX_KIND_MAGIC.as_slice()) {
+            let (&version, mut kind) = rest
+                .split_first()
+                .ok_or(TransactionError::UnsupportedKindVersion(None))?;
+
+            if version != TX_KIND_VERSION {
+                return Err(TransactionError::UnsupportedKindVersion(Some(version)));
+            }
+
+            return Ok(TxKind::decode_all(&mut kind)?);
+        }
+
+        Ok(TxKind::Transfer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{asset::AssetId, tx::transaction::Transaction};
+
+    fn addr(id: u8) -> Address {
+        [id; 20].into()
+    }
+
+    #[test]
+    fn kinds_roundtrip() {
+        let kinds = [
+            TxKind::Transfer,
+            TxKind::Mint,
+            TxKind::Burn,
+            TxKind::ContractDeploy { code: vec![1, 2] },
+            TxKind::ContractCall { input: vec![3] },
+            TxKind::MultiTransfer {
+                transfers: vec![TransferLeg {
+                    to: addr(1),
+                    amount: Uint256::from(5),
+                }],
+            },
+            TxKind::Asset(AssetOp::Burn {
+                asset: AssetId::default(),
+                amount: Uint256::from(1),
+            }),
+            TxKind::HtlcLock {
+                recipient: addr(2),
+                hashlock: Hash::hash(b"secret"),
+                timelock: 10,
+            },
+            TxKind::HtlcClaim {
+                id: Hash::empty(),
+                preimage: b"secret".to_vec(),
+            },
+            TxKind::HtlcRefund { id: Hash::empty() },
+            TxKind::VestingGrant {
+                beneficiary: addr(3),
+                start: 1,
+                cliff: 2,
+                duration: 3,
+            },
+            TxKind::Stake {
+                public_key: PublicKey([2; 33].into()),
+                proof: [3; 64].into(),
+            },
+            TxKind::Unstake,
+            TxKind::WithdrawStake,
+            TxKind::MultisigCreate {
+                account: MultisigAccount {
+                    threshold: 1,
+                    keys: vec![PublicKey([2; 33].into())],
+                },
+            },
+            TxKind::VestingAccept { id: Hash::empty() },
+            TxKind::VestingCancel { id: Hash::empty() },
+        ];
+
+        for kind in kinds {
+            let tx = Transaction::with_kind(addr(1), addr(2), Uint256::zero(), kind.clone());
+            assert_eq!(tx.kind().unwrap(), kind);
+        }
+    }
+
+    #[test]
+    fn untyped_payloads_are_transfers() {
+        let memo = Transaction::new(addr(1), addr(2), Uint256::from(1), vec![0xde, 0xad]);
+        assert_eq!(memo.kind().unwrap(), TxKind::Transfer);
+    }
+
+    #[test]
+    fn rejects_unknown_version_and_trailing_bytes() {
+        let mut data = TxKind::Burn.to_payload();
+        data[TX_KIND_MAGIC.len()] = TX_KIND_VERSION + 1;
+
+        assert!(matches!(
+            TxKind::from_payload(&data),
+            Err(TransactionError::UnsupportedKindVersion(Some(2)))
+        ));
+
+        let mut data = TxKind::Burn.to_payload();
+        data.push(0);
+
+        assert!(matches!(
+            TxKind::from_payload(&data),
+            Err(TransactionError::ScaleError(_))
+        ));
+    }
+}
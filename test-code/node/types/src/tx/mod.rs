@@ -1,7 +1,9 @@
 pub mod error;
+pub mod kind;
 // pub mod pool;
 pub mod pool_helper;
 pub mod queue;
+pub mod receipt;
 pub mod transaction;
 
 // #[cfg(test)]
@@ -1,4 +1,4 @@
-use config::get_config;
+use config::{Config, get_config};
 use parity_scale_codec::{Decode, Encode};
 
 #[cfg(feature = "json")]
@@ -47,8 +47,16 @@ impl TxPoolHelper {
         self.tx_count += 1;
         self.pool_size += tx_size;
 
-        let result = (config.tx_max_size - self.pool_size) < config.min_tx_threshold;
+        Ok(threshold_reached(&config, self.pool_size))
+    }
 
-        Ok(result)
+    /// Whether `pool_size` bytes of transactions are close enough to the maximum
+    /// pool size to be sealed into a block.
+    pub fn is_ready(pool_size: u64) -> bool {
+        threshold_reached(&get_config(), pool_size)
     }
 }
+
+fn threshold_reached(config: &Config, pool_size: u64) -> bool {
+    config.tx_max_size.saturating_sub(pool_size) < config.min_tx_threshold
+}
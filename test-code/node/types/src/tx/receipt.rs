@@ -0,0 +1,18 @@
+use parity_scale_codec::{Decode, Encode};
+
+#[cfg(feature = "json")]
+use serde::{Deserialize, Serialize};
+
+use crate::{hash::Hash, log::Log, tx::kind::TransferLeg};
+
+/// Result of executing a transaction inside a block.
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[derive(Debug, Encode, Decode, PartialEq, Eq, Clone)]
+pub struct Receipt {
+    pub tx_hash: Hash,
+    pub success: bool,
+    pub gas_used: u64,
+    pub logs: Vec<Log>,
+    /// Legs paid by a successful multi-transfer, empty otherwise.
+    pub legs: Vec<TransferLeg>,
+}
@@ -1,9 +1,23 @@
-use crate::{Address, int::Uint256};
+use crate::{
+    Address,
+    asset::AssetOp,
+    hash::Hash,
+    int::Uint256,
+    multisig::{MULTISIG_TX_MAGIC, MultisigAccount, MultisigWitness},
+    tx::{
+        error::TransactionError,
+        kind::{TransferLeg, TxKind},
+    },
+};
 use parity_scale_codec::{Decode, Encode};
 
 #[cfg(feature = "json")]
 use serde::{Deserialize, Serialize};
 
+/// Domain of [`Transaction::signing_hash`], keeping its messages apart from other
+/// signed data.
+const TX_SIGNING_DOMAIN: &[u8] = b"rm-reth/tx";
+
 #[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
 #[derive(Debug, Encode, Decode, PartialEq, Clone)]
 pub struct Transaction {
@@ -23,6 +37,106 @@ impl Transaction {
         }
     }
 
+    /// Builds a typed transaction, see [`TxKind`].
+    pub fn with_kind(from: Address, to: Address, amount: Uint256, kind: TxKind) -> Self {
+        Self::new(from, to, amount, kind.to_payload())
+    }
+
+    /// Builds an asset transaction. No native amount is moved.
+    pub fn asset(from: Address, op: AssetOp) -> Self {
+        let to = match &op {
+            AssetOp::Mint { to, .. } | AssetOp::Transfer { to, .. } => *to,
+            AssetOp::Issue { .. } | AssetOp::Burn { .. } => from,
+        };
+
+        Self::with_kind(from, to, Uint256::zero(), TxKind::Asset(op))
+    }
+
+    /// Registers `account` on chain and moves `amount` into it. Once registered,
+    /// the account can only send transactions carrying a valid witness.
+    pub fn multisig_create(from: Address, account: MultisigAccount, amount: Uint256) -> Self {
+        let to = account.address();
+
+        Self::with_kind(from, to, amount, TxKind::MultisigCreate { account })
+    }
+
+    /// Builds a batch paying every leg from `from`. No amount is set on the
+    /// transaction itself.
+    pub fn multi_transfer(from: Address, transfers: Vec<TransferLeg>) -> Self {
+        Self::with_kind(
+            from,
+            from,
+            Uint256::zero(),
+            TxKind::MultiTransfer { transfers },
+        )
+    }
+
+    #[inline]
+    pub fn kind(&self) -> Result<TxKind, TransactionError> {
+        TxKind::from_payload(self.split_witness()?.1)
+    }
+
+    /// Returns whether `self` is a plain native transfer without a multisig witness.
+    #[inline]
+    pub fn is_transfer(&self) -> bool {
+        !self.data.starts_with(MULTISIG_TX_MAGIC) && matches!(self.kind(), Ok(TxKind::Transfer))
+    }
+
+    /// Splits `data` into the multisig witness, if any, and the [`TxKind`] payload.
+    ///
+    /// The witness is stored as `MULTISIG_TX_MAGIC ++ SCALE(MultisigWitness)`
+    /// in front of the payload.
+    pub fn split_witness(&self) -> Result<(Option<MultisigWitness>, &[u8]), TransactionError> {
+        match self.data.strip_prefix(MULTISIG_TX_MAGIC.as_slice()) {
+            Some(mut rest) => {
+                let witness = MultisigWitness::decode(&mut rest)?;
+                Ok((Some(witness), rest))
+            }
+            None => Ok((None, &self.data)),
+        }
+    }
+
+    #[inline]
+    pub fn witness(&self) -> Result<Option<MultisigWitness>, TransactionError> {
+        Ok(self.split_witness()?.0)
+    }
+
+    /// Replaces the multisig witness of `self`, keeping its payload.
+    pub fn with_witness(self, witness: MultisigWitness) -> Result<Self, TransactionError> {
+        let payload = self.split_witness()?.1;
+
+        let mut data = MULTISIG_TX_MAGIC.to_vec();
+        witness.encode_to(&mut data);
+        data.extend_from_slice(payload);
+
+        Ok(Self { data, ..self })
+    }
+
+    /// Hash the multisig keys sign: `self` without its witness, sent at `nonce`.
+    ///
+    /// The nonce of the sender advances with every transaction it sends, so a
+    /// signed witness cannot be replayed.
+    pub fn signing_hash(&self, nonce: u64) -> Result<Hash, TransactionError> {
+        let payload = self.split_witness()?.1;
+
+        Ok(Hash::hash(
+            &(
+                TX_SIGNING_DOMAIN,
+                &self.from,
+                &self.to,
+                &self.amount,
+                nonce,
+                payload,
+            )
+                .encode(),
+        ))
+    }
+
+    #[inline]
+    pub fn hash(&self) -> Hash {
+        Hash::hash(&self.encode())
+    }
+
     #[inline]
     pub fn size(&self) -> usize {
         self.data.len()
@@ -0,0 +1,222 @@
+use parity_scale_codec::{Decode, Encode};
+use redb::TypeName;
+
+#[cfg(feature = "json")]
+use serde::{Deserialize, Serialize};
+
+use crate::{Address, hash::Hash, int::Uint256};
+
+/// Linear release of `total` to `beneficiary`, in block heights.
+///
+/// Nothing is vested before `start + cliff`, everything from `start + duration` on,
+/// and the vested amount grows linearly in between.
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[derive(Debug, Encode, Decode, PartialEq, Eq, Clone)]
+pub struct VestingSchedule {
+    pub beneficiary: Address,
+    pub grantor: Address,
+    pub start: u64,
+    pub cliff: u64,
+    pub duration: u64,
+    pub total: Uint256,
+}
+
+impl VestingSchedule {
+    pub fn vested(&self, height: u64) -> Uint256 {
+        let elapsed = height.saturating_sub(self.start);
+
+        if elapsed < self.cliff {
+            return Uint256::zero();
+        }
+
+        if elapsed >= self.duration {
+            return self.total.clone();
+        }
+
+        let elapsed = Uint256::from(elapsed);
+        let duration = Uint256::from(self.duration);
+
+        match self.total.clone().checked_mul(elapsed.clone()) {
+            Some(amount) => amount / duration,
+            // rounds down a bit more, only reachable for totals close to the maximum
+            None => self.total.clone() / duration * elapsed,
+        }
+    }
+
+    #[inline]
+    pub fn locked(&self, height: u64) -> Uint256 {
+        self.total.clone().saturating_sub(self.vested(height))
+    }
+}
+
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[derive(Debug, Encode, Decode, PartialEq, Eq, Clone)]
+pub enum VestingOfferStatus {
+    #[codec(index = 0)]
+    Pending,
+    /// Paid to the beneficiary, whose balance follows the schedule from then on.
+    #[codec(index = 1)]
+    Accepted,
+    /// Returned to the grantor before the beneficiary accepted it.
+    #[codec(index = 2)]
+    Cancelled,
+}
+
+/// Vesting schedule offered by its grantor, identified by the hash of the granting
+/// transaction.
+///
+/// The total is held back from the grantor until the beneficiary accepts the
+/// schedule, so nobody gets a schedule without consenting to it.
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[derive(Debug, Encode, Decode, PartialEq, Eq, Clone)]
+pub struct VestingOffer {
+    pub id: Hash,
+    pub schedule: VestingSchedule,
+    pub status: VestingOfferStatus,
+}
+
+impl VestingOffer {
+    #[inline]
+    pub fn is_pending(&self) -> bool {
+        self.status == VestingOfferStatus::Pending
+    }
+}
+
+/// Native balance of an account split by what its vesting schedule allows to spend.
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[derive(Debug, Encode, Decode, PartialEq, Eq, Clone)]
+pub struct VestingStatus {
+    pub height: u64,
+    pub balance: Uint256,
+    pub locked: Uint256,
+    pub spendable: Uint256,
+    pub schedule: Option<VestingSchedule>,
+}
+
+impl VestingStatus {
+    pub fn new(height: u64, balance: Uint256, schedule: Option<VestingSchedule>) -> Self {
+        let locked = schedule
+            .as_ref()
+            .map(|schedule| schedule.locked(height))
+            .unwrap_or_default();
+
+        Self {
+            height,
+            spendable: balance.clone().saturating_sub(locked.clone()),
+            balance,
+            locked,
+            schedule,
+        }
+    }
+}
+
+impl redb::Value for VestingSchedule {
+    type SelfType<'a>
+        = VestingSchedule
+    where
+        Self: 'a;
+
+    type AsBytes<'a>
+        = Vec<u8>
+    where
+        Self: 'a;
+
+    fn fixed_width() -> Option<usize> {
+        None
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a Self::SelfType<'b>) -> Self::AsBytes<'a>
+    where
+        Self: 'b,
+    {
+        value.encode()
+    }
+
+    fn from_bytes<'a>(data: &'a [u8]) -> Self::SelfType<'a>
+    where
+        Self: 'a,
+    {
+        let mut slice = data;
+
+        VestingSchedule::decode(&mut slice).expect("vesting schedule decode failed")
+    }
+
+    fn type_name() -> TypeName {
+        TypeName::new("VestingSchedule")
+    }
+}
+
+impl redb::Value for VestingOffer {
+    type SelfType<'a>
+        = VestingOffer
+    where
+        Self: 'a;
+
+    type AsBytes<'a>
+        = Vec<u8>
+    where
+        Self: 'a;
+
+    fn fixed_width() -> Option<usize> {
+        None
+    }
+
+    fn as_bytes<'a, 'b: 'a>(value: &'a Self::SelfType<'b>) -> Self::AsBytes<'a>
+    where
+        Self: 'b,
+    {
+        value.encode()
+    }
+
+    fn from_bytes<'a>(data: &'a [u8]) -> Self::SelfType<'a>
+    where
+        Self: 'a,
+    {
+        let mut slice = data;
+
+        VestingOffer::decode(&mut slice).expect("vesting offer decode failed")
+    }
+
+    fn type_name() -> TypeName {
+        TypeName::new("VestingOffer")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule() -> VestingSchedule {
+        VestingSchedule {
+            beneficiary: [1; 20].into(),
+            grantor: [2; 20].into(),
+            start: 100,
+            cliff: 10,
+            duration: 40,
+            total: Uint256::from(1000),
+        }
+    }
+
+    #[test]
+    fn vests_linearly_after_cliff() {
+        let schedule = schedule();
+
+        assert_eq!(schedule.vested(0), Uint256::zero());
+        assert_eq!(schedule.vested(109), Uint256::zero());
+        assert_eq!(schedule.vested(110), Uint256::from(250));
+        assert_eq!(schedule.vested(130), Uint256::from(750));
+        assert_eq!(schedule.vested(140), Uint256::from(1000));
+        assert_eq!(schedule.locked(1_000), Uint256::zero());
+    }
+
+    #[test]
+    fn status_splits_balance() {
+        let status = VestingStatus::new(120, Uint256::from(700), Some(schedule()));
+
+        assert_eq!(status.locked, Uint256::from(500));
+        assert_eq!(status.spendable, Uint256::from(200));
+
+        let status = VestingStatus::new(120, Uint256::from(300), Some(schedule()));
+        assert_eq!(status.spendable, Uint256::zero());
+    }
+}
@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use rm_reth_types::{Address, int::Uint256, tx::transaction::Transaction};
+use vm::{
+    execute::{apply_transfer, receipt, verify_witness},
+    gas::check_limits,
+    parallel::ParallelExecutor,
+};
+
+const TX_COUNT: usize = 1_000;
+
+fn addr(id: u32) -> Address {
+    let mut bytes = [0u8; 20];
+    bytes[..4].copy_from_slice(&id.to_le_bytes());
+    bytes.into()
+}
+
+/// Builds a block over `accounts` addresses: few accounts means many conflicts.
+fn block(accounts: u32) -> (HashMap<Address, Uint256>, Vec<Transaction>) {
+    let mut rng = StdRng::seed_from_u64(42);
+
+    let base = (0..accounts)
+        .map(|id| (addr(id), Uint256::from(1_000_000u64)))
+        .collect();
+
+    let txs = (0..TX_COUNT)
+        .map(|_| {
+            Transaction::new(
+                addr(rng.random_range(0..accounts)),
+                addr(rng.random_range(0..accounts)),
+                Uint256::from(rng.random_range(0..100u64)),
+                vec![],
+            )
+        })
+        .collect();
+
+    (base, txs)
+}
+
+fn bench_executors(c: &mut Criterion) {
+    let mut group = c.benchmark_group("execute_block");
+
+    for accounts in [10u32, 1_000, 100_000] {
+        let (base, txs) = block(accounts);
+
+        group.bench_with_input(BenchmarkId::new("sequential", accounts), &txs, |b, txs| {
+            b.iter(|| {
+                let mut tokens = base.clone();
+
+                // same checks and receipts as the executor
+                let receipts: Vec<_> = txs
+                    .iter()
+                    .map(|tx| {
+                        let result = check_limits(tx)
+                            .and_then(|()| verify_witness(tx))
+                            .and_then(|_| apply_transfer(&mut tokens, tx));
+                        receipt(tx, result)
+                    })
+                    .collect();
+
+                (tokens, receipts)
+            })
+        });
+
+        for workers in [1, 4] {
+            let executor = ParallelExecutor::new(workers);
+            let id = BenchmarkId::new(format!("parallel-{}", workers), accounts);
+
+            group.bench_with_input(id, &txs, |b, txs| b.iter(|| executor.execute(&base, txs)));
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_executors);
+criterion_main!(benches);
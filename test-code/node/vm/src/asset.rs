@@ -0,0 +1,353 @@
+use std::collections::{HashMap, hash_map::Entry};
+
+use rm_reth_types::{
+    Address,
+    asset::{AssetBalance, AssetId, AssetMetadata, AssetOp, MAX_SYMBOL_LEN},
+    hash::Hash,
+    int::Uint256,
+    log::Log,
+    tx::{kind::TxKind, transaction::Transaction},
+};
+use storage::{Snapshot, error::StorageError};
+
+use crate::error::VmError;
+
+/// Topic of the log emitted by every asset balance change.
+pub fn asset_transfer_topic() -> Hash {
+    Hash::hash(b"AssetTransfer(bytes32,address,address,uint256)")
+}
+
+/// Log emitted by the sender of an asset operation. Mints are sent from, and burns
+/// sent to, the zero address.
+pub fn asset_transfer_log(
+    sender: Address,
+    asset: AssetId,
+    from: &Address,
+    to: &Address,
+    amount: &Uint256,
+) -> Log {
+    Log {
+        address: sender,
+        topics: vec![
+            asset_transfer_topic(),
+            asset.0,
+            Log::address_topic(from),
+            Log::address_topic(to),
+        ],
+        data: amount.to_le_bytes().to_vec(),
+    }
+}
+
+/// Asset metadata and balances touched by a set of transactions.
+#[derive(Debug, Clone, Default)]
+pub struct AssetLedger {
+    pub metadata: HashMap<AssetId, AssetMetadata>,
+    pub balances: HashMap<(Address, AssetId), Uint256>,
+}
+
+impl AssetLedger {
+    /// Loads everything the asset transactions of `txs` may read.
+    pub fn load(snapshot: &Snapshot<'_>, txs: &[Transaction]) -> Result<Self, StorageError> {
+        let mut ledger = Self::default();
+
+        for tx in txs {
+            let Ok(TxKind::Asset(op)) = tx.kind() else {
+                continue;
+            };
+
+            let id = op.asset_id(&tx.from);
+
+            if let Entry::Vacant(entry) = ledger.metadata.entry(id)
+                && let Some(metadata) = snapshot.asset(&id)?
+            {
+                entry.insert(metadata);
+            }
+
+            for holder in op.holders(&tx.from) {
+                if let Entry::Vacant(entry) = ledger.balances.entry((holder, id)) {
+                    entry.insert(snapshot.asset_balance(&holder, &id)?);
+                }
+            }
+        }
+
+        Ok(ledger)
+    }
+
+    #[inline]
+    pub fn balance(&self, addr: &Address, asset: &AssetId) -> Uint256 {
+        self.balances
+            .get(&(*addr, *asset))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Applies `op` sent by `sender`.
+    ///
+    /// Like [`crate::execute::apply_transfer`], nothing is written on error.
+    pub fn apply(&mut self, sender: Address, op: AssetOp) -> Result<Vec<Log>, VmError> {
+        let asset = op.asset_id(&sender);
+        let zero = Address::default();
+
+        match op {
+            AssetOp::Issue {
+                symbol,
+                decimals,
+                initial_supply,
+            } => {
+                if symbol.is_empty()
+                    || symbol.len() > MAX_SYMBOL_LEN
+                    || !symbol.bytes().all(|b| b.is_ascii_alphanumeric())
+                {
+                    return Err(VmError::InvalidSymbol(symbol));
+                }
+
+                if self.metadata.contains_key(&asset) {
+                    return Err(VmError::AssetExists(asset));
+                }
+
+                let log = asset_transfer_log(sender, asset, &zero, &sender, &initial_supply);
+
+                self.balances
+                    .insert((sender, asset), initial_supply.clone());
+                self.metadata.insert(
+                    asset,
+                    AssetMetadata {
+                        symbol,
+                        decimals,
+                        issuer: sender,
+                        total_supply: initial_supply,
+                    },
+                );
+
+                Ok(vec![log])
+            }
+            AssetOp::Mint { to, amount, .. } => {
+                let metadata = self
+                    .metadata
+                    .get(&asset)
+                    .ok_or(VmError::UnknownAsset(asset))?;
+
+                if metadata.issuer != sender {
+                    return Err(VmError::NotIssuer { asset, sender });
+                }
+
+                let total_supply = metadata
+                    .total_supply
+                    .clone()
+                    .checked_add(amount.clone())
+                    .ok_or(VmError::SupplyOverflow(asset))?;
+
+                // bounded by the total supply
+                let to_balance = self.balance(&to, &asset) + amount.clone();
+
+                self.metadata.get_mut(&asset).unwrap().total_supply = total_supply;
+                self.balances.insert((to, asset), to_balance);
+
+                Ok(vec![asset_transfer_log(sender, asset, &zero, &to, &amount)])
+            }
+            AssetOp::Burn { amount, .. } => {
+                if !self.metadata.contains_key(&asset) {
+                    return Err(VmError::UnknownAsset(asset));
+                }
+
+                let from_balance = self.debit(&sender, asset, &amount)?;
+
+                let metadata = self.metadata.get_mut(&asset).unwrap();
+                metadata.total_supply =
+                    metadata.total_supply.clone().saturating_sub(amount.clone());
+                self.balances.insert((sender, asset), from_balance);
+
+                Ok(vec![asset_transfer_log(
+                    sender, asset, &sender, &zero, &amount,
+                )])
+            }
+            AssetOp::Transfer { to, amount, .. } => {
+                if !self.metadata.contains_key(&asset) {
+                    return Err(VmError::UnknownAsset(asset));
+                }
+
+                let from_balance = self.debit(&sender, asset, &amount)?;
+
+                if sender != to {
+                    // bounded by the total supply
+                    let to_balance = self.balance(&to, &asset) + amount.clone();
+
+                    self.balances.insert((sender, asset), from_balance);
+                    self.balances.insert((to, asset), to_balance);
+                }
+
+                Ok(vec![asset_transfer_log(
+                    sender, asset, &sender, &to, &amount,
+                )])
+            }
+        }
+    }
+
+    /// Splits the ledger into the entries stored in a block, sorted so the block hash
+    /// does not depend on the map order.
+    pub fn into_block_parts(self) -> (Vec<AssetMetadata>, Vec<AssetBalance>) {
+        let mut assets: Vec<_> = self.metadata.into_values().collect();
+        assets.sort_by_key(|metadata| metadata.id());
+
+        let mut balances: Vec<_> = self
+            .balances
+            .into_iter()
+            .map(|((addr, asset), amount)| AssetBalance {
+                addr,
+                asset,
+                amount,
+            })
+            .collect();
+        balances.sort_by_key(|balance| (balance.asset, balance.addr));
+
+        (assets, balances)
+    }
+
+    fn debit(&self, addr: &Address, asset: AssetId, amount: &Uint256) -> Result<Uint256, VmError> {
+        let available = self.balance(addr, &asset);
+
+        available
+            .clone()
+            .checked_sub(amount.clone())
+            .ok_or(VmError::InsufficientAssetBalance {
+                asset,
+                required: amount.clone(),
+                available,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addr(id: u8) -> Address {
+        [id; 20].into()
+    }
+
+    fn u(v: u64) -> Uint256 {
+        Uint256::from(v)
+    }
+
+    fn issue(ledger: &mut AssetLedger, issuer: Address, supply: u64) -> AssetId {
+        let op = AssetOp::Issue {
+            symbol: "GOLD".into(),
+            decimals: 6,
+            initial_supply: u(supply),
+        };
+
+        ledger.apply(issuer, op).unwrap();
+
+        AssetId::derive(&issuer, "GOLD")
+    }
+
+    #[test]
+    fn issue_mint_transfer_burn() {
+        let mut ledger = AssetLedger::default();
+        let gold = issue(&mut ledger, addr(1), 100);
+
+        let ops = [
+            (
+                addr(1),
+                AssetOp::Mint {
+                    asset: gold,
+                    to: addr(2),
+                    amount: u(50),
+                },
+            ),
+            (
+                addr(2),
+                AssetOp::Transfer {
+                    asset: gold,
+                    to: addr(3),
+                    amount: u(20),
+                },
+            ),
+            (
+                addr(3),
+                AssetOp::Burn {
+                    asset: gold,
+                    amount: u(5),
+                },
+            ),
+        ];
+
+        for (from, op) in ops {
+            assert_eq!(ledger.apply(from, op).unwrap().len(), 1);
+        }
+
+        assert_eq!(ledger.balance(&addr(1), &gold), u(100));
+        assert_eq!(ledger.balance(&addr(2), &gold), u(30));
+        assert_eq!(ledger.balance(&addr(3), &gold), u(15));
+        assert_eq!(ledger.metadata[&gold].total_supply, u(145));
+        assert_eq!(ledger.metadata[&gold].decimals, 6);
+    }
+
+    #[test]
+    fn rejected_ops_leave_ledger_untouched() {
+        let mut ledger = AssetLedger::default();
+        let gold = issue(&mut ledger, addr(1), 100);
+
+        let cases = [
+            (
+                addr(2),
+                AssetOp::Mint {
+                    asset: gold,
+                    to: addr(2),
+                    amount: u(1),
+                },
+                VmError::NotIssuer {
+                    asset: gold,
+                    sender: addr(2),
+                },
+            ),
+            (
+                addr(2),
+                AssetOp::Transfer {
+                    asset: gold,
+                    to: addr(3),
+                    amount: u(1),
+                },
+                VmError::InsufficientAssetBalance {
+                    asset: gold,
+                    required: u(1),
+                    available: u(0),
+                },
+            ),
+            (
+                addr(1),
+                AssetOp::Issue {
+                    symbol: "GOLD".into(),
+                    decimals: 0,
+                    initial_supply: u(1),
+                },
+                VmError::AssetExists(gold),
+            ),
+            (
+                addr(1),
+                AssetOp::Issue {
+                    symbol: "not a symbol".into(),
+                    decimals: 0,
+                    initial_supply: u(1),
+                },
+                VmError::InvalidSymbol("not a symbol".into()),
+            ),
+            (
+                addr(1),
+                AssetOp::Burn {
+                    asset: AssetId::default(),
+                    amount: u(1),
+                },
+                VmError::UnknownAsset(AssetId::default()),
+            ),
+        ];
+
+        for (from, op, err) in cases {
+            assert_eq!(ledger.apply(from, op), Err(err));
+        }
+
+        assert_eq!(ledger.metadata.len(), 1);
+        assert_eq!(ledger.metadata[&gold].total_supply, u(100));
+        assert_eq!(ledger.balance(&addr(1), &gold), u(100));
+    }
+}
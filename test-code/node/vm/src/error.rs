@@ -0,0 +1,129 @@
+use rm_reth_types::{Address, asset::AssetId, hash::Hash, int::Uint256, multisig::MultisigError};
+
+/// Reason a transaction failed to execute.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum VmError {
+    #[error("insufficient balance (required: {required}, available: {available})")]
+    InsufficientBalance {
+        required: Uint256,
+        available: Uint256,
+    },
+
+    #[error("balance overflow for {0}")]
+    BalanceOverflow(Address),
+
+    #[error("invalid transaction payload")]
+    InvalidPayload,
+
+    #[error("unsupported transaction kind: {0}")]
+    UnsupportedTxKind(&'static str),
+
+    #[error("{0} is not allowed to mint")]
+    UnauthorizedMinter(Address),
+
+    #[error("invalid asset symbol: {0:?}")]
+    InvalidSymbol(String),
+
+    #[error("asset already issued: {0}")]
+    AssetExists(AssetId),
+
+    #[error("unknown asset: {0}")]
+    UnknownAsset(AssetId),
+
+    #[error("{sender} is not the issuer of {asset}")]
+    NotIssuer { asset: AssetId, sender: Address },
+
+    #[error("insufficient {asset} balance (required: {required}, available: {available})")]
+    InsufficientAssetBalance {
+        asset: AssetId,
+        required: Uint256,
+        available: Uint256,
+    },
+
+    #[error("total supply overflow for {0}")]
+    SupplyOverflow(AssetId),
+
+    #[error("multi-transfer without legs")]
+    EmptyBatch,
+
+    #[error("too many transfer legs (legs: {legs}, max: {max})")]
+    TooManyLegs { legs: usize, max: usize },
+
+    #[error("gas limit exceeded (gas: {gas}, limit: {limit})")]
+    GasLimitExceeded { gas: u64, limit: u64 },
+
+    #[error("timelock must be above the current height (timelock: {timelock}, height: {height})")]
+    InvalidTimelock { timelock: u64, height: u64 },
+
+    #[error("htlc already exists: {0}")]
+    HtlcExists(Hash),
+
+    #[error("unknown htlc: {0}")]
+    UnknownHtlc(Hash),
+
+    #[error("htlc is not open: {0}")]
+    HtlcNotOpen(Hash),
+
+    #[error("htlc expired: {0}")]
+    HtlcExpired(Hash),
+
+    #[error("htlc not expired yet: {0}")]
+    HtlcNotExpired(Hash),
+
+    #[error("preimage does not match the hashlock of {0}")]
+    InvalidPreimage(Hash),
+
+    #[error("invalid vesting schedule")]
+    InvalidVestingSchedule,
+
+    #[error("vesting schedule still locking the balance of {0}")]
+    VestingExists(Address),
+
+    #[error("vesting offer already exists: {0}")]
+    VestingOfferExists(Hash),
+
+    #[error("unknown vesting offer: {0}")]
+    UnknownVestingOffer(Hash),
+
+    #[error("vesting offer is not pending: {0}")]
+    VestingOfferNotPending(Hash),
+
+    #[error("{0} is not the beneficiary of the vesting offer")]
+    NotVestingBeneficiary(Address),
+
+    #[error("{0} is not the grantor of the vesting offer")]
+    NotVestingGrantor(Address),
+
+    #[error(
+        "balance still vesting (required: {required}, spendable: {spendable}, locked: {locked})"
+    )]
+    VestingLocked {
+        required: Uint256,
+        spendable: Uint256,
+        locked: Uint256,
+    },
+
+    #[error("multisig verification failed: {0}")]
+    Multisig(#[from] MultisigError),
+
+    #[error("invalid stake")]
+    InvalidStake,
+
+    #[error("{0} already stakes with another key")]
+    StakeKeyMismatch(Address),
+
+    #[error("staking key is not signed over its sender")]
+    InvalidKeyProof,
+
+    #[error("staking key is already registered to {0}")]
+    StakeKeyTaken(Address),
+
+    #[error("{0} is not a validator")]
+    NotAValidator(Address),
+
+    #[error("insufficient stake (required: {required}, staked: {staked})")]
+    InsufficientStake { required: Uint256, staked: Uint256 },
+
+    #[error("nothing to withdraw for {0}")]
+    NothingToWithdraw(Address),
+}
@@ -0,0 +1,645 @@
+use std::collections::{HashMap, HashSet};
+
+use rm_reth_types::{
+    Address,
+    hash::Hash,
+    htlc::{Htlc, HtlcStatus},
+    int::Uint256,
+    log::Log,
+    multisig::{MultisigError, MultisigWitness},
+    tx::{
+        kind::{TransferLeg, TxKind},
+        receipt::Receipt,
+        transaction::Transaction,
+    },
+};
+
+use crate::{
+    asset::AssetLedger,
+    error::VmError,
+    gas::{check_limits, intrinsic_gas},
+    htlc::HtlcLedger,
+    multisig::MultisigLedger,
+    staking::StakingLedger,
+    vesting::VestingLedger,
+};
+
+/// Topic of the log emitted by every native balance movement.
+pub fn transfer_topic() -> Hash {
+    Hash::hash(b"Transfer(address,address,uint256)")
+}
+
+/// Log emitted by `sender` for a native balance movement. Mints are sent from, and
+/// burns sent to, the zero address.
+pub fn transfer_log(sender: Address, from: &Address, to: &Address, amount: &Uint256) -> Log {
+    Log {
+        address: sender,
+        topics: vec![
+            transfer_topic(),
+            Log::address_topic(from),
+            Log::address_topic(to),
+        ],
+        data: amount.to_le_bytes().to_vec(),
+    }
+}
+
+/// Builds the receipt of `tx` from its execution result.
+pub fn receipt(tx: &Transaction, result: Result<Vec<Log>, VmError>) -> Receipt {
+    let (success, logs) = match result {
+        Ok(logs) => (true, logs),
+        Err(_) => (false, vec![]),
+    };
+
+    let legs = match tx.kind() {
+        Ok(TxKind::MultiTransfer { transfers }) if success => transfers,
+        _ => vec![],
+    };
+
+    Receipt {
+        tx_hash: tx.hash(),
+        success,
+        gas_used: intrinsic_gas(tx),
+        logs,
+        legs,
+    }
+}
+
+/// Native balances `tx` may read or write, so callers can preload them.
+pub fn touched_accounts(tx: &Transaction) -> Vec<Address> {
+    match tx.kind() {
+        Ok(TxKind::MultiTransfer { transfers }) => std::iter::once(tx.from)
+            .chain(transfers.into_iter().map(|leg| leg.to))
+            .collect(),
+        // a claim later in the block credits the recipient
+        Ok(TxKind::HtlcLock { recipient, .. }) => vec![tx.from, recipient],
+        Ok(
+            TxKind::VestingGrant { .. }
+            | TxKind::VestingAccept { .. }
+            | TxKind::VestingCancel { .. }
+            | TxKind::Stake { .. }
+            | TxKind::Unstake
+            | TxKind::WithdrawStake,
+        ) => vec![tx.from],
+        Ok(TxKind::MultisigCreate { account }) => vec![tx.from, account.address()],
+        _ => vec![tx.from, tx.to],
+    }
+}
+
+/// Balance view a transaction is executed against.
+///
+/// Both the sequential and the parallel executor go through [`apply_transfer`],
+/// so they only differ in how reads and writes are resolved.
+pub trait BalanceState {
+    fn balance(&mut self, addr: &Address) -> Uint256;
+
+    fn set_balance(&mut self, addr: Address, amount: Uint256);
+}
+
+impl BalanceState for HashMap<Address, Uint256> {
+    #[inline]
+    fn balance(&mut self, addr: &Address) -> Uint256 {
+        self.get(addr).cloned().unwrap_or_default()
+    }
+
+    #[inline]
+    fn set_balance(&mut self, addr: Address, amount: Uint256) {
+        self.insert(addr, amount);
+    }
+}
+
+/// State beyond native balances that some transaction kinds need.
+pub struct TxEnv<'a> {
+    pub assets: &'a mut AssetLedger,
+    /// Senders allowed to send [`TxKind::Mint`].
+    pub minters: &'a HashSet<Address>,
+    pub htlcs: &'a mut HtlcLedger,
+    /// Schedules restricting what senders may spend, see [`native_debit`].
+    pub vesting: &'a mut VestingLedger,
+    pub staking: &'a mut StakingLedger,
+    /// Registered multisig accounts, which only send transactions with a witness.
+    pub multisig: &'a mut MultisigLedger,
+    /// Height of the block the transactions are executed in.
+    pub height: u64,
+    /// Position of the transaction being applied in its block.
+    pub index: u32,
+}
+
+/// Applies `tx` by dispatching on its [`TxKind`].
+///
+/// On error the transaction is skipped and nothing is written, otherwise the
+/// emitted logs are returned.
+pub fn apply_tx<S: BalanceState>(
+    state: &mut S,
+    env: &mut TxEnv<'_>,
+    tx: &Transaction,
+) -> Result<Vec<Log>, VmError> {
+    let kind = tx.kind().map_err(|_| VmError::InvalidPayload)?;
+
+    check_limits(tx)?;
+    env.multisig.authorize(tx)?;
+
+    env.vesting
+        .check_spend(state, &tx.from, &native_debit(tx, &kind), env.height)?;
+
+    let logs = match kind {
+        TxKind::Transfer => apply_transfer(state, tx),
+        TxKind::Mint => apply_mint(state, env.minters, tx),
+        TxKind::Burn => apply_burn(state, tx),
+        TxKind::MultiTransfer { transfers } => apply_multi_transfer(state, tx.from, &transfers),
+        TxKind::Asset(op) => env.assets.apply(tx.from, op),
+        TxKind::HtlcLock {
+            recipient,
+            hashlock,
+            timelock,
+        } => {
+            let htlc = Htlc {
+                id: Htlc::lock_id(&tx.hash(), env.height, env.index),
+                sender: tx.from,
+                recipient,
+                amount: tx.amount.clone(),
+                hashlock,
+                timelock,
+                status: HtlcStatus::Open,
+            };
+
+            env.htlcs.lock(state, htlc, env.height)
+        }
+        TxKind::HtlcClaim { id, preimage } => {
+            env.htlcs.claim(state, tx.from, id, preimage, env.height)
+        }
+        TxKind::HtlcRefund { id } => env.htlcs.refund(state, tx.from, id, env.height),
+        TxKind::VestingGrant {
+            beneficiary,
+            start,
+            cliff,
+            duration,
+        } => env
+            .vesting
+            .grant(state, tx, beneficiary, start, cliff, duration),
+        TxKind::VestingAccept { id } => env.vesting.accept(state, tx.from, id, env.height),
+        TxKind::VestingCancel { id } => env.vesting.cancel(state, tx.from, id),
+        TxKind::Stake { public_key, proof } => env.staking.stake(state, tx, public_key, &proof),
+        TxKind::Unstake => env.staking.unstake(tx, env.height),
+        TxKind::WithdrawStake => env.staking.withdraw(state, tx, env.height),
+        TxKind::MultisigCreate { account } => env.multisig.create(state, tx, account),
+        TxKind::ContractDeploy { .. } | TxKind::ContractCall { .. } => {
+            Err(VmError::UnsupportedTxKind(kind.name()))
+        }
+    }?;
+
+    env.multisig.record_spend(tx);
+
+    Ok(logs)
+}
+
+/// Verifies the multisig witness of `tx`, if it carries one, and returns it.
+///
+/// The witness account has to be the sender and enough of its keys have to sign
+/// [`Transaction::signing_hash`] at the nonce of the witness. Whether that nonce
+/// is the current one is left to [`MultisigLedger::authorize`].
+pub fn verify_witness(tx: &Transaction) -> Result<Option<MultisigWitness>, VmError> {
+    let Some(witness) = tx.witness().map_err(|_| VmError::InvalidPayload)? else {
+        return Ok(None);
+    };
+
+    let expected = witness.account.address();
+
+    if expected != tx.from {
+        return Err(MultisigError::SenderMismatch {
+            expected,
+            sender: tx.from,
+        }
+        .into());
+    }
+
+    let message = tx
+        .signing_hash(witness.nonce)
+        .map_err(|_| VmError::InvalidPayload)?;
+    witness.verify(&message)?;
+
+    Ok(Some(witness))
+}
+
+/// Native balance `kind` takes out of `tx.from`, payments to itself excluded.
+pub fn native_debit(tx: &Transaction, kind: &TxKind) -> Uint256 {
+    match kind {
+        TxKind::Transfer if tx.from != tx.to => tx.amount.clone(),
+        TxKind::Burn
+        | TxKind::HtlcLock { .. }
+        | TxKind::VestingGrant { .. }
+        | TxKind::Stake { .. } => tx.amount.clone(),
+        TxKind::MultisigCreate { account } if account.address() != tx.from => tx.amount.clone(),
+        TxKind::MultiTransfer { transfers } => transfers
+            .iter()
+            .filter(|leg| leg.to != tx.from)
+            .fold(Uint256::zero(), |total, leg| {
+                total.saturating_add(leg.amount.clone())
+            }),
+        _ => Uint256::zero(),
+    }
+}
+
+/// Applies a single transfer to `state`.
+pub fn apply_transfer<S: BalanceState>(
+    state: &mut S,
+    tx: &Transaction,
+) -> Result<Vec<Log>, VmError> {
+    if tx.from != tx.to {
+        // check vaild tx
+        let available = state.balance(&tx.from);
+
+        let from_balance = match available.clone().checked_sub(tx.amount.clone()) {
+            Some(from_balance) => from_balance,
+            None => {
+                return Err(VmError::InsufficientBalance {
+                    required: tx.amount.clone(),
+                    available,
+                });
+            }
+        };
+
+        let to_balance = match state.balance(&tx.to).checked_add(tx.amount.clone()) {
+            Some(to_balance) => to_balance,
+            None => return Err(VmError::BalanceOverflow(tx.to)),
+        };
+
+        // update balances
+        state.set_balance(tx.from, from_balance);
+        state.set_balance(tx.to, to_balance);
+    } else {
+        // check vaild tx
+        let balance = state.balance(&tx.from);
+
+        if balance < tx.amount {
+            return Err(VmError::InsufficientBalance {
+                required: tx.amount.clone(),
+                available: balance,
+            });
+        }
+
+        state.set_balance(tx.from, balance);
+    }
+
+    Ok(vec![transfer_log(tx.from, &tx.from, &tx.to, &tx.amount)])
+}
+
+/// Credits `tx.amount` new tokens to `tx.to`.
+pub fn apply_mint<S: BalanceState>(
+    state: &mut S,
+    minters: &HashSet<Address>,
+    tx: &Transaction,
+) -> Result<Vec<Log>, VmError> {
+    if !minters.contains(&tx.from) {
+        return Err(VmError::UnauthorizedMinter(tx.from));
+    }
+
+    let to_balance = state
+        .balance(&tx.to)
+        .checked_add(tx.amount.clone())
+        .ok_or(VmError::BalanceOverflow(tx.to))?;
+
+    state.set_balance(tx.to, to_balance);
+
+    Ok(vec![transfer_log(
+        tx.from,
+        &Address::default(),
+        &tx.to,
+        &tx.amount,
+    )])
+}
+
+/// Destroys `tx.amount` tokens held by `tx.from`.
+pub fn apply_burn<S: BalanceState>(state: &mut S, tx: &Transaction) -> Result<Vec<Log>, VmError> {
+    let available = state.balance(&tx.from);
+
+    let from_balance =
+        available
+            .clone()
+            .checked_sub(tx.amount.clone())
+            .ok_or(VmError::InsufficientBalance {
+                required: tx.amount.clone(),
+                available,
+            })?;
+
+    state.set_balance(tx.from, from_balance);
+
+    Ok(vec![transfer_log(
+        tx.from,
+        &tx.from,
+        &Address::default(),
+        &tx.amount,
+    )])
+}
+
+/// Pays every leg of `transfers` from `from`, or none of them.
+pub fn apply_multi_transfer<S: BalanceState>(
+    state: &mut S,
+    from: Address,
+    transfers: &[TransferLeg],
+) -> Result<Vec<Log>, VmError> {
+    // legs are applied on a copy of the touched balances first
+    let mut pending: HashMap<Address, Uint256> = HashMap::new();
+
+    for TransferLeg { to, amount } in transfers {
+        let available = match pending.get(&from) {
+            Some(balance) => balance.clone(),
+            None => state.balance(&from),
+        };
+
+        let from_balance =
+            available
+                .clone()
+                .checked_sub(amount.clone())
+                .ok_or(VmError::InsufficientBalance {
+                    required: amount.clone(),
+                    available,
+                })?;
+        pending.insert(from, from_balance);
+
+        let to_balance = match pending.get(to) {
+            Some(balance) => balance.clone(),
+            None => state.balance(to),
+        };
+
+        let to_balance = to_balance
+            .checked_add(amount.clone())
+            .ok_or(VmError::BalanceOverflow(*to))?;
+        pending.insert(*to, to_balance);
+    }
+
+    for (addr, balance) in pending {
+        state.set_balance(addr, balance);
+    }
+
+    Ok(transfers
+        .iter()
+        .map(|leg| transfer_log(from, &from, &leg.to, &leg.amount))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use rm_reth_types::asset::AssetOp;
+
+    use super::*;
+    use crate::gas::{MAX_TRANSFER_LEGS, MAX_TX_GAS, TRANSFER_LEG_GAS, TX_BASE_GAS};
+
+    fn addr(id: u8) -> Address {
+        [id; 20].into()
+    }
+
+    fn u(v: u64) -> Uint256 {
+        Uint256::from(v)
+    }
+
+    fn leg(id: u8, amount: u64) -> TransferLeg {
+        TransferLeg {
+            to: addr(id),
+            amount: u(amount),
+        }
+    }
+
+    fn run(
+        tokens: &mut HashMap<Address, Uint256>,
+        minters: &HashSet<Address>,
+        tx: &Transaction,
+    ) -> Result<Vec<Log>, VmError> {
+        run_in(tokens, minters, &mut MultisigLedger::default(), tx)
+    }
+
+    fn run_with(
+        tokens: &mut HashMap<Address, Uint256>,
+        multisig: &mut MultisigLedger,
+        tx: &Transaction,
+    ) -> Result<Vec<Log>, VmError> {
+        run_in(tokens, &HashSet::new(), multisig, tx)
+    }
+
+    fn run_in(
+        tokens: &mut HashMap<Address, Uint256>,
+        minters: &HashSet<Address>,
+        multisig: &mut MultisigLedger,
+        tx: &Transaction,
+    ) -> Result<Vec<Log>, VmError> {
+        let mut assets = AssetLedger::default();
+        let mut htlcs = HtlcLedger::default();
+        let mut vesting = VestingLedger::default();
+        let mut staking = StakingLedger::default();
+        let mut env = TxEnv {
+            assets: &mut assets,
+            minters,
+            htlcs: &mut htlcs,
+            vesting: &mut vesting,
+            staking: &mut staking,
+            multisig,
+            height: 0,
+            index: 0,
+        };
+
+        apply_tx(tokens, &mut env, tx)
+    }
+
+    #[test]
+    fn dispatches_each_kind() {
+        let minters = HashSet::from([addr(9)]);
+        let mut tokens = HashMap::from([(addr(1), u(100))]);
+
+        let mint = Transaction::with_kind(addr(9), addr(2), u(50), TxKind::Mint);
+        let burn = Transaction::with_kind(addr(1), addr(1), u(10), TxKind::Burn);
+        let multi = Transaction::with_kind(
+            addr(1),
+            addr(1),
+            u(0),
+            TxKind::MultiTransfer {
+                transfers: vec![leg(3, 20), leg(4, 30)],
+            },
+        );
+
+        assert_eq!(run(&mut tokens, &minters, &mint).unwrap().len(), 1);
+        assert_eq!(run(&mut tokens, &minters, &burn).unwrap().len(), 1);
+        assert_eq!(run(&mut tokens, &minters, &multi).unwrap().len(), 2);
+
+        assert_eq!(tokens[&addr(1)], u(40));
+        assert_eq!(tokens[&addr(2)], u(50));
+        assert_eq!(tokens[&addr(3)], u(20));
+        assert_eq!(tokens[&addr(4)], u(30));
+
+        let issue = Transaction::asset(
+            addr(1),
+            AssetOp::Issue {
+                symbol: "GOLD".into(),
+                decimals: 0,
+                initial_supply: u(1),
+            },
+        );
+        assert!(run(&mut tokens, &minters, &issue).is_ok());
+    }
+
+    #[test]
+    fn rejects_unauthorised_and_unsupported_kinds() {
+        let minters = HashSet::new();
+        let mut tokens = HashMap::new();
+
+        let mint = Transaction::with_kind(addr(1), addr(1), u(50), TxKind::Mint);
+        assert_eq!(
+            run(&mut tokens, &minters, &mint),
+            Err(VmError::UnauthorizedMinter(addr(1)))
+        );
+
+        let deploy = Transaction::with_kind(
+            addr(1),
+            addr(1),
+            u(0),
+            TxKind::ContractDeploy { code: vec![0] },
+        );
+        assert_eq!(
+            run(&mut tokens, &minters, &deploy),
+            Err(VmError::UnsupportedTxKind("contract_deploy"))
+        );
+
+        let mut malformed = TxKind::Burn.to_payload();
+        malformed.push(0);
+        let malformed = Transaction::new(addr(1), addr(1), u(0), malformed);
+        assert_eq!(
+            run(&mut tokens, &minters, &malformed),
+            Err(VmError::InvalidPayload)
+        );
+
+        assert!(tokens.values().all(Uint256::is_zero));
+    }
+
+    #[test]
+    fn multi_transfer_is_all_or_nothing() {
+        let mut tokens = HashMap::from([(addr(1), u(100))]);
+
+        let result = apply_multi_transfer(&mut tokens, addr(1), &[leg(2, 60), leg(3, 60)]);
+
+        assert_eq!(
+            result,
+            Err(VmError::InsufficientBalance {
+                required: u(60),
+                available: u(40),
+            })
+        );
+        assert_eq!(tokens, HashMap::from([(addr(1), u(100))]));
+    }
+
+    #[test]
+    fn multi_transfer_limits() {
+        let minters = HashSet::new();
+        let mut tokens = HashMap::from([(addr(1), u(1_000_000))]);
+
+        let empty = Transaction::multi_transfer(addr(1), vec![]);
+        assert_eq!(run(&mut tokens, &minters, &empty), Err(VmError::EmptyBatch));
+
+        let oversized =
+            Transaction::multi_transfer(addr(1), vec![leg(2, 1); MAX_TRANSFER_LEGS + 1]);
+        assert_eq!(
+            run(&mut tokens, &minters, &oversized),
+            Err(VmError::TooManyLegs {
+                legs: MAX_TRANSFER_LEGS + 1,
+                max: MAX_TRANSFER_LEGS,
+            })
+        );
+
+        let full = Transaction::multi_transfer(addr(1), vec![leg(2, 1); MAX_TRANSFER_LEGS]);
+        assert!(intrinsic_gas(&full) <= MAX_TX_GAS);
+        assert!(run(&mut tokens, &minters, &full).is_ok());
+        assert_eq!(tokens[&addr(2)], u(MAX_TRANSFER_LEGS as u64));
+
+        let huge = Transaction::new(addr(1), addr(2), u(1), vec![0; MAX_TX_GAS as usize]);
+        assert!(matches!(
+            run(&mut tokens, &minters, &huge),
+            Err(VmError::GasLimitExceeded { .. })
+        ));
+    }
+
+    #[test]
+    fn multisig_spend_needs_threshold_signatures() {
+        use k256::ecdsa::SigningKey;
+        use rm_reth_types::multisig::{MultisigAccount, MultisigWitness, PublicKey};
+
+        let keys: Vec<_> = (1..=3)
+            .map(|id| SigningKey::from_bytes(&[id; 32].into()).unwrap())
+            .collect();
+        let account =
+            MultisigAccount::new(2, keys.iter().map(PublicKey::from_signing_key).collect())
+                .unwrap();
+        let treasury = account.address();
+
+        let mut multisig = MultisigLedger::default();
+        let mut tokens = HashMap::from([(addr(1), u(100))]);
+
+        let create = Transaction::multisig_create(addr(1), account.clone(), u(100));
+        assert_eq!(
+            run_with(&mut tokens, &mut multisig, &create).unwrap().len(),
+            2
+        );
+        assert!(multisig.is_registered(&treasury));
+        assert_eq!(tokens[&treasury], u(100));
+
+        // a registered account cannot send without a witness
+        let tx = Transaction::new(treasury, addr(2), u(30), vec![]);
+        assert_eq!(
+            run_with(&mut tokens, &mut multisig, &tx),
+            Err(VmError::Multisig(MultisigError::MissingWitness(treasury)))
+        );
+
+        let message = tx.signing_hash(0).unwrap();
+
+        let mut witness = MultisigWitness::new(account, 0);
+        witness.cosign(&keys[0], &message).unwrap();
+
+        let partial = tx.clone().with_witness(witness.clone()).unwrap();
+        assert_eq!(
+            run_with(&mut tokens, &mut multisig, &partial),
+            Err(VmError::Multisig(MultisigError::NotEnoughSignatures {
+                required: 2,
+                got: 1
+            }))
+        );
+
+        witness.cosign(&keys[2], &message).unwrap();
+        let signed = tx.with_witness(witness.clone()).unwrap();
+        assert!(run_with(&mut tokens, &mut multisig, &signed).is_ok());
+        assert_eq!(tokens[&treasury], u(70));
+        assert_eq!(tokens[&addr(2)], u(30));
+        assert_eq!(multisig.nonce(&treasury), 1);
+
+        // the nonce moved on, so the same witness cannot be replayed
+        assert_eq!(
+            run_with(&mut tokens, &mut multisig, &signed),
+            Err(VmError::Multisig(MultisigError::InvalidNonce {
+                expected: 1,
+                got: 0
+            }))
+        );
+        assert_eq!(tokens[&treasury], u(70));
+
+        // the witness only authorises its own account
+        let forged = Transaction::new(addr(1), addr(2), u(30), vec![])
+            .with_witness(witness)
+            .unwrap();
+        assert!(matches!(
+            run_with(&mut tokens, &mut multisig, &forged),
+            Err(VmError::Multisig(MultisigError::SenderMismatch { .. }))
+        ));
+    }
+
+    #[test]
+    fn multi_transfer_receipt_lists_legs() {
+        let mut tokens = HashMap::from([(addr(1), u(100))]);
+        let legs = vec![leg(2, 10), leg(3, 20)];
+        let tx = Transaction::multi_transfer(addr(1), legs.clone());
+
+        let ok = receipt(&tx, run(&mut tokens, &HashSet::new(), &tx));
+        assert!(ok.success);
+        assert_eq!(ok.legs, legs);
+        assert_eq!(ok.logs.len(), 2);
+        assert_eq!(ok.gas_used, intrinsic_gas(&tx));
+        assert!(ok.gas_used >= TX_BASE_GAS + 2 * TRANSFER_LEG_GAS);
+
+        let failed = receipt(&tx, Err(VmError::EmptyBatch));
+        assert!(failed.legs.is_empty());
+    }
+}
@@ -0,0 +1,67 @@
+use rm_reth_types::tx::{kind::TxKind, transaction::Transaction};
+
+use crate::error::VmError;
+
+/// Flat cost charged for every transaction.
+pub const TX_BASE_GAS: u64 = 21_000;
+
+/// Cost per byte of `Transaction::data`.
+pub const TX_DATA_BYTE_GAS: u64 = 16;
+
+/// Cost per leg of a multi-transfer, on top of its data.
+pub const TRANSFER_LEG_GAS: u64 = 6_000;
+
+/// Maximum number of legs of a single multi-transfer.
+pub const MAX_TRANSFER_LEGS: usize = 256;
+
+/// Cost per signature of a multisig witness, on top of its data.
+pub const MULTISIG_SIG_GAS: u64 = 3_000;
+
+/// Maximum gas a single transaction may consume.
+pub const MAX_TX_GAS: u64 = 2_000_000;
+
+/// Gas a transaction consumes before any execution takes place.
+#[inline]
+pub fn intrinsic_gas(tx: &Transaction) -> u64 {
+    let legs = match tx.kind() {
+        Ok(TxKind::MultiTransfer { transfers }) => transfers.len() as u64,
+        _ => 0,
+    };
+
+    let signatures = match tx.witness() {
+        Ok(Some(witness)) => witness.signatures.len() as u64,
+        _ => 0,
+    };
+
+    TX_BASE_GAS
+        + TX_DATA_BYTE_GAS * tx.data.len() as u64
+        + TRANSFER_LEG_GAS * legs
+        + MULTISIG_SIG_GAS * signatures
+}
+
+/// Rejects transactions exceeding the size and gas limits, before execution.
+pub fn check_limits(tx: &Transaction) -> Result<(), VmError> {
+    if let Ok(TxKind::MultiTransfer { transfers }) = tx.kind() {
+        if transfers.is_empty() {
+            return Err(VmError::EmptyBatch);
+        }
+
+        if transfers.len() > MAX_TRANSFER_LEGS {
+            return Err(VmError::TooManyLegs {
+                legs: transfers.len(),
+                max: MAX_TRANSFER_LEGS,
+            });
+        }
+    }
+
+    let gas = intrinsic_gas(tx);
+
+    if gas > MAX_TX_GAS {
+        return Err(VmError::GasLimitExceeded {
+            gas,
+            limit: MAX_TX_GAS,
+        });
+    }
+
+    Ok(())
+}
@@ -0,0 +1,312 @@
+use std::collections::{HashMap, hash_map::Entry};
+
+use rm_reth_types::{
+    Address,
+    hash::Hash,
+    htlc::{Htlc, HtlcStatus},
+    int::Uint256,
+    log::Log,
+    tx::{kind::TxKind, transaction::Transaction},
+};
+use storage::{Snapshot, error::StorageError};
+
+use crate::{error::VmError, execute::BalanceState};
+
+pub fn htlc_locked_topic() -> Hash {
+    Hash::hash(b"HtlcLocked(bytes32,address,address,uint256)")
+}
+
+pub fn htlc_claimed_topic() -> Hash {
+    Hash::hash(b"HtlcClaimed(bytes32,bytes)")
+}
+
+pub fn htlc_refunded_topic() -> Hash {
+    Hash::hash(b"HtlcRefunded(bytes32)")
+}
+
+/// Hash time-locked contracts touched by a set of transactions.
+#[derive(Debug, Clone, Default)]
+pub struct HtlcLedger {
+    pub locks: HashMap<Hash, Htlc>,
+}
+
+impl HtlcLedger {
+    /// Loads every lock the HTLC transactions of `txs` may read.
+    pub fn load(snapshot: &Snapshot<'_>, txs: &[Transaction]) -> Result<Self, StorageError> {
+        let mut ledger = Self::default();
+
+        for tx in txs {
+            // new locks get ids no stored lock has
+            let id = match tx.kind() {
+                Ok(TxKind::HtlcClaim { id, .. } | TxKind::HtlcRefund { id }) => id,
+                _ => continue,
+            };
+
+            if let Entry::Vacant(entry) = ledger.locks.entry(id)
+                && let Some(htlc) = snapshot.htlc(&id)?
+            {
+                entry.insert(htlc);
+            }
+        }
+
+        Ok(ledger)
+    }
+
+    /// Native balances claims and refunds of the loaded locks may credit.
+    pub fn accounts(&self) -> impl Iterator<Item = Address> + '_ {
+        self.locks
+            .values()
+            .flat_map(|htlc| [htlc.sender, htlc.recipient])
+    }
+
+    /// Moves `htlc.amount` from its sender into `htlc`, a new open lock.
+    pub fn lock<S: BalanceState>(
+        &mut self,
+        state: &mut S,
+        htlc: Htlc,
+        height: u64,
+    ) -> Result<Vec<Log>, VmError> {
+        let Htlc {
+            id,
+            sender,
+            recipient,
+            ref amount,
+            timelock,
+            ..
+        } = htlc;
+
+        if timelock <= height {
+            return Err(VmError::InvalidTimelock { timelock, height });
+        }
+
+        if self.locks.contains_key(&id) {
+            return Err(VmError::HtlcExists(id));
+        }
+
+        let available = state.balance(&sender);
+        let from_balance =
+            available
+                .clone()
+                .checked_sub(amount.clone())
+                .ok_or(VmError::InsufficientBalance {
+                    required: amount.clone(),
+                    available,
+                })?;
+
+        state.set_balance(sender, from_balance);
+
+        let log = Log {
+            address: sender,
+            topics: vec![
+                htlc_locked_topic(),
+                id,
+                Log::address_topic(&sender),
+                Log::address_topic(&recipient),
+            ],
+            data: amount.to_le_bytes().to_vec(),
+        };
+
+        self.locks.insert(id, htlc);
+
+        Ok(vec![log])
+    }
+
+    /// Pays the open, unexpired lock `id` to its recipient.
+    pub fn claim<S: BalanceState>(
+        &mut self,
+        state: &mut S,
+        sender: Address,
+        id: Hash,
+        preimage: Vec<u8>,
+        height: u64,
+    ) -> Result<Vec<Log>, VmError> {
+        let htlc = self.open(id)?;
+
+        if htlc.is_expired(height) {
+            return Err(VmError::HtlcExpired(id));
+        }
+
+        if !htlc.unlocks(&preimage) {
+            return Err(VmError::InvalidPreimage(id));
+        }
+
+        let recipient = htlc.recipient;
+        let to_balance = credit(state, &recipient, &htlc.amount)?;
+
+        state.set_balance(recipient, to_balance);
+
+        let log = Log {
+            address: sender,
+            topics: vec![htlc_claimed_topic(), id],
+            data: preimage.clone(),
+        };
+
+        self.locks.get_mut(&id).unwrap().status = HtlcStatus::Claimed { preimage };
+
+        Ok(vec![log])
+    }
+
+    /// Returns the open, expired lock `id` to its sender.
+    pub fn refund<S: BalanceState>(
+        &mut self,
+        state: &mut S,
+        sender: Address,
+        id: Hash,
+        height: u64,
+    ) -> Result<Vec<Log>, VmError> {
+        let htlc = self.open(id)?;
+
+        if !htlc.is_expired(height) {
+            return Err(VmError::HtlcNotExpired(id));
+        }
+
+        let owner = htlc.sender;
+        let to_balance = credit(state, &owner, &htlc.amount)?;
+
+        state.set_balance(owner, to_balance);
+        self.locks.get_mut(&id).unwrap().status = HtlcStatus::Refunded;
+
+        Ok(vec![Log {
+            address: sender,
+            topics: vec![htlc_refunded_topic(), id],
+            data: vec![],
+        }])
+    }
+
+    /// Touched locks sorted by id, as stored in a block.
+    pub fn into_block_parts(self) -> Vec<Htlc> {
+        let mut locks: Vec<_> = self.locks.into_values().collect();
+        locks.sort_by(|a, b| a.id.as_slice().cmp(b.id.as_slice()));
+
+        locks
+    }
+
+    fn open(&self, id: Hash) -> Result<&Htlc, VmError> {
+        let htlc = self.locks.get(&id).ok_or(VmError::UnknownHtlc(id))?;
+
+        if !htlc.is_open() {
+            return Err(VmError::HtlcNotOpen(id));
+        }
+
+        Ok(htlc)
+    }
+}
+
+fn credit<S: BalanceState>(
+    state: &mut S,
+    addr: &Address,
+    amount: &Uint256,
+) -> Result<Uint256, VmError> {
+    state
+        .balance(addr)
+        .checked_add(amount.clone())
+        .ok_or(VmError::BalanceOverflow(*addr))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addr(id: u8) -> Address {
+        [id; 20].into()
+    }
+
+    fn u(v: u64) -> Uint256 {
+        Uint256::from(v)
+    }
+
+    fn htlc(amount: u64, timelock: u64) -> Htlc {
+        Htlc {
+            id: Hash::hash(&amount.to_le_bytes()),
+            sender: addr(1),
+            recipient: addr(2),
+            amount: u(amount),
+            hashlock: Hash::hash(b"secret"),
+            timelock,
+            status: HtlcStatus::Open,
+        }
+    }
+
+    fn locked(tokens: &mut HashMap<Address, Uint256>, ledger: &mut HtlcLedger) -> Hash {
+        let htlc = htlc(40, 10);
+        let id = htlc.id;
+
+        ledger.lock(tokens, htlc, 5).unwrap();
+
+        id
+    }
+
+    #[test]
+    fn claim_with_preimage_before_expiry() {
+        let mut tokens = HashMap::from([(addr(1), u(100))]);
+        let mut ledger = HtlcLedger::default();
+        let id = locked(&mut tokens, &mut ledger);
+
+        assert_eq!(tokens[&addr(1)], u(60));
+
+        assert_eq!(
+            ledger.claim(&mut tokens, addr(3), id, b"wrong".to_vec(), 9),
+            Err(VmError::InvalidPreimage(id))
+        );
+        assert_eq!(
+            ledger.claim(&mut tokens, addr(3), id, b"secret".to_vec(), 10),
+            Err(VmError::HtlcExpired(id))
+        );
+
+        let logs = ledger
+            .claim(&mut tokens, addr(3), id, b"secret".to_vec(), 9)
+            .unwrap();
+
+        assert_eq!(logs[0].data, b"secret");
+        assert_eq!(tokens[&addr(2)], u(40));
+        assert_eq!(
+            ledger.locks[&id].status,
+            HtlcStatus::Claimed {
+                preimage: b"secret".to_vec()
+            }
+        );
+
+        assert_eq!(
+            ledger.refund(&mut tokens, addr(1), id, 20),
+            Err(VmError::HtlcNotOpen(id))
+        );
+    }
+
+    #[test]
+    fn refund_only_after_expiry() {
+        let mut tokens = HashMap::from([(addr(1), u(100))]);
+        let mut ledger = HtlcLedger::default();
+        let id = locked(&mut tokens, &mut ledger);
+
+        assert_eq!(
+            ledger.refund(&mut tokens, addr(1), id, 9),
+            Err(VmError::HtlcNotExpired(id))
+        );
+
+        ledger.refund(&mut tokens, addr(1), id, 10).unwrap();
+
+        assert_eq!(tokens[&addr(1)], u(100));
+        assert_eq!(ledger.locks[&id].status, HtlcStatus::Refunded);
+    }
+
+    #[test]
+    fn lock_rejects_past_timelock_and_missing_funds() {
+        let mut tokens = HashMap::from([(addr(1), u(10))]);
+        let mut ledger = HtlcLedger::default();
+
+        assert_eq!(
+            ledger.lock(&mut tokens, htlc(5, 5), 5),
+            Err(VmError::InvalidTimelock {
+                timelock: 5,
+                height: 5
+            })
+        );
+        assert!(matches!(
+            ledger.lock(&mut tokens, htlc(40, 10), 5),
+            Err(VmError::InsufficientBalance { .. })
+        ));
+
+        assert!(ledger.locks.is_empty());
+        assert_eq!(tokens[&addr(1)], u(10));
+    }
+}
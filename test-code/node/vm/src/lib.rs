@@ -1,8 +1,33 @@
-use std::collections::HashMap;
-
-use rm_reth_types::{Address, int::Uint256, tx::transaction::Transaction};
+pub mod asset;
+pub mod error;
+pub mod execute;
+pub mod gas;
+pub mod htlc;
+pub mod multisig;
+pub mod parallel;
+pub mod simulate;
+pub mod staking;
+pub mod vesting;
+
+use std::collections::{HashMap, HashSet};
+
+use rm_reth_types::{
+    Address,
+    int::Uint256,
+    tx::{kind::TxKind, receipt::Receipt, transaction::Transaction},
+};
 use storage::{StorageManager, TableId, error::StorageError};
 
+use crate::{
+    asset::AssetLedger,
+    execute::{TxEnv, apply_tx, receipt, touched_accounts},
+    htlc::HtlcLedger,
+    multisig::MultisigLedger,
+    parallel::ParallelExecutor,
+    staking::StakingLedger,
+    vesting::VestingLedger,
+};
+
 enum State {
     Initial,
     Processed,
@@ -13,6 +38,16 @@ pub struct VmPool<'a> {
     state: State,
     pub tx_pool: Vec<Transaction>,
     pub tokens: HashMap<Address, Uint256>,
+    pub receipts: Vec<Receipt>,
+    pub assets: AssetLedger,
+    /// Senders of the block's mint transactions that are allowed to mint.
+    pub minters: HashSet<Address>,
+    pub htlcs: HtlcLedger,
+    pub vesting: VestingLedger,
+    pub staking: StakingLedger,
+    pub multisig: MultisigLedger,
+    /// Height of the block being built, compared against HTLC timelocks.
+    pub block_height: u64,
 }
 
 impl<'a> VmPool<'a> {
@@ -20,9 +55,27 @@ impl<'a> VmPool<'a> {
         storage: &'a StorageManager,
         tx_pool: &[Transaction],
     ) -> Result<Self, StorageError> {
+        let snapshot = storage.snapshot()?;
+        let assets = AssetLedger::load(&snapshot, tx_pool)?;
+        let htlcs = HtlcLedger::load(&snapshot, tx_pool)?;
+        let vesting = VestingLedger::load(&snapshot, tx_pool)?;
+        let staking = StakingLedger::load(&snapshot, tx_pool)?;
+        let multisig = MultisigLedger::load(&snapshot, tx_pool)?;
+
+        let mut minters = HashSet::new();
+        for tx in tx_pool {
+            if matches!(tx.kind(), Ok(TxKind::Mint)) && snapshot.is_minter(&tx.from)? {
+                minters.insert(tx.from);
+            }
+        }
+
         let balance_db = storage.get_ref(TableId::Balance).to_balance();
 
-        let addresss_list: Vec<Address> = tx_pool.iter().flat_map(|tx| [tx.from, tx.to]).collect();
+        let addresss_list: Vec<Address> = tx_pool
+            .iter()
+            .flat_map(touched_accounts)
+            .chain(htlcs.accounts())
+            .collect();
 
         let balance_map = balance_db
             .multi_get_or_default(&addresss_list)
@@ -36,53 +89,69 @@ impl<'a> VmPool<'a> {
             state: State::Initial,
             tx_pool: tx_pool.iter().cloned().collect(),
             tokens: balance_map,
+            receipts: Vec::with_capacity(tx_pool.len()),
+            assets,
+            minters,
+            htlcs,
+            vesting,
+            staking,
+            multisig,
+            block_height: 0,
         })
     }
 
-    pub fn process_tx(&mut self, tx_pool: &[Transaction]) {
-        match self.state {
-            State::Initial => {
-                for tx in tx_pool.into_iter() {
-                    if tx.from != tx.to {
-                        // check vaild tx
-                        let from_balance = match self
-                            .tokens
-                            .get(&tx.from)
-                            .unwrap()
-                            .clone()
-                            .checked_sub(tx.amount.clone())
-                        {
-                            Some(from_balance) => from_balance,
-                            None => continue,
-                        };
-
-                        let to_balance = match self
-                            .tokens
-                            .get(&tx.to)
-                            .unwrap()
-                            .clone()
-                            .checked_add(tx.amount.clone())
-                        {
-                            Some(to_balance) => to_balance,
-                            None => continue,
-                        };
-
-                        // update balances
-                        self.tokens.insert(tx.from, from_balance);
-                        self.tokens.insert(tx.to, to_balance);
-                    } else {
-                        // check vaild tx
-                        let balance = self.tokens.get(&tx.from).unwrap().clone();
-
-                        if balance >= tx.amount {
-                            self.tokens.insert(tx.from, balance);
-                        }
-                    }
-                }
+    /// Sets the height of the block the transactions are executed in.
+    pub fn at_height(mut self, block_height: u64) -> Self {
+        self.block_height = block_height;
+        self
+    }
 
-                self.state = State::Processed;
+    pub fn process_tx(&mut self, tx_pool: &[Transaction]) {
+        if let State::Initial = self.state {
+            let mut env = TxEnv {
+                assets: &mut self.assets,
+                minters: &self.minters,
+                htlcs: &mut self.htlcs,
+                vesting: &mut self.vesting,
+                staking: &mut self.staking,
+                multisig: &mut self.multisig,
+                height: self.block_height,
+                index: 0,
+            };
+
+            for (index, tx) in tx_pool.iter().enumerate() {
+                env.index = index as u32;
+
+                // failed transactions are skipped, but still get a receipt
+                let result = apply_tx(&mut self.tokens, &mut env, tx);
+                self.receipts.push(receipt(tx, result));
             }
-            _ => {}
+
+            self.state = State::Processed;
+        }
+    }
+
+    /// Same as [`VmPool::process_tx`], but runs the transactions on `executor`.
+    ///
+    /// The executor only runs plain transfers and knows nothing about vesting or
+    /// multisig accounts, so blocks containing other kinds, senders with locked
+    /// balances or registered multisig senders are processed sequentially.
+    pub fn process_tx_parallel(&mut self, tx_pool: &[Transaction], executor: &ParallelExecutor) {
+        let locked = |tx: &Transaction| !self.vesting.locked(&tx.from, self.block_height).is_zero();
+
+        if !tx_pool.iter().all(Transaction::is_transfer)
+            || tx_pool.iter().any(locked)
+            || !self.multisig.accounts.is_empty()
+        {
+            return self.process_tx(tx_pool);
+        }
+
+        if let State::Initial = self.state {
+            let outcome = executor.execute(&self.tokens, tx_pool);
+
+            self.tokens = outcome.tokens;
+            self.receipts = outcome.receipts;
+            self.state = State::Processed;
         }
     }
 
@@ -91,6 +160,46 @@ impl<'a> VmPool<'a> {
 
         balance_db.multi_insert(self.tokens.iter().map(|(k, v)| (k, v)))?;
 
+        let asset_db = self.storage.get_ref(TableId::Asset).to_asset();
+        asset_db.multi_insert(self.assets.metadata.iter())?;
+
+        let asset_balance_db = self
+            .storage
+            .get_ref(TableId::AssetBalance)
+            .to_asset_balance();
+        asset_balance_db.multi_insert(self.assets.balances.iter())?;
+
+        let htlc_db = self.storage.get_ref(TableId::Htlc).to_htlc();
+        htlc_db.multi_insert(self.htlcs.locks.iter())?;
+
+        let vesting_db = self.storage.get_ref(TableId::Vesting).to_vesting();
+        vesting_db.multi_insert(self.vesting.schedules.iter())?;
+
+        let vesting_offer_db = self
+            .storage
+            .get_ref(TableId::VestingOffer)
+            .to_vesting_offer();
+        vesting_offer_db.multi_insert(self.vesting.offers.iter())?;
+
+        let validator_db = self.storage.get_ref(TableId::Validator).to_validator();
+        validator_db.multi_insert(self.staking.validators.iter())?;
+
+        let multisig_db = self.storage.get_ref(TableId::Multisig).to_multisig();
+        multisig_db.multi_insert(
+            self.multisig
+                .accounts
+                .iter()
+                .map(|(addr, record)| (addr, &record.account)),
+        )?;
+
+        let nonce_db = self.storage.get_ref(TableId::Nonce).to_nonce();
+        nonce_db.multi_insert(
+            self.multisig
+                .accounts
+                .iter()
+                .map(|(addr, record)| (addr, &record.nonce)),
+        )?;
+
         Ok(())
     }
 }
@@ -184,4 +293,81 @@ mod tests {
 
         assert_eq!(pool.tokens.get(&a3).cloned(), Some(u(0)));
     }
+
+    #[test]
+    fn process_tx_parallel_matches_process_tx() {
+        STORAGE.init_table().unwrap();
+
+        let a1 = addr(1);
+        let a2 = addr(2);
+        let a3 = addr(3);
+
+        STORAGE
+            .balance_insert_items([(a1, u(50)), (a2, u(0))].iter().map(|(k, v)| (k, v)))
+            .unwrap();
+
+        let txs = vec![
+            tx(a1, a2, 40),
+            tx(a1, a3, 20),
+            tx(a2, a1, 20),
+            tx(a3, a3, 0),
+        ];
+
+        let mut sequential = VmPool::from_tx_pool(&STORAGE, &txs).unwrap();
+        sequential.process_tx(&txs);
+
+        let mut parallel = VmPool::from_tx_pool(&STORAGE, &txs).unwrap();
+        parallel.process_tx_parallel(&txs, &ParallelExecutor::new(4));
+
+        assert_eq!(parallel.tokens, sequential.tokens);
+        assert_eq!(parallel.receipts, sequential.receipts);
+    }
+
+    #[test]
+    fn process_tx_parallel_matches_process_tx_on_random_blocks() {
+        use crate::gas::{MAX_TX_GAS, TX_DATA_BYTE_GAS};
+        use rand::{Rng, SeedableRng, rngs::StdRng};
+
+        let mut rng = StdRng::seed_from_u64(11);
+        let oversized = (MAX_TX_GAS / TX_DATA_BYTE_GAS + 1) as usize;
+
+        for _ in 0..20 {
+            let storage = StorageManager::in_memory().unwrap();
+            let addrs: Vec<Address> = (0..rng.random_range(2..8)).map(addr).collect();
+
+            let balances: Vec<_> = addrs
+                .iter()
+                .map(|a| (*a, u(rng.random_range(0..200))))
+                .collect();
+            storage
+                .balance_insert_items(balances.iter().map(|(k, v)| (k, v)))
+                .unwrap();
+
+            let txs: Vec<_> = (0..rng.random_range(1..40))
+                .map(|_| {
+                    let mut tx = tx(
+                        addrs[rng.random_range(0..addrs.len())],
+                        addrs[rng.random_range(0..addrs.len())],
+                        rng.random_range(0..100),
+                    );
+
+                    // over the gas limit, rejected before touching any balance
+                    if rng.random_bool(0.1) {
+                        tx.data = vec![0; oversized];
+                    }
+
+                    tx
+                })
+                .collect();
+
+            let mut sequential = VmPool::from_tx_pool(&storage, &txs).unwrap();
+            sequential.process_tx(&txs);
+
+            let mut parallel = VmPool::from_tx_pool(&storage, &txs).unwrap();
+            parallel.process_tx_parallel(&txs, &ParallelExecutor::new(rng.random_range(1..8)));
+
+            assert_eq!(parallel.tokens, sequential.tokens);
+            assert_eq!(parallel.receipts, sequential.receipts);
+        }
+    }
 }
@@ -0,0 +1,176 @@
+use std::collections::{HashMap, hash_map::Entry};
+
+use rm_reth_types::{
+    Address,
+    hash::Hash,
+    log::Log,
+    multisig::{MultisigAccount, MultisigError, MultisigRecord},
+    tx::{kind::TxKind, transaction::Transaction},
+};
+use storage::{Snapshot, error::StorageError};
+
+use crate::{
+    error::VmError,
+    execute::{BalanceState, transfer_log, verify_witness},
+};
+
+pub fn multisig_created_topic() -> Hash {
+    Hash::hash(b"MultisigCreated(address,uint8)")
+}
+
+/// Registered multisig accounts a set of transactions sends from or creates.
+#[derive(Debug, Clone, Default)]
+pub struct MultisigLedger {
+    pub accounts: HashMap<Address, MultisigRecord>,
+}
+
+impl MultisigLedger {
+    /// Loads the registry entry, with its nonce, of every sender of `txs` and of
+    /// every account they create.
+    pub fn load(snapshot: &Snapshot<'_>, txs: &[Transaction]) -> Result<Self, StorageError> {
+        let mut ledger = Self::default();
+
+        for tx in txs {
+            let created = match tx.kind() {
+                Ok(TxKind::MultisigCreate { account }) => Some(account.address()),
+                _ => None,
+            };
+
+            for addr in std::iter::once(tx.from).chain(created) {
+                if let Entry::Vacant(entry) = ledger.accounts.entry(addr)
+                    && let Some(account) = snapshot.multisig(&addr)?
+                {
+                    entry.insert(MultisigRecord {
+                        account,
+                        nonce: snapshot.nonce(&addr)?,
+                    });
+                }
+            }
+        }
+
+        Ok(ledger)
+    }
+
+    #[inline]
+    pub fn is_registered(&self, addr: &Address) -> bool {
+        self.accounts.contains_key(addr)
+    }
+
+    /// Nonce the next witness of `addr` has to carry. Accounts are registered by
+    /// their first spend at the latest, so unregistered ones are at zero.
+    #[inline]
+    pub fn nonce(&self, addr: &Address) -> u64 {
+        self.accounts
+            .get(addr)
+            .map(|record| record.nonce)
+            .unwrap_or_default()
+    }
+
+    /// Rejects `tx` unless its sender may send it now: registered accounts need a
+    /// witness at their current nonce, and any witness has to be valid.
+    pub fn authorize(&self, tx: &Transaction) -> Result<(), VmError> {
+        self.check_witness(tx, |expected, got| got == expected)
+    }
+
+    /// Same as [`MultisigLedger::authorize`], but accepts witnesses for later
+    /// nonces, so an account can have several transactions waiting in the mempool.
+    pub fn authorize_pending(&self, tx: &Transaction) -> Result<(), VmError> {
+        self.check_witness(tx, |expected, got| got >= expected)
+    }
+
+    fn check_witness(
+        &self,
+        tx: &Transaction,
+        nonce_ok: impl FnOnce(u64, u64) -> bool,
+    ) -> Result<(), VmError> {
+        let Some(witness) = verify_witness(tx)? else {
+            if self.is_registered(&tx.from) {
+                return Err(MultisigError::MissingWitness(tx.from).into());
+            }
+
+            return Ok(());
+        };
+
+        let expected = self.nonce(&tx.from);
+
+        if !nonce_ok(expected, witness.nonce) {
+            return Err(MultisigError::InvalidNonce {
+                expected,
+                got: witness.nonce,
+            }
+            .into());
+        }
+
+        Ok(())
+    }
+
+    /// Advances the nonce of the sender of `tx` once it was applied, registering
+    /// the account on its first witnessed spend.
+    pub fn record_spend(&mut self, tx: &Transaction) {
+        if let Ok(Some(witness)) = tx.witness() {
+            self.accounts
+                .entry(tx.from)
+                .or_insert(MultisigRecord {
+                    account: witness.account,
+                    nonce: 0,
+                })
+                .nonce += 1;
+        }
+    }
+
+    /// Registers `account` and moves `tx.amount` into it.
+    ///
+    /// Registering an account twice only funds it, as its address is derived from
+    /// its keys and threshold.
+    pub fn create<S: BalanceState>(
+        &mut self,
+        state: &mut S,
+        tx: &Transaction,
+        account: MultisigAccount,
+    ) -> Result<Vec<Log>, VmError> {
+        account.validate()?;
+
+        let address = account.address();
+        let available = state.balance(&tx.from);
+
+        let from_balance = available.clone().checked_sub(tx.amount.clone()).ok_or(
+            VmError::InsufficientBalance {
+                required: tx.amount.clone(),
+                available,
+            },
+        )?;
+
+        if address != tx.from {
+            let to_balance = state
+                .balance(&address)
+                .checked_add(tx.amount.clone())
+                .ok_or(VmError::BalanceOverflow(address))?;
+
+            state.set_balance(tx.from, from_balance);
+            state.set_balance(address, to_balance);
+        }
+
+        let threshold = account.threshold;
+
+        self.accounts
+            .entry(address)
+            .or_insert(MultisigRecord { account, nonce: 0 });
+
+        Ok(vec![
+            transfer_log(tx.from, &tx.from, &address, &tx.amount),
+            Log {
+                address: tx.from,
+                topics: vec![multisig_created_topic(), Log::address_topic(&address)],
+                data: vec![threshold],
+            },
+        ])
+    }
+
+    /// Loaded entries sorted by address, as stored in a block.
+    pub fn into_block_parts(self) -> Vec<MultisigRecord> {
+        let mut records: Vec<_> = self.accounts.into_values().collect();
+        records.sort_by_key(MultisigRecord::address);
+
+        records
+    }
+}
@@ -0,0 +1,506 @@
+//! Optimistic parallel transaction execution (Block-STM style).
+//!
+//! Pending transactions are split into contiguous chunks, one per worker. A worker
+//! executes its chunk in order, so a transaction sees the writes of lower-indexed
+//! transactions of the same chunk directly, and all other writes through a
+//! multi-version memory filled by previous waves, minus the writes of the pending
+//! transactions. Every execution records the
+//! version of each address it read; after a wave, transactions whose reads no longer
+//! match the latest lower-indexed write are scheduled for re-execution.
+//!
+//! The lowest pending transaction of a wave only depends on final transactions, so it
+//! is final after that wave. The loop therefore ends after at most `txs.len()` waves
+//! and the result matches sequential execution.
+//!
+//! Waves are only spread over threads when every worker gets at least
+//! [`MIN_CHUNK`] transactions, and blocks too small to split run sequentially. A
+//! wave that would re-execute most of the rest of the block runs as one chunk
+//! instead: as the readers of every pending transaction are pending too, it only
+//! reads final writes, so it is the last wave.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    thread,
+};
+
+use rm_reth_types::{
+    Address,
+    int::Uint256,
+    log::Log,
+    tx::{receipt::Receipt, transaction::Transaction},
+};
+
+use crate::{
+    error::VmError,
+    execute::{BalanceState, apply_transfer, receipt, verify_witness},
+    gas::check_limits,
+};
+
+/// Fewest transactions worth a thread of their own, unless set with
+/// [`ParallelExecutor::with_min_chunk`].
+pub const MIN_CHUNK: usize = 128;
+
+/// Origin of a value read by a transaction: `None` for the pre-block state,
+/// otherwise the `(tx index, incarnation)` that wrote it.
+type Version = Option<(usize, u32)>;
+
+/// Addresses a transaction read, with the version it saw. A transaction only
+/// touches a handful of addresses, so a `Vec` beats hashing them.
+type ReadSet = Vec<(Address, Version)>;
+
+/// Balances written by each transaction, keyed by address then by tx index.
+#[derive(Default)]
+struct MvMemory {
+    data: HashMap<Address, BTreeMap<usize, (u32, Uint256)>>,
+}
+
+impl MvMemory {
+    fn version(&self, addr: &Address, tx_idx: usize) -> Version {
+        self.data
+            .get(addr)?
+            .range(..tx_idx)
+            .next_back()
+            .map(|(idx, (incarnation, _))| (*idx, *incarnation))
+    }
+
+    fn remove(&mut self, addr: &Address, tx_idx: usize) {
+        if let Some(entries) = self.data.get_mut(addr) {
+            entries.remove(&tx_idx);
+        }
+    }
+
+    fn write(&mut self, addr: Address, tx_idx: usize, incarnation: u32, value: Uint256) {
+        self.data
+            .entry(addr)
+            .or_default()
+            .insert(tx_idx, (incarnation, value));
+    }
+}
+
+/// State view of one worker while it executes its chunk.
+struct ChunkView<'a> {
+    base: &'a HashMap<Address, Uint256>,
+    memory: &'a MvMemory,
+    incarnations: &'a [u32],
+    /// Writes of the transactions of this chunk executed so far.
+    local: HashMap<Address, BTreeMap<usize, Uint256>>,
+    tx_idx: usize,
+    reads: ReadSet,
+    writes: Vec<(Address, Uint256)>,
+}
+
+impl ChunkView<'_> {
+    fn read(&self, addr: &Address) -> (Version, Uint256) {
+        let local = self
+            .local
+            .get(addr)
+            .and_then(|entries| entries.range(..self.tx_idx).next_back())
+            .map(|(idx, value)| (*idx, self.incarnations[*idx] + 1, value));
+
+        let shared = self
+            .memory
+            .data
+            .get(addr)
+            .and_then(|entries| entries.range(..self.tx_idx).next_back())
+            .map(|(idx, (incarnation, value))| (*idx, *incarnation, value));
+
+        let latest = match (local, shared) {
+            (Some(l), Some(s)) => Some(if l.0 > s.0 { l } else { s }),
+            (l, s) => l.or(s),
+        };
+
+        match latest {
+            Some((idx, incarnation, value)) => (Some((idx, incarnation)), value.clone()),
+            None => (None, self.base.get(addr).cloned().unwrap_or_default()),
+        }
+    }
+
+    fn execute(&mut self, tx_idx: usize, tx: &Transaction) -> (Execution, TxResult) {
+        self.tx_idx = tx_idx;
+
+        // same stateless checks as `apply_tx`, so receipts match sequential execution
+        let result = check_limits(tx)
+            .and_then(|()| verify_witness(tx))
+            .and_then(|_| apply_transfer(self, tx));
+
+        let reads = std::mem::take(&mut self.reads);
+        let mut writes = std::mem::take(&mut self.writes);
+
+        // a skipped tx must not leave partial writes behind
+        if result.is_err() {
+            writes.clear();
+        }
+
+        for (addr, value) in &writes {
+            self.local
+                .entry(*addr)
+                .or_default()
+                .insert(tx_idx, value.clone());
+        }
+
+        let execution = Execution {
+            tx_idx,
+            reads,
+            writes,
+        };
+
+        (execution, result)
+    }
+}
+
+impl BalanceState for ChunkView<'_> {
+    fn balance(&mut self, addr: &Address) -> Uint256 {
+        if let Some((_, value)) = self.writes.iter().find(|(written, _)| written == addr) {
+            return value.clone();
+        }
+
+        let (version, value) = self.read(addr);
+
+        if !self.reads.iter().any(|(read, _)| read == addr) {
+            self.reads.push((*addr, version));
+        }
+
+        value
+    }
+
+    fn set_balance(&mut self, addr: Address, amount: Uint256) {
+        match self.writes.iter_mut().find(|(written, _)| *written == addr) {
+            Some((_, value)) => *value = amount,
+            None => self.writes.push((addr, amount)),
+        }
+    }
+}
+
+type TxResult = Result<Vec<Log>, VmError>;
+
+struct Execution {
+    tx_idx: usize,
+    reads: ReadSet,
+    writes: Vec<(Address, Uint256)>,
+}
+
+/// Result of [`ParallelExecutor::execute`].
+#[derive(Debug)]
+pub struct ParallelOutcome {
+    /// Base balances updated with the final writes of the block.
+    pub tokens: HashMap<Address, Uint256>,
+    /// One receipt per transaction, in block order.
+    pub receipts: Vec<Receipt>,
+    /// Number of execution waves that were needed.
+    pub waves: usize,
+    /// Total number of executions, including re-executions.
+    pub executions: usize,
+}
+
+pub struct ParallelExecutor {
+    workers: usize,
+    min_chunk: usize,
+}
+
+impl ParallelExecutor {
+    pub fn new(workers: usize) -> Self {
+        Self {
+            workers: workers.max(1),
+            min_chunk: MIN_CHUNK,
+        }
+    }
+
+    /// Sets the fewest transactions a worker thread is started for.
+    pub fn with_min_chunk(mut self, min_chunk: usize) -> Self {
+        self.min_chunk = min_chunk.max(1);
+        self
+    }
+
+    pub fn with_available_parallelism() -> Self {
+        Self::new(thread::available_parallelism().map_or(1, |n| n.get()))
+    }
+
+    #[inline]
+    pub fn workers(&self) -> usize {
+        self.workers
+    }
+
+    /// Whether `len` transactions are spread over more than one worker.
+    #[inline]
+    pub fn splits(&self, len: usize) -> bool {
+        self.chunk_size(len) < len
+    }
+
+    fn chunk_size(&self, len: usize) -> usize {
+        len.div_ceil(self.workers).max(self.min_chunk)
+    }
+
+    pub fn execute(
+        &self,
+        base: &HashMap<Address, Uint256>,
+        txs: &[Transaction],
+    ) -> ParallelOutcome {
+        if !self.splits(txs.len()) {
+            return sequential(base, txs);
+        }
+
+        let mut memory = MvMemory::default();
+        let mut incarnations = vec![0u32; txs.len()];
+        let mut read_sets: Vec<ReadSet> = vec![Vec::new(); txs.len()];
+        let mut write_sets: Vec<Vec<Address>> = vec![Vec::new(); txs.len()];
+        let mut results: Vec<Option<TxResult>> = vec![None; txs.len()];
+
+        let mut pending: Vec<usize> = (0..txs.len()).collect();
+        let mut waves = 0;
+        let mut executions = 0;
+        let mut last_wave = false;
+
+        while let Some(&lowest) = pending.first() {
+            waves += 1;
+            executions += pending.len();
+
+            let chunk_size = match last_wave {
+                true => pending.len(),
+                false => self.chunk_size(pending.len()),
+            };
+
+            // the pending txs write again, other readers must not see their old writes
+            for &tx_idx in &pending {
+                for addr in write_sets[tx_idx].drain(..) {
+                    memory.remove(&addr, tx_idx);
+                }
+            }
+
+            let wave = self.run_wave(base, &memory, &incarnations, txs, &pending, chunk_size);
+
+            for (execution, result) in wave {
+                let Execution {
+                    tx_idx,
+                    reads,
+                    writes,
+                } = execution;
+
+                incarnations[tx_idx] += 1;
+
+                for (addr, value) in writes {
+                    memory.write(addr, tx_idx, incarnations[tx_idx], value);
+                    write_sets[tx_idx].push(addr);
+                }
+
+                read_sets[tx_idx] = reads;
+                results[tx_idx] = Some(result);
+            }
+
+            // everything below the lowest pending tx was final before this wave, and
+            // readers of a pending tx are stale once it runs again
+            let mut stale = vec![false; txs.len()];
+            pending.clear();
+
+            for tx_idx in lowest..txs.len() {
+                stale[tx_idx] = read_sets[tx_idx].iter().any(|(addr, version)| {
+                    version.is_some_and(|(idx, _)| stale[idx])
+                        || memory.version(addr, tx_idx) != *version
+                });
+
+                if stale[tx_idx] {
+                    pending.push(tx_idx);
+                }
+            }
+
+            // the other txs are final, so one chunk of the pending ones is the last
+            // wave, cheaper than waves re-executing most of the block in parallel
+            last_wave = pending
+                .first()
+                .is_some_and(|&lowest| pending.len() * 2 > txs.len() - lowest);
+        }
+
+        let mut tokens = base.clone();
+
+        for (addr, entries) in memory.data {
+            if let Some((_, (_, value))) = entries.into_iter().next_back() {
+                tokens.insert(addr, value);
+            }
+        }
+
+        let receipts = txs
+            .iter()
+            .zip(results)
+            .map(|(tx, result)| receipt(tx, result.expect("every tx is executed at least once")))
+            .collect();
+
+        ParallelOutcome {
+            tokens,
+            receipts,
+            waves,
+            executions,
+        }
+    }
+
+    fn run_wave(
+        &self,
+        base: &HashMap<Address, Uint256>,
+        memory: &MvMemory,
+        incarnations: &[u32],
+        txs: &[Transaction],
+        pending: &[usize],
+        chunk_size: usize,
+    ) -> Vec<(Execution, TxResult)> {
+        let execute_chunk = |chunk: &[usize]| {
+            let mut view = ChunkView {
+                base,
+                memory,
+                incarnations,
+                local: HashMap::new(),
+                tx_idx: 0,
+                reads: Vec::new(),
+                writes: Vec::new(),
+            };
+
+            chunk
+                .iter()
+                .map(|&tx_idx| view.execute(tx_idx, &txs[tx_idx]))
+                .collect::<Vec<_>>()
+        };
+
+        if chunk_size >= pending.len() {
+            return execute_chunk(pending);
+        }
+
+        thread::scope(|scope| {
+            let handles: Vec<_> = pending
+                .chunks(chunk_size)
+                .map(|chunk| scope.spawn(move || execute_chunk(chunk)))
+                .collect();
+
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().expect("parallel executor worker panicked"))
+                .collect()
+        })
+    }
+}
+
+fn sequential(base: &HashMap<Address, Uint256>, txs: &[Transaction]) -> ParallelOutcome {
+    let mut tokens = base.clone();
+
+    let receipts = txs
+        .iter()
+        .map(|tx| {
+            let result = check_limits(tx)
+                .and_then(|()| verify_witness(tx))
+                .and_then(|_| apply_transfer(&mut tokens, tx));
+            receipt(tx, result)
+        })
+        .collect();
+
+    ParallelOutcome {
+        tokens,
+        receipts,
+        waves: 1,
+        executions: txs.len(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+
+    fn addr(id: u8) -> Address {
+        [id; 20].into()
+    }
+
+    fn tx(from: Address, to: Address, amount: u64) -> Transaction {
+        Transaction::new(from, to, Uint256::from(amount), vec![])
+    }
+
+    fn sequential(
+        base: &HashMap<Address, Uint256>,
+        txs: &[Transaction],
+    ) -> (HashMap<Address, Uint256>, Vec<Receipt>) {
+        let mut tokens = base.clone();
+        let receipts = txs
+            .iter()
+            .map(|tx| receipt(tx, apply_transfer(&mut tokens, tx)))
+            .collect();
+        (tokens, receipts)
+    }
+
+    #[test]
+    fn independent_transfers_finish_in_one_wave() {
+        let base: HashMap<_, _> = (1..=8).map(|id| (addr(id), Uint256::from(100))).collect();
+        let txs: Vec<_> = (1..=4).map(|id| tx(addr(id), addr(id + 4), 10)).collect();
+
+        let outcome = ParallelExecutor::new(4)
+            .with_min_chunk(1)
+            .execute(&base, &txs);
+
+        assert_eq!(outcome.waves, 1);
+        assert_eq!(outcome.executions, txs.len());
+        assert_eq!(outcome.tokens, sequential(&base, &txs).0);
+    }
+
+    #[test]
+    fn single_worker_runs_one_wave() {
+        let base: HashMap<_, _> = [(addr(1), Uint256::from(50))].into_iter().collect();
+        let txs = vec![tx(addr(1), addr(2), 40), tx(addr(2), addr(1), 20)];
+
+        let outcome = ParallelExecutor::new(1).execute(&base, &txs);
+
+        assert_eq!(outcome.waves, 1);
+        assert_eq!(outcome.tokens, sequential(&base, &txs).0);
+    }
+
+    #[test]
+    fn dependent_transfers_are_re_executed() {
+        let base: HashMap<_, _> = [(addr(1), Uint256::from(50)), (addr(2), Uint256::zero())]
+            .into_iter()
+            .collect();
+
+        // tx 1 is skipped and tx 2 only succeeds after observing tx 0
+        let txs = vec![
+            tx(addr(1), addr(2), 40),
+            tx(addr(1), addr(3), 20),
+            tx(addr(2), addr(1), 20),
+        ];
+
+        let outcome = ParallelExecutor::new(3)
+            .with_min_chunk(1)
+            .execute(&base, &txs);
+        let (expected, receipts) = sequential(&base, &txs);
+
+        assert!(outcome.waves > 1);
+        assert_eq!(outcome.receipts, receipts);
+        assert!(!outcome.receipts[1].success);
+        assert_eq!(outcome.tokens, expected);
+        assert_eq!(outcome.tokens.get(&addr(1)), Some(&Uint256::from(30)));
+    }
+
+    #[test]
+    fn matches_sequential_execution_on_random_blocks() {
+        let mut rng = StdRng::seed_from_u64(7);
+
+        for _ in 0..50 {
+            let addrs: Vec<Address> = (0..rng.random_range(2..10)).map(addr).collect();
+
+            let base: HashMap<_, _> = addrs
+                .iter()
+                .map(|a| (*a, Uint256::from(rng.random_range(0..200u64))))
+                .collect();
+
+            let txs: Vec<_> = (0..rng.random_range(1..100))
+                .map(|_| {
+                    tx(
+                        addrs[rng.random_range(0..addrs.len())],
+                        addrs[rng.random_range(0..addrs.len())],
+                        rng.random_range(0..100),
+                    )
+                })
+                .collect();
+
+            let outcome = ParallelExecutor::new(rng.random_range(1..8))
+                .with_min_chunk(rng.random_range(1..4))
+                .execute(&base, &txs);
+            let (expected, receipts) = sequential(&base, &txs);
+
+            assert_eq!(outcome.tokens, expected);
+            assert_eq!(outcome.receipts, receipts);
+            assert!(outcome.waves <= txs.len());
+        }
+    }
+}
@@ -0,0 +1,273 @@
+use std::collections::{HashMap, HashSet};
+
+use rm_reth_types::{
+    Address, asset::AssetId, int::Uint256, log::Log, tx::transaction::Transaction,
+};
+use storage::{Snapshot, error::StorageError};
+
+use crate::{
+    asset::AssetLedger,
+    error::VmError,
+    execute::{TxEnv, apply_tx, touched_accounts},
+    gas::intrinsic_gas,
+    htlc::HtlcLedger,
+    multisig::MultisigLedger,
+    staking::StakingLedger,
+    vesting::VestingLedger,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BalanceChange {
+    pub addr: Address,
+    /// `None` for the native token.
+    pub asset: Option<AssetId>,
+    pub before: Uint256,
+    pub after: Uint256,
+}
+
+/// Outcome of executing a transaction without persisting it.
+#[derive(Debug, Clone)]
+pub struct Simulation {
+    pub gas_used: u64,
+    /// Current nonce of the sender in the snapshot.
+    pub nonce: u64,
+    /// Balances that would change, native first, then sorted by asset and address.
+    pub balance_changes: Vec<BalanceChange>,
+    pub logs: Vec<Log>,
+    pub failure: Option<VmError>,
+}
+
+impl Simulation {
+    #[inline]
+    pub fn success(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+/// Executes `tx` against `snapshot` as if it was included at block `height`.
+/// Nothing is written to storage.
+pub fn simulate(
+    snapshot: &Snapshot<'_>,
+    tx: &Transaction,
+    height: u64,
+) -> Result<Simulation, StorageError> {
+    let txs = std::slice::from_ref(tx);
+
+    let assets_before = AssetLedger::load(snapshot, txs)?;
+    let mut assets = assets_before.clone();
+    let mut htlcs = HtlcLedger::load(snapshot, txs)?;
+    let mut vesting = VestingLedger::load(snapshot, txs)?;
+    let mut staking = StakingLedger::load(snapshot, txs)?;
+    let mut multisig = MultisigLedger::load(snapshot, txs)?;
+
+    let mut before = HashMap::new();
+
+    for addr in touched_accounts(tx).into_iter().chain(htlcs.accounts()) {
+        before.insert(addr, snapshot.balance(&addr)?);
+    }
+
+    let mut tokens = before.clone();
+
+    let mut minters = HashSet::new();
+    if snapshot.is_minter(&tx.from)? {
+        minters.insert(tx.from);
+    }
+
+    let mut env = TxEnv {
+        assets: &mut assets,
+        minters: &minters,
+        htlcs: &mut htlcs,
+        vesting: &mut vesting,
+        staking: &mut staking,
+        multisig: &mut multisig,
+        height,
+        // as the first transaction of the block
+        index: 0,
+    };
+
+    let (logs, failure) = match apply_tx(&mut tokens, &mut env, tx) {
+        Ok(logs) => (logs, None),
+        Err(e) => (vec![], Some(e)),
+    };
+
+    let native = tokens
+        .into_iter()
+        .filter(|(addr, after)| before[addr] != *after)
+        .map(|(addr, after)| BalanceChange {
+            addr,
+            asset: None,
+            before: before[&addr].clone(),
+            after,
+        });
+
+    let asset = assets
+        .balances
+        .into_iter()
+        .map(|((addr, asset), after)| BalanceChange {
+            addr,
+            asset: Some(asset),
+            before: assets_before.balance(&addr, &asset),
+            after,
+        })
+        .filter(|change| change.before != change.after);
+
+    let mut balance_changes: Vec<BalanceChange> = native.chain(asset).collect();
+
+    balance_changes.sort_by_key(|change| (change.asset, change.addr));
+
+    Ok(Simulation {
+        gas_used: intrinsic_gas(tx),
+        nonce: snapshot.nonce(&tx.from)?,
+        balance_changes,
+        logs,
+        failure,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rm_reth_types::asset::{AssetMetadata, AssetOp};
+    use storage::{StorageManager, TableId};
+
+    fn addr(id: u8) -> Address {
+        [id; 20].into()
+    }
+
+    fn storage_with(balances: &[(Address, u64)]) -> StorageManager {
+        let storage = StorageManager::in_memory().unwrap();
+
+        let balances: Vec<_> = balances
+            .iter()
+            .map(|(addr, amount)| (*addr, Uint256::from(*amount)))
+            .collect();
+
+        storage
+            .balance_insert_items(balances.iter().map(|(k, v)| (k, v)))
+            .unwrap();
+
+        storage
+    }
+
+    #[test]
+    fn simulate_reports_balance_changes_and_gas() {
+        let storage = storage_with(&[(addr(1), 100), (addr(2), 5)]);
+        let tx = Transaction::new(addr(1), addr(2), Uint256::from(40), vec![0; 4]);
+
+        let result = simulate(&storage.snapshot().unwrap(), &tx, 1).unwrap();
+
+        assert!(result.success());
+        assert_eq!(result.gas_used, intrinsic_gas(&tx));
+        assert_eq!(
+            result.balance_changes,
+            vec![
+                BalanceChange {
+                    addr: addr(1),
+                    asset: None,
+                    before: Uint256::from(100),
+                    after: Uint256::from(60),
+                },
+                BalanceChange {
+                    addr: addr(2),
+                    asset: None,
+                    before: Uint256::from(5),
+                    after: Uint256::from(45),
+                },
+            ]
+        );
+
+        // nothing was persisted
+        let balance_db = storage.get_ref(TableId::Balance).to_balance();
+        assert_eq!(balance_db.get(&addr(1)).unwrap(), Some(Uint256::from(100)));
+    }
+
+    #[test]
+    fn simulate_reports_failure_reason() {
+        let storage = storage_with(&[(addr(1), 10)]);
+        let tx = Transaction::new(addr(1), addr(2), Uint256::from(40), vec![]);
+
+        let result = simulate(&storage.snapshot().unwrap(), &tx, 1).unwrap();
+
+        assert!(!result.success());
+        assert!(result.balance_changes.is_empty());
+        assert_eq!(
+            result.failure,
+            Some(VmError::InsufficientBalance {
+                required: Uint256::from(40),
+                available: Uint256::from(10),
+            })
+        );
+    }
+
+    #[test]
+    fn simulate_ignores_writes_after_snapshot() {
+        let storage = storage_with(&[(addr(1), 10)]);
+        let snapshot = storage.snapshot().unwrap();
+
+        storage
+            .get_ref(TableId::Balance)
+            .to_balance()
+            .insert(&addr(1), &Uint256::from(1000))
+            .unwrap();
+
+        let tx = Transaction::new(addr(1), addr(2), Uint256::from(40), vec![]);
+
+        assert!(!simulate(&snapshot, &tx, 1).unwrap().success());
+    }
+
+    #[test]
+    fn simulate_reports_asset_balance_changes() {
+        let storage = StorageManager::in_memory().unwrap();
+        let gold = AssetId::derive(&addr(1), "GOLD");
+
+        storage
+            .get_ref(TableId::Asset)
+            .to_asset()
+            .insert(
+                &gold,
+                &AssetMetadata {
+                    symbol: "GOLD".into(),
+                    decimals: 0,
+                    issuer: addr(1),
+                    total_supply: Uint256::from(100),
+                },
+            )
+            .unwrap();
+        storage
+            .get_ref(TableId::AssetBalance)
+            .to_asset_balance()
+            .insert(&(addr(1), gold), &Uint256::from(100))
+            .unwrap();
+
+        let tx = Transaction::asset(
+            addr(1),
+            AssetOp::Transfer {
+                asset: gold,
+                to: addr(2),
+                amount: Uint256::from(30),
+            },
+        );
+
+        let result = simulate(&storage.snapshot().unwrap(), &tx, 1).unwrap();
+
+        assert!(result.success());
+        assert_eq!(result.logs.len(), 1);
+        assert_eq!(
+            result.balance_changes,
+            vec![
+                BalanceChange {
+                    addr: addr(1),
+                    asset: Some(gold),
+                    before: Uint256::from(100),
+                    after: Uint256::from(70),
+                },
+                BalanceChange {
+                    addr: addr(2),
+                    asset: Some(gold),
+                    before: Uint256::from(0),
+                    after: Uint256::from(30),
+                },
+            ]
+        );
+    }
+}
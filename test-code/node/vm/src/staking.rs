@@ -0,0 +1,351 @@
+use std::collections::{HashMap, hash_map::Entry};
+
+use config::get_config;
+use rm_reth_types::{
+    Address,
+    bytes::FixedBytes,
+    hash::Hash,
+    log::Log,
+    multisig::PublicKey,
+    staking::{Unbonding, ValidatorRecord, key_id, possession_message},
+    tx::{kind::TxKind, transaction::Transaction},
+};
+use storage::{Snapshot, error::StorageError};
+
+use crate::{error::VmError, execute::BalanceState};
+
+pub fn staked_topic() -> Hash {
+    Hash::hash(b"Staked(address,uint256)")
+}
+
+pub fn unstaked_topic() -> Hash {
+    Hash::hash(b"Unstaked(address,uint256,uint64)")
+}
+
+pub fn stake_withdrawn_topic() -> Hash {
+    Hash::hash(b"StakeWithdrawn(address,uint256)")
+}
+
+/// Registry entries of the validators a set of transactions stakes with.
+#[derive(Debug, Clone, Default)]
+pub struct StakingLedger {
+    pub validators: HashMap<Address, ValidatorRecord>,
+    /// Validator registered with each staking key the transactions use.
+    pub key_owners: HashMap<Hash, Address>,
+    /// Blocks between unstaking and being able to withdraw.
+    pub unbonding_period: u64,
+}
+
+impl StakingLedger {
+    /// Loads the registry entry of every sender of a staking transaction of `txs`.
+    pub fn load(snapshot: &Snapshot<'_>, txs: &[Transaction]) -> Result<Self, StorageError> {
+        let mut ledger = Self {
+            validators: HashMap::new(),
+            key_owners: HashMap::new(),
+            unbonding_period: get_config().staking.unbonding_period,
+        };
+
+        for tx in txs {
+            match tx.kind() {
+                Ok(TxKind::Stake { public_key, .. }) => {
+                    if let Entry::Vacant(entry) = ledger.key_owners.entry(key_id(&public_key))
+                        && let Some(owner) = snapshot.validator_by_key(&public_key)?
+                    {
+                        entry.insert(owner);
+                    }
+                }
+                Ok(TxKind::Unstake | TxKind::WithdrawStake) => {}
+                _ => continue,
+            }
+
+            if let Entry::Vacant(entry) = ledger.validators.entry(tx.from)
+                && let Some(record) = snapshot.validator(&tx.from)?
+            {
+                entry.insert(record);
+            }
+        }
+
+        Ok(ledger)
+    }
+
+    /// Bonds `tx.amount` of the sender's balance, registering it on its first stake.
+    ///
+    /// `proof` must sign [`possession_message`] of the sender with `public_key`, and
+    /// the key must not be registered to another validator.
+    pub fn stake<S: BalanceState>(
+        &mut self,
+        state: &mut S,
+        tx: &Transaction,
+        public_key: PublicKey,
+        proof: &FixedBytes<64>,
+    ) -> Result<Vec<Log>, VmError> {
+        if tx.amount.is_zero() || public_key.verifying_key().is_none() {
+            return Err(VmError::InvalidStake);
+        }
+
+        if let Some(record) = self.validators.get(&tx.from)
+            && record.public_key != public_key
+        {
+            return Err(VmError::StakeKeyMismatch(tx.from));
+        }
+
+        if !public_key.verify(&possession_message(&tx.from), proof) {
+            return Err(VmError::InvalidKeyProof);
+        }
+
+        let id = key_id(&public_key);
+        if let Some(owner) = self.key_owners.get(&id)
+            && *owner != tx.from
+        {
+            return Err(VmError::StakeKeyTaken(*owner));
+        }
+
+        let available = state.balance(&tx.from);
+        let balance = available.clone().checked_sub(tx.amount.clone()).ok_or(
+            VmError::InsufficientBalance {
+                required: tx.amount.clone(),
+                available,
+            },
+        )?;
+
+        let record = self
+            .validators
+            .entry(tx.from)
+            .or_insert_with(|| ValidatorRecord::new(tx.from, public_key));
+
+        record.stake = record
+            .stake
+            .clone()
+            .checked_add(tx.amount.clone())
+            .ok_or(VmError::BalanceOverflow(tx.from))?;
+        state.set_balance(tx.from, balance);
+        self.key_owners.insert(id, tx.from);
+
+        Ok(vec![Log {
+            address: tx.from,
+            topics: vec![staked_topic(), Log::address_topic(&tx.from)],
+            data: tx.amount.to_le_bytes().to_vec(),
+        }])
+    }
+
+    /// Moves `tx.amount` of the stake of the sender to unbonding, withdrawable once
+    /// the unbonding period passed.
+    pub fn unstake(&mut self, tx: &Transaction, height: u64) -> Result<Vec<Log>, VmError> {
+        if tx.amount.is_zero() {
+            return Err(VmError::InvalidStake);
+        }
+
+        let record = self
+            .validators
+            .get_mut(&tx.from)
+            .ok_or(VmError::NotAValidator(tx.from))?;
+
+        record.stake = record.stake.clone().checked_sub(tx.amount.clone()).ok_or(
+            VmError::InsufficientStake {
+                required: tx.amount.clone(),
+                staked: record.stake.clone(),
+            },
+        )?;
+
+        let release_height = height.saturating_add(self.unbonding_period);
+        record.unbonding.push(Unbonding {
+            amount: tx.amount.clone(),
+            release_height,
+        });
+
+        let mut data = tx.amount.to_le_bytes().to_vec();
+        data.extend(release_height.to_le_bytes());
+
+        Ok(vec![Log {
+            address: tx.from,
+            topics: vec![unstaked_topic(), Log::address_topic(&tx.from)],
+            data,
+        }])
+    }
+
+    /// Pays back every unbonding entry of the sender released at `height`.
+    pub fn withdraw<S: BalanceState>(
+        &mut self,
+        state: &mut S,
+        tx: &Transaction,
+        height: u64,
+    ) -> Result<Vec<Log>, VmError> {
+        let record = self
+            .validators
+            .get_mut(&tx.from)
+            .ok_or(VmError::NotAValidator(tx.from))?;
+
+        let amount = record.withdrawable(height);
+
+        if amount.is_zero() {
+            return Err(VmError::NothingToWithdraw(tx.from));
+        }
+
+        let balance = state
+            .balance(&tx.from)
+            .checked_add(amount.clone())
+            .ok_or(VmError::BalanceOverflow(tx.from))?;
+
+        record
+            .unbonding
+            .retain(|entry| entry.release_height > height);
+        state.set_balance(tx.from, balance);
+
+        Ok(vec![Log {
+            address: tx.from,
+            topics: vec![stake_withdrawn_topic(), Log::address_topic(&tx.from)],
+            data: amount.to_le_bytes().to_vec(),
+        }])
+    }
+
+    /// Loaded entries sorted by address, as stored in a block.
+    pub fn into_block_parts(self) -> Vec<ValidatorRecord> {
+        let mut validators: Vec<_> = self.validators.into_values().collect();
+        validators.sort_by_key(|record| record.address);
+
+        validators
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k256::ecdsa::SigningKey;
+    use rm_reth_types::{int::Uint256, staking::prove_possession};
+
+    fn addr(id: u8) -> Address {
+        [id; 20].into()
+    }
+
+    fn u(v: u64) -> Uint256 {
+        Uint256::from(v)
+    }
+
+    fn key(id: u8) -> SigningKey {
+        SigningKey::from_bytes(&[id; 32].into()).unwrap()
+    }
+
+    fn tx(amount: u64, kind: TxKind) -> Transaction {
+        Transaction::with_kind(addr(1), addr(1), u(amount), kind)
+    }
+
+    fn stake_as(
+        ledger: &mut StakingLedger,
+        tokens: &mut HashMap<Address, Uint256>,
+        from: Address,
+        amount: u64,
+        public_key: PublicKey,
+        proof: FixedBytes<64>,
+    ) -> Result<Vec<Log>, VmError> {
+        let tx = Transaction::with_kind(from, from, u(amount), TxKind::Stake { public_key, proof });
+        ledger.stake(tokens, &tx, public_key, &proof)
+    }
+
+    fn stake(
+        ledger: &mut StakingLedger,
+        tokens: &mut HashMap<Address, Uint256>,
+        amount: u64,
+        key: &SigningKey,
+    ) -> Result<Vec<Log>, VmError> {
+        let proof = prove_possession(key, &addr(1));
+        stake_as(
+            ledger,
+            tokens,
+            addr(1),
+            amount,
+            PublicKey::from_signing_key(key),
+            proof,
+        )
+    }
+
+    fn ledger() -> StakingLedger {
+        StakingLedger {
+            validators: HashMap::new(),
+            key_owners: HashMap::new(),
+            unbonding_period: 10,
+        }
+    }
+
+    #[test]
+    fn stake_bonds_balance_under_one_key() {
+        let mut tokens = HashMap::from([(addr(1), u(100))]);
+        let mut ledger = ledger();
+
+        stake(&mut ledger, &mut tokens, 60, &key(1)).unwrap();
+        assert_eq!(tokens[&addr(1)], u(40));
+        assert_eq!(ledger.validators[&addr(1)].stake, u(60));
+
+        assert_eq!(
+            stake(&mut ledger, &mut tokens, 10, &key(2)),
+            Err(VmError::StakeKeyMismatch(addr(1)))
+        );
+        assert!(matches!(
+            stake(&mut ledger, &mut tokens, 50, &key(1)),
+            Err(VmError::InsufficientBalance { .. })
+        ));
+        assert_eq!(
+            stake_as(
+                &mut ledger,
+                &mut tokens,
+                addr(1),
+                10,
+                PublicKey([0; 33].into()),
+                [0; 64].into()
+            ),
+            Err(VmError::InvalidStake)
+        );
+    }
+
+    #[test]
+    fn stake_key_needs_a_proof_and_a_single_owner() {
+        let mut tokens = HashMap::from([(addr(1), u(100)), (addr(2), u(100))]);
+        let mut ledger = ledger();
+        let public_key = PublicKey::from_signing_key(&key(1));
+
+        // a proof over another address does not register the key
+        let proof = prove_possession(&key(1), &addr(1));
+        assert_eq!(
+            stake_as(&mut ledger, &mut tokens, addr(2), 10, public_key, proof),
+            Err(VmError::InvalidKeyProof)
+        );
+
+        stake(&mut ledger, &mut tokens, 10, &key(1)).unwrap();
+
+        // the key holder cannot register it a second time under another address
+        let proof = prove_possession(&key(1), &addr(2));
+        assert_eq!(
+            stake_as(&mut ledger, &mut tokens, addr(2), 10, public_key, proof),
+            Err(VmError::StakeKeyTaken(addr(1)))
+        );
+        assert!(!ledger.validators.contains_key(&addr(2)));
+        assert_eq!(tokens[&addr(2)], u(100));
+    }
+
+    #[test]
+    fn unstaked_tokens_wait_for_unbonding_period() {
+        let mut tokens = HashMap::from([(addr(1), u(100))]);
+        let mut ledger = ledger();
+
+        stake(&mut ledger, &mut tokens, 100, &key(1)).unwrap();
+        ledger.unstake(&tx(30, TxKind::Unstake), 5).unwrap();
+
+        assert_eq!(
+            ledger.unstake(&tx(80, TxKind::Unstake), 5),
+            Err(VmError::InsufficientStake {
+                required: u(80),
+                staked: u(70),
+            })
+        );
+        assert_eq!(
+            ledger.withdraw(&mut tokens, &tx(0, TxKind::WithdrawStake), 14),
+            Err(VmError::NothingToWithdraw(addr(1)))
+        );
+
+        ledger
+            .withdraw(&mut tokens, &tx(0, TxKind::WithdrawStake), 15)
+            .unwrap();
+        assert_eq!(tokens[&addr(1)], u(30));
+        assert!(ledger.validators[&addr(1)].unbonding.is_empty());
+        assert_eq!(ledger.validators[&addr(1)].stake, u(70));
+    }
+}
@@ -0,0 +1,434 @@
+use std::collections::{HashMap, hash_map::Entry};
+
+use rm_reth_types::{
+    Address,
+    hash::Hash,
+    int::Uint256,
+    log::Log,
+    tx::{kind::TxKind, transaction::Transaction},
+    vesting::{VestingOffer, VestingOfferStatus, VestingSchedule},
+};
+use storage::{Snapshot, error::StorageError};
+
+use crate::{
+    error::VmError,
+    execute::{BalanceState, transfer_log},
+};
+
+pub fn vesting_offered_topic() -> Hash {
+    Hash::hash(b"VestingOffered(bytes32,address,address,uint256)")
+}
+
+pub fn vesting_granted_topic() -> Hash {
+    Hash::hash(b"VestingGranted(address,address,uint64,uint64,uint64)")
+}
+
+pub fn vesting_cancelled_topic() -> Hash {
+    Hash::hash(b"VestingCancelled(bytes32)")
+}
+
+/// Vesting schedules of the accounts a set of transactions spends from, and the
+/// vesting offers they make, accept or cancel.
+#[derive(Debug, Clone, Default)]
+pub struct VestingLedger {
+    pub schedules: HashMap<Address, VestingSchedule>,
+    pub offers: HashMap<Hash, VestingOffer>,
+}
+
+impl VestingLedger {
+    /// Loads the schedule of every sender of `txs` and every offer they may read.
+    ///
+    /// Only beneficiaries accept offers, so the senders cover every schedule an
+    /// accepted offer may replace.
+    pub fn load(snapshot: &Snapshot<'_>, txs: &[Transaction]) -> Result<Self, StorageError> {
+        let mut ledger = Self::default();
+
+        for tx in txs {
+            if let Entry::Vacant(entry) = ledger.schedules.entry(tx.from)
+                && let Some(schedule) = snapshot.vesting(&tx.from)?
+            {
+                entry.insert(schedule);
+            }
+
+            let id = match tx.kind() {
+                Ok(TxKind::VestingGrant { .. }) => tx.hash(),
+                Ok(TxKind::VestingAccept { id } | TxKind::VestingCancel { id }) => id,
+                _ => continue,
+            };
+
+            if let Entry::Vacant(entry) = ledger.offers.entry(id)
+                && let Some(offer) = snapshot.vesting_offer(&id)?
+            {
+                entry.insert(offer);
+            }
+        }
+
+        Ok(ledger)
+    }
+
+    /// Part of the native balance of `addr` that is not vested yet at `height`.
+    pub fn locked(&self, addr: &Address, height: u64) -> Uint256 {
+        self.schedules
+            .get(addr)
+            .map(|schedule| schedule.locked(height))
+            .unwrap_or_default()
+    }
+
+    /// Rejects taking `amount` from `addr` when it would dip into the locked part of
+    /// its balance. A plain lack of funds is left to the transaction handler.
+    pub fn check_spend<S: BalanceState>(
+        &self,
+        state: &mut S,
+        addr: &Address,
+        amount: &Uint256,
+        height: u64,
+    ) -> Result<(), VmError> {
+        let locked = self.locked(addr, height);
+
+        if locked.is_zero() || amount.is_zero() {
+            return Ok(());
+        }
+
+        let available = state.balance(addr);
+        let spendable = available.clone().saturating_sub(locked.clone());
+
+        if *amount <= available && *amount > spendable {
+            return Err(VmError::VestingLocked {
+                required: amount.clone(),
+                spendable,
+                locked,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Moves `tx.amount` from the sender into an offer to `beneficiary`, identified
+    /// by `tx.hash()`. The schedule only applies once the beneficiary accepts it.
+    pub fn grant<S: BalanceState>(
+        &mut self,
+        state: &mut S,
+        tx: &Transaction,
+        beneficiary: Address,
+        start: u64,
+        cliff: u64,
+        duration: u64,
+    ) -> Result<Vec<Log>, VmError> {
+        let id = tx.hash();
+
+        if duration == 0 || cliff > duration || tx.amount.is_zero() {
+            return Err(VmError::InvalidVestingSchedule);
+        }
+
+        if self.offers.contains_key(&id) {
+            return Err(VmError::VestingOfferExists(id));
+        }
+
+        let available = state.balance(&tx.from);
+        let from_balance = available.clone().checked_sub(tx.amount.clone()).ok_or(
+            VmError::InsufficientBalance {
+                required: tx.amount.clone(),
+                available,
+            },
+        )?;
+
+        state.set_balance(tx.from, from_balance);
+
+        self.offers.insert(
+            id,
+            VestingOffer {
+                id,
+                schedule: VestingSchedule {
+                    beneficiary,
+                    grantor: tx.from,
+                    start,
+                    cliff,
+                    duration,
+                    total: tx.amount.clone(),
+                },
+                status: VestingOfferStatus::Pending,
+            },
+        );
+
+        Ok(vec![Log {
+            address: tx.from,
+            topics: vec![
+                vesting_offered_topic(),
+                id,
+                Log::address_topic(&tx.from),
+                Log::address_topic(&beneficiary),
+            ],
+            data: tx.amount.to_le_bytes().to_vec(),
+        }])
+    }
+
+    /// Pays the pending offer `id` to its beneficiary `sender` and makes its
+    /// schedule the one of `sender`.
+    ///
+    /// A previous schedule of `sender` is only replaced once it is fully vested
+    /// at `height`.
+    pub fn accept<S: BalanceState>(
+        &mut self,
+        state: &mut S,
+        sender: Address,
+        id: Hash,
+        height: u64,
+    ) -> Result<Vec<Log>, VmError> {
+        let schedule = self.pending(id)?.schedule.clone();
+
+        if schedule.beneficiary != sender {
+            return Err(VmError::NotVestingBeneficiary(sender));
+        }
+
+        if !self.locked(&sender, height).is_zero() {
+            return Err(VmError::VestingExists(sender));
+        }
+
+        let to_balance = state
+            .balance(&sender)
+            .checked_add(schedule.total.clone())
+            .ok_or(VmError::BalanceOverflow(sender))?;
+
+        state.set_balance(sender, to_balance);
+
+        let mut data = schedule.start.to_le_bytes().to_vec();
+        data.extend(schedule.cliff.to_le_bytes());
+        data.extend(schedule.duration.to_le_bytes());
+
+        let logs = vec![
+            transfer_log(sender, &schedule.grantor, &sender, &schedule.total),
+            Log {
+                address: sender,
+                topics: vec![
+                    vesting_granted_topic(),
+                    Log::address_topic(&schedule.grantor),
+                    Log::address_topic(&sender),
+                ],
+                data,
+            },
+        ];
+
+        self.offers.get_mut(&id).unwrap().status = VestingOfferStatus::Accepted;
+        self.schedules.insert(sender, schedule);
+
+        Ok(logs)
+    }
+
+    /// Returns the pending offer `id` to its grantor `sender`.
+    pub fn cancel<S: BalanceState>(
+        &mut self,
+        state: &mut S,
+        sender: Address,
+        id: Hash,
+    ) -> Result<Vec<Log>, VmError> {
+        let offer = self.pending(id)?;
+
+        if offer.schedule.grantor != sender {
+            return Err(VmError::NotVestingGrantor(sender));
+        }
+
+        let to_balance = state
+            .balance(&sender)
+            .checked_add(offer.schedule.total.clone())
+            .ok_or(VmError::BalanceOverflow(sender))?;
+
+        state.set_balance(sender, to_balance);
+        self.offers.get_mut(&id).unwrap().status = VestingOfferStatus::Cancelled;
+
+        Ok(vec![Log {
+            address: sender,
+            topics: vec![vesting_cancelled_topic(), id],
+            data: vec![],
+        }])
+    }
+
+    /// Loaded schedules sorted by beneficiary and touched offers sorted by id, as
+    /// stored in a block.
+    pub fn into_block_parts(self) -> (Vec<VestingSchedule>, Vec<VestingOffer>) {
+        let mut schedules: Vec<_> = self.schedules.into_values().collect();
+        schedules.sort_by_key(|schedule| schedule.beneficiary);
+
+        let mut offers: Vec<_> = self.offers.into_values().collect();
+        offers.sort_by(|a, b| a.id.as_slice().cmp(b.id.as_slice()));
+
+        (schedules, offers)
+    }
+
+    fn pending(&self, id: Hash) -> Result<&VestingOffer, VmError> {
+        let offer = self
+            .offers
+            .get(&id)
+            .ok_or(VmError::UnknownVestingOffer(id))?;
+
+        if !offer.is_pending() {
+            return Err(VmError::VestingOfferNotPending(id));
+        }
+
+        Ok(offer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addr(id: u8) -> Address {
+        [id; 20].into()
+    }
+
+    fn u(v: u64) -> Uint256 {
+        Uint256::from(v)
+    }
+
+    fn grant_tx(amount: u64) -> Transaction {
+        Transaction::with_kind(
+            addr(1),
+            addr(1),
+            u(amount),
+            TxKind::VestingGrant {
+                beneficiary: addr(2),
+                start: 10,
+                cliff: 5,
+                duration: 20,
+            },
+        )
+    }
+
+    fn offered(tokens: &mut HashMap<Address, Uint256>, amount: u64) -> (VestingLedger, Hash) {
+        let mut ledger = VestingLedger::default();
+        let tx = grant_tx(amount);
+
+        ledger.grant(tokens, &tx, addr(2), 10, 5, 20).unwrap();
+
+        (ledger, tx.hash())
+    }
+
+    fn granted(tokens: &mut HashMap<Address, Uint256>) -> VestingLedger {
+        let (mut ledger, id) = offered(tokens, 100);
+
+        ledger.accept(tokens, addr(2), id, 0).unwrap();
+
+        ledger
+    }
+
+    #[test]
+    fn grant_holds_total_until_accepted() {
+        let mut tokens = HashMap::from([(addr(1), u(150))]);
+        let (mut ledger, id) = offered(&mut tokens, 100);
+
+        assert_eq!(tokens[&addr(1)], u(50));
+        assert_eq!(tokens.get(&addr(2)), None);
+        assert_eq!(ledger.locked(&addr(2), 0), u(0));
+
+        assert_eq!(
+            ledger.accept(&mut tokens, addr(3), id, 0),
+            Err(VmError::NotVestingBeneficiary(addr(3)))
+        );
+        ledger.accept(&mut tokens, addr(2), id, 0).unwrap();
+
+        assert_eq!(tokens[&addr(2)], u(100));
+        assert_eq!(ledger.locked(&addr(2), 0), u(100));
+        assert_eq!(ledger.locked(&addr(2), 20), u(50));
+        assert_eq!(ledger.locked(&addr(2), 30), u(0));
+        assert_eq!(
+            ledger.accept(&mut tokens, addr(2), id, 0),
+            Err(VmError::VestingOfferNotPending(id))
+        );
+    }
+
+    #[test]
+    fn grantor_cancels_pending_offer() {
+        let mut tokens = HashMap::from([(addr(1), u(150))]);
+        let (mut ledger, id) = offered(&mut tokens, 100);
+
+        assert_eq!(
+            ledger.cancel(&mut tokens, addr(2), id),
+            Err(VmError::NotVestingGrantor(addr(2)))
+        );
+        ledger.cancel(&mut tokens, addr(1), id).unwrap();
+
+        assert_eq!(tokens[&addr(1)], u(150));
+        assert_eq!(
+            ledger.accept(&mut tokens, addr(2), id, 0),
+            Err(VmError::VestingOfferNotPending(id))
+        );
+        assert_eq!(
+            ledger.cancel(&mut tokens, addr(1), Hash::empty()),
+            Err(VmError::UnknownVestingOffer(Hash::empty()))
+        );
+    }
+
+    #[test]
+    fn grant_rejects_invalid_schedules() {
+        let mut tokens = HashMap::from([(addr(1), u(500))]);
+        let mut ledger = VestingLedger::default();
+
+        assert_eq!(
+            ledger.grant(&mut tokens, &grant_tx(100), addr(3), 0, 21, 20),
+            Err(VmError::InvalidVestingSchedule)
+        );
+        assert_eq!(
+            ledger.grant(&mut tokens, &grant_tx(100), addr(3), 0, 0, 0),
+            Err(VmError::InvalidVestingSchedule)
+        );
+        assert_eq!(
+            ledger.grant(&mut tokens, &grant_tx(0), addr(3), 0, 0, 10),
+            Err(VmError::InvalidVestingSchedule)
+        );
+        assert_eq!(tokens[&addr(1)], u(500));
+    }
+
+    #[test]
+    fn accept_replaces_only_fully_vested_schedules() {
+        let mut tokens = HashMap::from([(addr(1), u(500))]);
+        let mut ledger = granted(&mut tokens);
+
+        let tx = grant_tx(200);
+        ledger.grant(&mut tokens, &tx, addr(2), 40, 0, 10).unwrap();
+
+        // the first schedule vests until height 30
+        assert_eq!(
+            ledger.accept(&mut tokens, addr(2), tx.hash(), 29),
+            Err(VmError::VestingExists(addr(2)))
+        );
+        ledger.accept(&mut tokens, addr(2), tx.hash(), 30).unwrap();
+
+        assert_eq!(tokens[&addr(2)], u(300));
+        assert_eq!(ledger.locked(&addr(2), 30), u(200));
+        assert_eq!(ledger.schedules[&addr(2)].total, u(200));
+    }
+
+    #[test]
+    fn only_vested_part_is_spendable() {
+        let mut tokens = HashMap::from([(addr(1), u(100))]);
+        let ledger = granted(&mut tokens);
+
+        // 25 vested at height 15, nothing before the cliff
+        assert_eq!(
+            ledger.check_spend(&mut tokens, &addr(2), &u(1), 14),
+            Err(VmError::VestingLocked {
+                required: u(1),
+                spendable: u(0),
+                locked: u(100),
+            })
+        );
+        assert!(
+            ledger
+                .check_spend(&mut tokens, &addr(2), &u(25), 15)
+                .is_ok()
+        );
+        assert!(
+            ledger
+                .check_spend(&mut tokens, &addr(2), &u(26), 15)
+                .is_err()
+        );
+
+        // lack of funds is reported by the handler, not as a vesting error
+        assert!(
+            ledger
+                .check_spend(&mut tokens, &addr(2), &u(101), 15)
+                .is_ok()
+        );
+        assert!(ledger.check_spend(&mut tokens, &addr(3), &u(1), 0).is_ok());
+    }
+}